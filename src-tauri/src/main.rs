@@ -1,13 +1,17 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::collections::HashMap;
-use std::sync::Mutex;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Manager, State, Window};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
 // Window configuration and state structures
+// Fields missing from saved JSON (older layouts, hand-edited files) take WindowConfig::default()'s value
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct WindowConfig {
     pub window_type: String,
     pub title: String,
@@ -20,832 +24,9648 @@ pub struct WindowConfig {
     pub maximizable: bool,
     pub closable: bool,
     pub always_on_top: bool,
+    #[serde(default = "default_true")]
     pub decorations: bool,
     pub transparent: bool,
     pub focus: bool,
     pub fullscreen: bool,
     pub url: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WindowState {
-    pub label: String,
-    pub config: WindowConfig,
-    pub z_order: u32,
-    pub is_focused: bool,
-    pub is_minimized: bool,
-    pub is_maximized: bool,
+    // Size limits in logical pixels; either bound may be left open
+    pub min_width: Option<f64>,
+    pub min_height: Option<f64>,
+    pub max_width: Option<f64>,
+    pub max_height: Option<f64>,
+    #[serde(default)]
+    pub placement: WindowPlacement,
+    // Skip pulling the window back onto a monitor; for deliberate off-screen placement
+    #[serde(default)]
+    pub allow_offscreen: bool,
+    #[serde(default)]
+    pub on_type_limit: TypeLimitBehavior,
+    // "light", "dark" or "system"; None follows the OS like "system" does
+    #[serde(default)]
+    pub theme: Option<String>,
+    #[serde(default)]
+    pub skip_taskbar: bool,
+    // False for utility windows (toasts, drag previews) that cycling and the MRU list skip
+    #[serde(default = "default_true")]
+    pub switcher_visible: bool,
+    // False builds the window hidden until reveal_window, so it doesn't flash white while loading
+    #[serde(default = "default_true")]
+    pub visible: bool,
+    // Build hidden and reveal as soon as the frontend calls window_ready
+    #[serde(default)]
+    pub create_and_reveal_when_ready: bool,
+    // A modal window disables its parent until it closes
+    #[serde(default)]
+    pub modal: bool,
+    #[serde(default)]
+    pub parent_label: Option<String>,
+    // Initial content zoom, clamped to MIN_ZOOM..=MAX_ZOOM
+    #[serde(default)]
+    pub zoom: Option<f64>,
+    // "#RRGGBB" or "#RRGGBBAA", painted before the page loads so dark windows don't flash white
+    #[serde(default)]
+    pub background_color: Option<String>,
+    // Whole-window alpha, clamped to MIN_OPACITY..=1.0 when applied
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    // With Absolute placement, x/y are relative to this monitor's origin (centered when
+    // left out); a monitor that isn't connected falls back to the primary one
     pub monitor_id: Option<String>,
-    pub created_at: u64,
-    pub last_focused_at: u64,
+    // False for splash screens and drag ghosts: the window exists but stays out of lists,
+    // focus history, cycling and saved layouts until adopt_window
+    #[serde(default = "default_true")]
+    pub managed: bool,
+    // Place, center and clamp against the whole monitor instead of its work area, i.e.
+    // allow covering the taskbar or dock
+    #[serde(default)]
+    pub use_full_area: bool,
+    // Whether width/height are logical pixels or percentages of the target monitor's
+    // usable area
+    #[serde(default)]
+    pub size_mode: SizeMode,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MonitorInfo {
-    pub id: String,
-    pub name: String,
-    pub width: u32,
-    pub height: u32,
-    pub x: i32,
-    pub y: i32,
-    pub scale_factor: f64,
-    pub is_primary: bool,
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SizeMode {
+    #[default]
+    Pixels,
+    // 0-100, resolved once against the monitor the window opens on
+    Percent,
 }
 
-// Window registry for state tracking
-pub struct WindowRegistry {
-    windows: HashMap<String, WindowState>,
-    z_order_counter: u32,
-    focused_window: Option<String>,
+// What create_app_window does when the window type is already at its limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum TypeLimitBehavior {
+    #[default]
+    Reject,
+    // Bring the oldest window of the type forward and return its label instead
+    FocusOldest,
 }
 
-impl WindowRegistry {
-    pub fn new() -> Self {
-        Self {
-            windows: HashMap::new(),
-            z_order_counter: 0,
-            focused_window: None,
-        }
-    }
-
-    pub fn add_window(&mut self, label: String, config: WindowConfig) {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+// Where create_app_window puts a new window; centered placements ignore x/y
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum WindowPlacement {
+    #[default]
+    Absolute,
+    CenterPrimary,
+    CenterOnFocusedMonitor,
+    CenterOnMonitor(String),
+    // Wherever the mouse is, for context-menu style windows
+    CenterOnCursorMonitor,
+    // Each new window of a type steps down and right from the previous one
+    Cascade,
+    // Centered on the focused monitor, shifted diagonally off any open window of the same
+    // type that already sits there
+    SmartAvoidOverlap,
+}
 
-        self.z_order_counter += 1;
+// Where the last cascaded window of a type was spawned, in physical pixels
+#[derive(Debug, Clone, PartialEq)]
+struct CascadeSpawn {
+    monitor_id: String,
+    x: i32,
+    y: i32,
+}
 
-        let window_state = WindowState {
-            label: label.clone(),
-            config,
-            z_order: self.z_order_counter,
-            is_focused: true,
-            is_minimized: false,
-            is_maximized: false,
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            window_type: "unknown".to_string(),
+            title: String::new(),
+            width: 800.0,
+            height: 600.0,
+            x: None,
+            y: None,
+            resizable: true,
+            minimizable: true,
+            maximizable: true,
+            closable: true,
+            always_on_top: false,
+            decorations: true,
+            transparent: false,
+            focus: true,
+            fullscreen: false,
+            url: None,
+            min_width: None,
+            min_height: None,
+            max_width: None,
+            max_height: None,
+            placement: WindowPlacement::Absolute,
+            allow_offscreen: false,
+            on_type_limit: TypeLimitBehavior::Reject,
+            theme: None,
+            skip_taskbar: false,
+            switcher_visible: true,
+            visible: true,
+            create_and_reveal_when_ready: false,
+            modal: false,
+            parent_label: None,
+            zoom: None,
+            background_color: None,
+            opacity: 1.0,
             monitor_id: None,
-            created_at: now,
-            last_focused_at: now,
-        };
-
-        // Update focus
-        if let Some(prev_focused) = &self.focused_window {
-            if let Some(prev_window) = self.windows.get_mut(prev_focused) {
-                prev_window.is_focused = false;
-            }
+            managed: true,
+            use_full_area: false,
+            size_mode: SizeMode::Pixels,
         }
-        self.focused_window = Some(label.clone());
-
-        self.windows.insert(label, window_state);
     }
+}
 
-    pub fn remove_window(&mut self, label: &str) {
-        self.windows.remove(label);
-        if self.focused_window.as_ref() == Some(&label.to_string()) {
-            self.focused_window = None;
-            // Focus the most recently created window
-            if let Some((_, most_recent)) = self.windows.iter_mut()
-                .max_by_key(|(_, state)| state.last_focused_at) {
-                most_recent.is_focused = true;
-                self.focused_window = Some(most_recent.label.clone());
-            }
+impl WindowConfig {
+    // A config that says nothing about position (Absolute with no x/y or monitor) takes the
+    // registry's default placement
+    pub fn placement_or(&self, default: &WindowPlacement) -> WindowPlacement {
+        let unpositioned = self.x.is_none() && self.y.is_none() && self.monitor_id.is_none();
+        if self.placement == WindowPlacement::Absolute && unpositioned {
+            default.clone()
+        } else {
+            self.placement.clone()
         }
     }
 
-    pub fn focus_window(&mut self, label: &str) {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
-
-        // Unfocus previously focused window
-        if let Some(prev_focused) = &self.focused_window {
-            if let Some(prev_window) = self.windows.get_mut(prev_focused) {
-                prev_window.is_focused = false;
-            }
-        }
+    // Best-effort config for a window we didn't create ourselves; labels we
+    // generate look like "<window_type>_<timestamp>"
+    pub fn synthesized(label: &str) -> Self {
+        let window_type = match label.rsplit_once('_') {
+            Some((prefix, suffix)) if !prefix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()) => prefix,
+            _ => label,
+        };
 
-        // Focus new window
-        if let Some(window) = self.windows.get_mut(label) {
-            window.is_focused = true;
-            window.last_focused_at = now;
-            self.z_order_counter += 1;
-            window.z_order = self.z_order_counter;
-            self.focused_window = Some(label.to_string());
+        Self {
+            window_type: window_type.to_string(),
+            title: label.to_string(),
+            ..Self::default()
         }
     }
 
-    pub fn get_windows_by_z_order(&self) -> Vec<&WindowState> {
-        let mut windows: Vec<&WindowState> = self.windows.values().collect();
-        windows.sort_by(|a, b| b.z_order.cmp(&a.z_order));
-        windows
+    pub fn starts_hidden(&self) -> bool {
+        !self.visible || self.reveals_when_ready()
     }
 
-    pub fn get_window(&self, label: &str) -> Option<&WindowState> {
-        self.windows.get(label)
+    // A background the webview can't paint is faked by staying hidden until first paint
+    pub fn reveals_when_ready(&self) -> bool {
+        self.create_and_reveal_when_ready || (self.background_color.is_some() && !WEBVIEW_BACKGROUND_SUPPORTED)
     }
 
-    pub fn get_focused_window(&self) -> Option<&WindowState> {
-        if let Some(focused_label) = &self.focused_window {
-            self.windows.get(focused_label)
-        } else {
-            None
-        }
-    }
+    // Collects every problem at once so the frontend can highlight all offending fields
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        let mut fail = |field: &str, message: &str| errors.push(ConfigError {
+            field: field.to_string(),
+            message: message.to_string(),
+        });
 
-    pub fn update_window_state(&mut self, label: &str, is_minimized: Option<bool>, is_maximized: Option<bool>, monitor_id: Option<String>) {
-        if let Some(window) = self.windows.get_mut(label) {
-            if let Some(minimized) = is_minimized {
-                window.is_minimized = minimized;
+        for (field, value) in [("width", self.width), ("height", self.height)] {
+            if !value.is_finite() || value <= 0.0 {
+                fail(field, "must be a positive number");
+            } else if self.size_mode == SizeMode::Percent {
+                if value > 100.0 {
+                    fail(field, "must be at most 100 percent");
+                }
+            } else if value > MAX_WINDOW_DIMENSION {
+                fail(field, &format!("must be at most {}", MAX_WINDOW_DIMENSION));
             }
-            if let Some(maximized) = is_maximized {
-                window.is_maximized = maximized;
+        }
+        for (field, value) in [("x", self.x), ("y", self.y)] {
+            if value.is_some_and(|v| !v.is_finite()) {
+                fail(field, "must be a finite number");
             }
-            if let Some(monitor) = monitor_id {
-                window.monitor_id = Some(monitor);
+        }
+        for (field, value) in [
+            ("min_width", self.min_width),
+            ("min_height", self.min_height),
+            ("max_width", self.max_width),
+            ("max_height", self.max_height),
+        ] {
+            if value.is_some_and(|v| !v.is_finite() || v < 0.0) {
+                fail(field, "must be a non-negative number");
             }
         }
-    }
-}
-
-type WindowRegistryState = Mutex<WindowRegistry>;
-
-// Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
-}
-
-// Advanced window creation and management APIs
-#[tauri::command]
-async fn create_app_window(
-    app: AppHandle,
-    window_type: String,
-    config: WindowConfig,
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<String, String> {
-    let label = format!("{}_{}", window_type, std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis());
-
-    let url = config.url.clone().unwrap_or_else(|| "index.html".to_string());
+        if matches!((self.min_width, self.max_width), (Some(min), Some(max)) if min > max) {
+            fail("min_width", "must not exceed max_width");
+        }
+        if matches!((self.min_height, self.max_height), (Some(min), Some(max)) if min > max) {
+            fail("min_height", "must not exceed max_height");
+        }
 
-    let mut builder = tauri::webview::WebviewWindowBuilder::new(&app, &label, tauri::WebviewUrl::App(url.into()))
-        .title(&config.title)
-        .inner_size(config.width, config.height)
-        .resizable(config.resizable)
-        .minimizable(config.minimizable)
-        .maximizable(config.maximizable)
-        .closable(config.closable)
-        .always_on_top(config.always_on_top)
-        .decorations(config.decorations)
-        .transparent(config.transparent)
-        .focus(config.focus)
-        .fullscreen(config.fullscreen);
+        if let Some(url) = &self.url {
+            match url_scheme(url) {
+                Some(scheme) if !ALLOWED_URL_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) => {
+                    fail("url", &format!("scheme '{}' is not allowed", scheme));
+                }
+                Some(_) => {}
+                None if url.trim().is_empty() => fail("url", "must not be empty"),
+                None if escapes_app(url) => {
+                    fail("url", "must stay inside the app");
+                }
+                None => {}
+            }
+        }
 
-    if let Some(x) = config.x {
-        if let Some(y) = config.y {
-            builder = builder.position(x, y);
+        if self.zoom.is_some_and(|zoom| !zoom.is_finite()) {
+            fail("zoom", "must be a finite number");
+        }
+        if !self.opacity.is_finite() {
+            fail("opacity", "must be a finite number");
+        }
+        if self.modal && self.parent_label.is_none() {
+            fail("parent_label", "is required for modal windows");
+        }
+        if self.theme.as_deref().is_some_and(|theme| parse_theme(theme).is_err()) {
+            fail("theme", "must be \"light\", \"dark\" or \"system\"");
+        }
+        if self.background_color.as_deref().is_some_and(|color| parse_color(color).is_err()) {
+            fail("background_color", "must be a hex color like #1e1e1e or #1e1e1eff");
         }
-    }
 
-    let window = builder.build().map_err(|e| e.to_string())?;
+        // Windows draws an opaque frame behind decorated transparent windows, and macOS
+        // leaves the title bar visible
+        if self.transparent && self.decorations {
+            fail("transparent", "requires decorations to be disabled");
+        }
 
-    // Add to registry
-    {
-        let mut registry = registry_state.lock().unwrap();
-        registry.add_window(label.clone(), config);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
 
-    // Set up window event listeners
-    let registry_clone = registry_state.clone();
-    let label_clone = label.clone();
-    window.on_window_event(move |event| {
-        match event {
-            tauri::WindowEvent::Focused(focused) => {
-                let mut registry = registry_clone.lock().unwrap();
-                if *focused {
-                    registry.focus_window(&label_clone);
-                }
+    // Logical width/height on `monitor`; percentages are of its work area (or whole area
+    // with use_full_area) and fall back to the default size when no monitor is known
+    pub fn resolve_size(&self, monitor: Option<&MonitorInfo>) -> (f64, f64) {
+        match (self.size_mode, monitor) {
+            (SizeMode::Pixels, _) => (self.width, self.height),
+            (SizeMode::Percent, Some(monitor)) => {
+                let area = monitor.usable(self.use_full_area);
+                let scale = monitor.scale_factor;
+                (
+                    (area.width as f64 / scale * self.width / 100.0).round(),
+                    (area.height as f64 / scale * self.height / 100.0).round(),
+                )
             }
-            tauri::WindowEvent::CloseRequested { .. } => {
-                let mut registry = registry_clone.lock().unwrap();
-                registry.remove_window(&label_clone);
+            (SizeMode::Percent, None) => {
+                let fallback = WindowConfig::default();
+                (fallback.width, fallback.height)
             }
-            _ => {}
         }
-    });
-
-    Ok(label)
-}
-
-#[tauri::command]
-async fn close_app_window(
-    label: String,
-    app: AppHandle,
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(&label) {
-        window.close().map_err(|e| e.to_string())?;
     }
 
-    {
-        let mut registry = registry_state.lock().unwrap();
-        registry.remove_window(&label);
+    // Fixes the size in logical pixels, once a percentage has been resolved or when a live
+    // size is written back, so it's never read as a percentage again
+    pub fn set_pixel_size(&mut self, width: f64, height: f64) {
+        self.width = width;
+        self.height = height;
+        self.size_mode = SizeMode::Pixels;
     }
 
-    Ok(())
+    // Pulls a requested size into the configured limits
+    pub fn clamp_size(&self, width: f64, height: f64) -> (f64, f64) {
+        let clamp = |value: f64, min: Option<f64>, max: Option<f64>| {
+            let value = max.map_or(value, |max| value.min(max));
+            min.map_or(value, |min| value.max(min))
+        };
+        (
+            clamp(width, self.min_width, self.max_width),
+            clamp(height, self.min_height, self.max_height),
+        )
+    }
 }
 
-#[tauri::command]
-async fn focus_app_window(
-    label: String,
-    app: AppHandle,
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(&label) {
-        window.set_focus().map_err(|e| e.to_string())?;
-    }
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigError {
+    // Name of the WindowConfig field, as the frontend spells it
+    pub field: String,
+    pub message: String,
+}
 
-    {
-        let mut registry = registry_state.lock().unwrap();
-        registry.focus_window(&label);
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
     }
+}
 
-    Ok(())
+// Returns the scheme of an absolute URL, or None for an app-relative path
+fn url_scheme(url: &str) -> Option<&str> {
+    let (scheme, _) = url.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then_some(scheme)
 }
 
-#[tauri::command]
-async fn minimize_window(
-    label: String,
-    app: AppHandle,
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(&label) {
-        window.minimize().map_err(|e| e.to_string())?;
+// "system" maps to None, which tells Tauri to follow the OS setting
+fn parse_theme(theme: &str) -> Result<Option<tauri::Theme>, String> {
+    match theme {
+        "light" => Ok(Some(tauri::Theme::Light)),
+        "dark" => Ok(Some(tauri::Theme::Dark)),
+        "system" => Ok(None),
+        other => Err(format!("Unknown theme '{}'", other)),
     }
+}
 
-    {
-        let mut registry = registry_state.lock().unwrap();
-        registry.update_window_state(&label, Some(true), None, None);
+// "#RRGGBB" (opaque) or "#RRGGBBAA"
+fn parse_color(color: &str) -> Result<tauri::window::Color, String> {
+    let invalid = || format!("Invalid color '{}'", color);
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+    if !matches!(hex.len(), 6 | 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
     }
-
-    Ok(())
+    let channel = |i: usize| hex.get(i..i + 2).map_or(Ok(0xff), |pair| u8::from_str_radix(pair, 16).map_err(|_| invalid()));
+    Ok(tauri::window::Color(channel(0)?, channel(2)?, channel(4)?, channel(6)?))
 }
 
-#[tauri::command]
-async fn maximize_window(
-    label: String,
-    app: AppHandle,
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(&label) {
-        window.maximize().map_err(|e| e.to_string())?;
-    }
+// macOS only colors the native window, and the webview stays white on top of it
+const WEBVIEW_BACKGROUND_SUPPORTED: bool = !cfg!(target_os = "macos");
 
-    {
-        let mut registry = registry_state.lock().unwrap();
-        registry.update_window_state(&label, None, Some(true), None);
-    }
+// Protocol-relative URLs and ".." segments would reach outside the app bundle
+fn escapes_app(path: &str) -> bool {
+    path.starts_with("//") || path.split(['/', '\\']).any(|segment| segment == "..")
+}
 
-    Ok(())
+// Absolute URLs a window may load, and only on an allowlisted host; everything else has to
+// be an app-relative path
+const ALLOWED_URL_SCHEMES: &[&str] = &["http", "https"];
+// Larger than any real display, small enough to keep NaN-adjacent garbage out
+const MAX_WINDOW_DIMENSION: f64 = 16384.0;
+
+// One field of a PartialWindowConfig. A key left out inherits the template's value; an
+// explicit `null` clears it (None, false, or the built-in default for required fields).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Patch<T> {
+    #[default]
+    Inherit,
+    Clear,
+    Set(T),
 }
 
-#[tauri::command]
-async fn unmaximize_window(
-    label: String,
-    app: AppHandle,
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(&label) {
-        window.unmaximize().map_err(|e| e.to_string())?;
+impl<T> Patch<T> {
+    fn merge(self, inherited: T, cleared: T) -> T {
+        match self {
+            Patch::Inherit => inherited,
+            Patch::Clear => cleared,
+            Patch::Set(value) => value,
+        }
     }
 
-    {
-        let mut registry = registry_state.lock().unwrap();
-        registry.update_window_state(&label, None, Some(false), None);
+    fn merge_option(self, inherited: Option<T>) -> Option<T> {
+        match self {
+            Patch::Inherit => inherited,
+            Patch::Clear => None,
+            Patch::Set(value) => Some(value),
+        }
     }
-
-    Ok(())
 }
 
-#[tauri::command]
-async fn resize_app_window(
-    label: String,
-    width: f64,
-    height: f64,
-    app: AppHandle,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(&label) {
-        window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-            width: width as u32,
-            height: height as u32
-        })).map_err(|e| e.to_string())?;
+// Only reached for keys that are present, so a missing key stays Inherit via #[serde(default)]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Patch<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::<T>::deserialize(deserializer)?.map_or(Patch::Clear, Patch::Set))
     }
+}
 
-    Ok(())
+// What create_app_window accepts: any field left out comes from the window type's template
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PartialWindowConfig {
+    pub window_type: Patch<String>,
+    pub title: Patch<String>,
+    pub width: Patch<f64>,
+    pub height: Patch<f64>,
+    pub x: Patch<f64>,
+    pub y: Patch<f64>,
+    pub resizable: Patch<bool>,
+    pub minimizable: Patch<bool>,
+    pub maximizable: Patch<bool>,
+    pub closable: Patch<bool>,
+    pub always_on_top: Patch<bool>,
+    pub decorations: Patch<bool>,
+    pub transparent: Patch<bool>,
+    pub focus: Patch<bool>,
+    pub fullscreen: Patch<bool>,
+    pub url: Patch<String>,
+    pub min_width: Patch<f64>,
+    pub min_height: Patch<f64>,
+    pub max_width: Patch<f64>,
+    pub max_height: Patch<f64>,
+    pub placement: Patch<WindowPlacement>,
+    pub allow_offscreen: Patch<bool>,
+    pub on_type_limit: Patch<TypeLimitBehavior>,
+    pub theme: Patch<String>,
+    pub skip_taskbar: Patch<bool>,
+    pub switcher_visible: Patch<bool>,
+    pub visible: Patch<bool>,
+    pub create_and_reveal_when_ready: Patch<bool>,
+    pub modal: Patch<bool>,
+    pub parent_label: Patch<String>,
+    pub zoom: Patch<f64>,
+    pub background_color: Patch<String>,
+    pub opacity: Patch<f64>,
+    pub monitor_id: Patch<String>,
+    pub managed: Patch<bool>,
+    pub use_full_area: Patch<bool>,
+    pub size_mode: Patch<SizeMode>,
 }
 
-#[tauri::command]
-async fn move_window(
-    label: String,
-    x: f64,
-    y: f64,
-    app: AppHandle,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(&label) {
-        window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-            x: x as i32,
-            y: y as i32
-        })).map_err(|e| e.to_string())?;
+impl PartialWindowConfig {
+    pub fn merge_over(self, template: &WindowConfig) -> WindowConfig {
+        // Destructured so a field added to the partial config can't be silently left out here
+        let PartialWindowConfig {
+            window_type, title, width, height, x, y, resizable, minimizable,
+            maximizable, closable, always_on_top, decorations, transparent, focus,
+            fullscreen, url, min_width, min_height, max_width, max_height,
+            placement, allow_offscreen, on_type_limit, theme, skip_taskbar,
+            switcher_visible, visible, create_and_reveal_when_ready, modal,
+            parent_label, zoom, background_color, opacity, monitor_id, managed,
+            use_full_area, size_mode,
+        } = self;
+        let template = template.clone();
+        let cleared = WindowConfig::default();
+
+        WindowConfig {
+            window_type: window_type.merge(template.window_type, cleared.window_type),
+            title: title.merge(template.title, cleared.title),
+            width: width.merge(template.width, cleared.width),
+            height: height.merge(template.height, cleared.height),
+            x: x.merge_option(template.x),
+            y: y.merge_option(template.y),
+            resizable: resizable.merge(template.resizable, false),
+            minimizable: minimizable.merge(template.minimizable, false),
+            maximizable: maximizable.merge(template.maximizable, false),
+            closable: closable.merge(template.closable, false),
+            always_on_top: always_on_top.merge(template.always_on_top, false),
+            decorations: decorations.merge(template.decorations, false),
+            transparent: transparent.merge(template.transparent, false),
+            focus: focus.merge(template.focus, false),
+            fullscreen: fullscreen.merge(template.fullscreen, false),
+            url: url.merge_option(template.url),
+            min_width: min_width.merge_option(template.min_width),
+            min_height: min_height.merge_option(template.min_height),
+            max_width: max_width.merge_option(template.max_width),
+            max_height: max_height.merge_option(template.max_height),
+            placement: placement.merge(template.placement, cleared.placement),
+            allow_offscreen: allow_offscreen.merge(template.allow_offscreen, false),
+            on_type_limit: on_type_limit.merge(template.on_type_limit, cleared.on_type_limit),
+            theme: theme.merge_option(template.theme),
+            skip_taskbar: skip_taskbar.merge(template.skip_taskbar, false),
+            switcher_visible: switcher_visible.merge(template.switcher_visible, false),
+            visible: visible.merge(template.visible, false),
+            create_and_reveal_when_ready: create_and_reveal_when_ready.merge(template.create_and_reveal_when_ready, false),
+            modal: modal.merge(template.modal, false),
+            parent_label: parent_label.merge_option(template.parent_label),
+            zoom: zoom.merge_option(template.zoom),
+            background_color: background_color.merge_option(template.background_color),
+            opacity: opacity.merge(template.opacity, cleared.opacity),
+            monitor_id: monitor_id.merge_option(template.monitor_id),
+            managed: managed.merge(template.managed, false),
+            use_full_area: use_full_area.merge(template.use_full_area, false),
+            size_mode: size_mode.merge(template.size_mode, cleared.size_mode),
+        }
     }
+}
 
-    Ok(())
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AppliedSize {
+    pub width: f64,
+    pub height: f64,
 }
 
-#[tauri::command]
-async fn get_window_list(
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<Vec<WindowState>, String> {
-    let registry = registry_state.lock().unwrap();
-    Ok(registry.get_windows_by_z_order().into_iter().cloned().collect())
-}
-
-#[tauri::command]
-async fn get_focused_window(
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<Option<WindowState>, String> {
-    let registry = registry_state.lock().unwrap();
-    Ok(registry.get_focused_window().cloned())
-}
-
-#[tauri::command]
-async fn get_monitors() -> Result<Vec<MonitorInfo>, String> {
-    // This would require platform-specific implementations
-    // For now, return a mock implementation
-    Ok(vec![MonitorInfo {
-        id: "primary".to_string(),
-        name: "Primary Monitor".to_string(),
-        width: 1920,
-        height: 1080,
-        x: 0,
-        y: 0,
-        scale_factor: 1.0,
-        is_primary: true,
-    }])
-}
-
-// Window persistence specific commands
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WindowPositionSize {
-    pub x: f64,
-    pub y: f64,
-    pub width: f64,
-    pub height: f64,
-    pub is_maximized: bool,
+pub struct WindowState {
+    pub label: String,
+    pub config: WindowConfig,
+    #[serde(default)]
+    pub z_order: u32,
+    #[serde(default)]
+    pub is_focused: bool,
+    #[serde(default)]
     pub is_minimized: bool,
+    #[serde(default)]
+    pub is_maximized: bool,
+    // Only known after a live query; our own commands never make windows fullscreen
+    #[serde(default)]
     pub is_fullscreen: bool,
-    pub monitor: u32,
-    pub monitor_name: Option<String>,
+    pub monitor_id: Option<String>,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub last_focused_at: u64,
+    // Live physical geometry, tracked from Moved/Resized events: the outer position and the
+    // inner size, the measures set_position and set_size take
+    pub current_x: Option<i32>,
+    pub current_y: Option<i32>,
+    pub current_width: Option<u32>,
+    pub current_height: Option<u32>,
+    // Hidden windows keep their webview alive but aren't shown anywhere
+    #[serde(default = "default_true")]
+    pub is_visible: bool,
+    // Outer bounds (x, y, width, height) from before toggle_fullscreen entered fullscreen
+    #[serde(default)]
+    pub pre_fullscreen_bounds: Option<(i32, i32, u32, u32)>,
+    // Set by request_user_attention, cleared once the window is focused
+    #[serde(default)]
+    pub needs_attention: bool,
+    // Webview content zoom; 1.0 is 100%
+    #[serde(default = "default_zoom")]
+    pub zoom: f64,
+    // Related windows (e.g. every diplomacy panel) that are raised and listed together
+    #[serde(default)]
+    pub group_id: Option<String>,
+    // Mouse events pass through to whatever is beneath; used for HUD overlays
+    #[serde(default)]
+    pub click_through: bool,
+    // Whatever the frontend wants to keep with the window, e.g. the entity a dashboard shows
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+    // The monitor this window was on when it was disconnected; cleared if that monitor comes back
+    #[serde(default)]
+    pub lost_monitor_id: Option<String>,
+    // Overlaps more than one monitor; monitor_id is the one showing most of it
+    #[serde(default)]
+    pub spanning: bool,
+    // Where the window was before being rehomed off a disconnected monitor, until
+    // restore_displaced_windows puts it back
+    #[serde(default)]
+    pub displaced_from: Option<DisplacedFrom>,
+    // Scale of the window's monitor, and the live bounds in the logical coordinates the
+    // commands take (see logical_to_physical); kept in step with the physical ones above
+    #[serde(default = "default_zoom")]
+    pub scale_factor: f64,
+    #[serde(default)]
+    pub logical_bounds: Option<(f64, f64, f64, f64)>,
 }
 
-#[tauri::command]
-async fn get_monitor_info() -> Result<Vec<MonitorInfo>, String> {
-    // Platform-specific monitor detection would go here
-    // For cross-platform compatibility, we'll implement a basic version
-
-    #[cfg(target_os = "windows")]
-    {
-        // Windows-specific monitor enumeration
-        get_windows_monitors()
-    }
-
-    #[cfg(target_os = "macos")]
-    {
-        // macOS-specific monitor enumeration
-        get_macos_monitors()
-    }
+// Payload of `window-scale-changed`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowScaleChange {
+    pub label: String,
+    pub old_scale_factor: f64,
+    pub new_scale_factor: f64,
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        // Linux-specific monitor enumeration
-        get_linux_monitors()
-    }
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplacedFrom {
+    pub monitor_id: String,
+    // Physical bounds (x, y, width, height), measured like WindowState's current_* fields
+    pub bounds: (i32, i32, u32, u32),
+}
 
-    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-    {
-        // Fallback for other platforms
-        Ok(vec![MonitorInfo {
-            id: "primary".to_string(),
-            name: "Primary Monitor".to_string(),
-            width: 1920,
-            height: 1080,
-            x: 0,
-            y: 0,
+impl WindowState {
+    // A just-created window, unfocused and shown unless its config starts it hidden. Its
+    // physical bounds are unknown until read off the live window or its first Moved/Resized
+    fn new(label: String, config: WindowConfig, z_order: u32, now: u64) -> Self {
+        Self {
+            label,
+            current_x: None,
+            current_y: None,
+            current_width: None,
+            current_height: None,
+            z_order,
+            is_focused: false,
+            is_minimized: false,
+            is_maximized: false,
+            is_fullscreen: false,
+            pre_fullscreen_bounds: None,
+            needs_attention: false,
+            zoom: config.zoom.map_or(1.0, clamp_zoom),
+            monitor_id: None,
+            group_id: None,
+            click_through: false,
+            metadata: HashMap::new(),
+            lost_monitor_id: None,
+            spanning: false,
+            displaced_from: None,
             scale_factor: 1.0,
-            is_primary: true,
-        }])
+            logical_bounds: None,
+            created_at: now,
+            last_focused_at: now,
+            is_visible: !config.starts_hidden(),
+            config,
+        }
     }
 }
 
-#[cfg(target_os = "windows")]
-fn get_windows_monitors() -> Result<Vec<MonitorInfo>, String> {
-    // For now, return mock data. In production, this would use Windows API
-    Ok(vec![MonitorInfo {
-        id: "primary".to_string(),
-        name: "Primary Monitor".to_string(),
-        width: 1920,
-        height: 1080,
-        x: 0,
-        y: 0,
-        scale_factor: 1.0,
-        is_primary: true,
-    }])
-}
-
-#[cfg(target_os = "macos")]
-fn get_macos_monitors() -> Result<Vec<MonitorInfo>, String> {
-    // For now, return mock data. In production, this would use macOS APIs
-    Ok(vec![MonitorInfo {
-        id: "primary".to_string(),
-        name: "Primary Monitor".to_string(),
-        width: 1920,
-        height: 1080,
-        x: 0,
-        y: 0,
-        scale_factor: 1.0,
-        is_primary: true,
-    }])
+fn default_true() -> bool {
+    true
 }
 
-#[cfg(target_os = "linux")]
-fn get_linux_monitors() -> Result<Vec<MonitorInfo>, String> {
-    // For now, return mock data. In production, this would use X11/Wayland APIs
-    Ok(vec![MonitorInfo {
-        id: "primary".to_string(),
-        name: "Primary Monitor".to_string(),
-        width: 1920,
-        height: 1080,
-        x: 0,
-        y: 0,
-        scale_factor: 1.0,
-        is_primary: true,
-    }])
+fn default_zoom() -> f64 {
+    1.0
 }
 
-#[tauri::command]
-async fn get_all_window_states(
-    app: AppHandle,
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<std::collections::HashMap<String, WindowPositionSize>, String> {
-    let registry = registry_state.lock().unwrap();
-    let mut result = std::collections::HashMap::new();
-
-    for (label, window_state) in registry.windows.iter() {
-        if let Some(window) = app.get_webview_window(label) {
-            let position = window.outer_position().map_err(|e| e.to_string())?;
-            let size = window.outer_size().map_err(|e| e.to_string())?;
-            let is_maximized = window.is_maximized().map_err(|e| e.to_string())?;
-            let is_minimized = window.is_minimized().map_err(|e| e.to_string())?;
-            let is_fullscreen = window.is_fullscreen().map_err(|e| e.to_string())?;
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
 
-            result.insert(label.clone(), WindowPositionSize {
-                x: position.x as f64,
-                y: position.y as f64,
-                width: size.width as f64,
-                height: size.height as f64,
-                is_maximized,
-                is_minimized,
-                is_fullscreen,
-                monitor: 0, // Default to primary monitor
-                monitor_name: window_state.monitor_id.clone(),
-            });
-        }
-    }
+fn clamp_zoom(factor: f64) -> f64 {
+    factor.clamp(MIN_ZOOM, MAX_ZOOM)
+}
 
-    Ok(result)
+fn default_opacity() -> f64 {
+    1.0
 }
 
-#[tauri::command]
-async fn set_window_state(
-    label: String,
-    state: WindowPositionSize,
-    app: AppHandle,
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(&label) {
-        // Set position
-        window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-            x: state.x as i32,
-            y: state.y as i32,
-        })).map_err(|e| e.to_string())?;
+const MIN_OPACITY: f64 = 0.1;
 
-        // Set size
-        window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-            width: state.width as u32,
-            height: state.height as u32,
-        })).map_err(|e| e.to_string())?;
+fn clamp_opacity(opacity: f64) -> f64 {
+    opacity.clamp(MIN_OPACITY, 1.0)
+}
 
-        // Set maximized state
-        if state.is_maximized {
-            window.maximize().map_err(|e| e.to_string())?;
-        } else {
-            window.unmaximize().map_err(|e| e.to_string())?;
-        }
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorInfo {
+    pub id: String,
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub scale_factor: f64,
+    pub is_primary: bool,
+    // The part not taken by the taskbar, dock or panels, in the same physical pixels
+    pub work_x: i32,
+    pub work_y: i32,
+    pub work_width: u32,
+    pub work_height: u32,
+    // False when the platform doesn't report a work area and the full bounds stand in for it
+    pub work_area_exact: bool,
+}
 
-        // Set minimized state
-        if state.is_minimized {
-            window.minimize().map_err(|e| e.to_string())?;
-        } else {
-            window.unminimize().map_err(|e| e.to_string())?;
+impl MonitorInfo {
+    // This monitor with its bounds narrowed to the work area, unless `use_full_area`, for
+    // the snapping, centering and clamping helpers that work on plain bounds
+    pub fn usable(&self, use_full_area: bool) -> MonitorInfo {
+        if use_full_area {
+            return self.clone();
         }
-
-        // Set fullscreen state
-        window.set_fullscreen(state.is_fullscreen).map_err(|e| e.to_string())?;
-
-        // Update registry
-        {
-            let mut registry = registry_state.lock().unwrap();
-            registry.update_window_state(
-                &label,
-                Some(state.is_minimized),
-                Some(state.is_maximized),
-                state.monitor_name,
-            );
+        MonitorInfo {
+            x: self.work_x,
+            y: self.work_y,
+            width: self.work_width,
+            height: self.work_height,
+            ..self.clone()
         }
     }
+}
 
-    Ok(())
+fn usable_monitors(monitors: &[MonitorInfo], use_full_area: bool) -> Vec<MonitorInfo> {
+    monitors.iter().map(|monitor| monitor.usable(use_full_area)).collect()
 }
 
-// Window switching and keyboard shortcuts
-#[tauri::command]
-async fn cycle_windows(
-    forward: bool,
-    registry_state: State<'_, WindowRegistryState>,
-    app: AppHandle,
-) -> Result<(), String> {
-    let current_focused = {
-        let registry = registry_state.lock().unwrap();
-        registry.get_focused_window().map(|w| w.label.clone())
-    };
+// Payload of `monitors-changed`, keyed by monitor id. A monitor that moved gets a new id,
+// so it shows up as removed and added
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MonitorChanges {
+    pub added: Vec<MonitorInfo>,
+    pub removed: Vec<MonitorInfo>,
+    pub changed: Vec<MonitorInfo>,
+    // The full list after the change
+    pub monitors: Vec<MonitorInfo>,
+}
 
-    let windows = {
-        let registry = registry_state.lock().unwrap();
-        registry.get_windows_by_z_order().into_iter().map(|w| w.label.clone()).collect::<Vec<_>>()
-    };
+impl MonitorChanges {
+    pub fn between(previous: &[MonitorInfo], current: &[MonitorInfo]) -> Self {
+        let find = |list: &[MonitorInfo], id: &str| list.iter().find(|m| m.id == id).cloned();
+        Self {
+            added: current.iter().filter(|m| find(previous, &m.id).is_none()).cloned().collect(),
+            removed: previous.iter().filter(|m| find(current, &m.id).is_none()).cloned().collect(),
+            changed: current.iter()
+                .filter(|m| find(previous, &m.id).is_some_and(|old| old != **m))
+                .cloned()
+                .collect(),
+            monitors: current.to_vec(),
+        }
+    }
 
-    if windows.is_empty() {
-        return Ok(());
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
     }
+}
 
-    let next_window = if let Some(current) = current_focused {
-        if let Some(current_index) = windows.iter().position(|w| w == &current) {
-            if forward {
-                windows.get((current_index + 1) % windows.len())
-            } else {
-                if current_index == 0 {
-                    windows.last()
-                } else {
-                    windows.get(current_index - 1)
-                }
-            }
-        } else {
-            windows.first()
-        }
-    } else {
-        windows.first()
-    };
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistrySyncReport {
+    pub removed: Vec<String>,
+    pub added: Vec<String>,
+}
 
-    if let Some(window_label) = next_window {
-        focus_app_window(window_label.clone(), app, registry_state).await?;
+impl RegistrySyncReport {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
     }
+}
 
-    Ok(())
+// `get_window_list` keeps returning a bare array unless live state was requested
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum WindowListing {
+    Registry(Vec<WindowState>),
+    Live {
+        windows: Vec<WindowState>,
+        // Label -> error for windows whose live state couldn't be read
+        errors: HashMap<String, String>,
+    },
 }
 
-// Window snapping and arrangement
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum SnapPosition {
-    Left,
-    Right,
-    Top,
-    Bottom,
-    TopLeft,
-    TopRight,
-    BottomLeft,
-    BottomRight,
-    Center,
-    Maximize,
+// Structured errors for commands where the frontend needs to tell failures apart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum WindowError {
+    NotFound { label: String },
+    Failed { label: String, message: String },
+    TypeLimitReached { window_type: String, limit: usize, count: usize, existing: Vec<String> },
+    InvalidConfig { errors: Vec<ConfigError> },
+    InvalidLabel { label: String },
+    LabelTaken { label: String },
+    UrlNotAllowed { url: String },
+    InvalidUrl { url: String, message: String },
+    // The webview itself couldn't be created; nothing was registered
+    BuildFailed { label: String, window_type: String, url: String, message: String },
 }
 
-#[tauri::command]
-async fn snap_window(
-    label: String,
-    position: SnapPosition,
-    app: AppHandle,
-) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window(&label) {
-        // Get monitor dimensions (mock for now)
-        let monitor_width = 1920.0;
-        let monitor_height = 1080.0;
-        let half_width = monitor_width / 2.0;
-        let half_height = monitor_height / 2.0;
-
-        match position {
-            SnapPosition::Left => {
-                window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x: 0, y: 0 })).map_err(|e| e.to_string())?;
-                window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-                    width: half_width as u32,
-                    height: monitor_height as u32
-                })).map_err(|e| e.to_string())?;
-            }
-            SnapPosition::Right => {
-                window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-                    x: half_width as i32,
-                    y: 0
-                })).map_err(|e| e.to_string())?;
-                window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-                    width: half_width as u32,
-                    height: monitor_height as u32
-                })).map_err(|e| e.to_string())?;
-            }
-            SnapPosition::Top => {
-                window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x: 0, y: 0 })).map_err(|e| e.to_string())?;
-                window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-                    width: monitor_width as u32,
-                    height: half_height as u32
-                })).map_err(|e| e.to_string())?;
-            }
-            SnapPosition::Bottom => {
-                window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-                    x: 0,
-                    y: half_height as i32
-                })).map_err(|e| e.to_string())?;
-                window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-                    width: monitor_width as u32,
-                    height: half_height as u32
-                })).map_err(|e| e.to_string())?;
-            }
-            SnapPosition::TopLeft => {
-                window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x: 0, y: 0 })).map_err(|e| e.to_string())?;
-                window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-                    width: half_width as u32,
-                    height: half_height as u32
-                })).map_err(|e| e.to_string())?;
-            }
-            SnapPosition::TopRight => {
-                window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-                    x: half_width as i32,
-                    y: 0
-                })).map_err(|e| e.to_string())?;
-                window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-                    width: half_width as u32,
-                    height: half_height as u32
-                })).map_err(|e| e.to_string())?;
-            }
-            SnapPosition::BottomLeft => {
-                window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-                    x: 0,
-                    y: half_height as i32
-                })).map_err(|e| e.to_string())?;
-                window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-                    width: half_width as u32,
-                    height: half_height as u32
-                })).map_err(|e| e.to_string())?;
-            }
-            SnapPosition::BottomRight => {
-                window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-                    x: half_width as i32,
-                    y: half_height as i32
-                })).map_err(|e| e.to_string())?;
-                window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-                    width: half_width as u32,
-                    height: half_height as u32
-                })).map_err(|e| e.to_string())?;
-            }
-            SnapPosition::Center => {
-                let center_width = monitor_width * 0.7;
-                let center_height = monitor_height * 0.7;
-                window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-                    x: ((monitor_width - center_width) / 2.0) as i32,
-                    y: ((monitor_height - center_height) / 2.0) as i32
-                })).map_err(|e| e.to_string())?;
-                window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-                    width: center_width as u32,
-                    height: center_height as u32
-                })).map_err(|e| e.to_string())?;
-            }
-            SnapPosition::Maximize => {
-                window.maximize().map_err(|e| e.to_string())?;
+impl std::fmt::Display for WindowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowError::NotFound { label } => write!(f, "Window '{}' not found", label),
+            WindowError::Failed { label, message } => write!(f, "Window '{}': {}", label, message),
+            WindowError::TypeLimitReached { window_type, limit, count, .. } => {
+                write!(f, "At most {} '{}' window(s) may be open ({} already are)", limit, window_type, count)
+            }
+            WindowError::InvalidLabel { label } => write!(
+                f,
+                "Window label '{}' may only contain letters, digits, '-', '/', ':' and '_'",
+                label
+            ),
+            WindowError::LabelTaken { label } => write!(f, "A window labelled '{}' already exists", label),
+            WindowError::UrlNotAllowed { url } => write!(f, "Windows may not load '{}'", url),
+            WindowError::InvalidUrl { url, message } => write!(f, "Invalid URL '{}': {}", url, message),
+            WindowError::BuildFailed { label, window_type, url, message } => write!(
+                f,
+                "Couldn't open '{}' window '{}' at '{}': {}",
+                window_type, label, url, message
+            ),
+            WindowError::InvalidConfig { errors } => {
+                let fields: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                write!(f, "Invalid window config: {}", fields.join("; "))
             }
         }
     }
+}
 
-    Ok(())
+// Tauri only accepts alphanumerics, '-', '/', ':' and '_' in window labels
+fn validate_window_label(label: &str) -> Result<(), WindowError> {
+    let valid = !label.is_empty()
+        && label.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '/' | ':' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(WindowError::InvalidLabel { label: label.to_string() })
+    }
 }
 
-// Window state persistence
-#[tauri::command]
-async fn save_window_state(
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<(), String> {
-    let registry = registry_state.lock().unwrap();
-    let windows: Vec<WindowState> = registry.get_windows_by_z_order().into_iter().cloned().collect();
+impl From<WindowError> for String {
+    fn from(error: WindowError) -> Self {
+        error.to_string()
+    }
+}
 
-    // Save to a JSON file (simplified implementation)
-    let app_data_dir = std::env::var("APPDATA").unwrap_or_else(|_| "/tmp".to_string());
-    let save_path = format!("{}/politicail_windows.json", app_data_dir);
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkCloseReport {
+    pub closed: Vec<String>,
+    // Windows that refused to close or are waiting for the user to confirm
+    pub skipped: Vec<String>,
+}
 
-    let json_data = serde_json::to_string_pretty(&windows).map_err(|e| e.to_string())?;
-    std::fs::write(save_path, json_data).map_err(|e| e.to_string())?;
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistryStats {
+    pub total_windows: usize,
+    pub windows_by_type: HashMap<String, usize>,
+    pub focused_window: Option<String>,
+    pub highest_z_order: u32,
+    // Last z-order handed out; running well ahead of highest_z_order means windows come and go a lot
+    pub z_order_counter: u32,
+    // Registry entries with no live Tauri window behind them
+    pub ghost_windows: usize,
+    pub oldest_window_age_ms: Option<u64>,
+}
 
-    Ok(())
+// Windows minimized by "show desktop", so restoring leaves user-minimized ones alone
+#[derive(Debug, Clone, Default)]
+pub struct ShowDesktopSnapshot {
+    pub labels: Vec<String>,
+    pub focused: Option<String>,
 }
 
-#[tauri::command]
-async fn load_window_state(
-    app: AppHandle,
-    registry_state: State<'_, WindowRegistryState>,
-) -> Result<Vec<String>, String> {
-    let app_data_dir = std::env::var("APPDATA").unwrap_or_else(|_| "/tmp".to_string());
-    let save_path = format!("{}/politicail_windows.json", app_data_dir);
+// An in-progress Alt+Tab style cycle; candidates are frozen when it starts
+#[derive(Debug, Clone)]
+struct CycleSession {
+    window_type: Option<String>,
+    candidates: Vec<String>,
+    index: usize,
+    last_step_at: u64,
+}
 
-    if !std::path::Path::new(&save_path).exists() {
-        return Ok(vec![]);
+// What the CloseRequested handler should do with a close request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CloseDecision {
+    Proceed,
+    // First request: ask the frontend and start the fallback timer
+    Ask { requested_at: u64 },
+    // Still waiting on an earlier request
+    AlreadyAsked,
+}
+
+// Minimize/maximize/fullscreen as the OS reports them, which the titlebar buttons
+// change without going through our commands
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NativeWindowFlags {
+    pub minimized: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+impl NativeWindowFlags {
+    fn bits(self) -> u8 {
+        self.minimized as u8 | (self.maximized as u8) << 1 | (self.fullscreen as u8) << 2
     }
+}
 
-    let json_data = std::fs::read_to_string(save_path).map_err(|e| e.to_string())?;
-    let saved_windows: Vec<WindowState> = serde_json::from_str(&json_data).map_err(|e| e.to_string())?;
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppFocusState {
+    pub app_focused: bool,
+    pub focused_window: Option<String>,
+}
 
-    let mut restored_labels = Vec::new();
+// An event sent to a window whose frontend hasn't attached its listeners yet
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedEvent {
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub queued_at: u64,
+}
 
-    for window_state in saved_windows {
-        let result = create_app_window(
-            app.clone(),
-            window_state.config.window_type.clone(),
-            window_state.config.clone(),
-            registry_state.clone()
-        ).await;
+const FOCUS_HISTORY_LIMIT: usize = 64;
+// Step between cascaded spawns of the same window type
+const CASCADE_SPAWN_OFFSET: i32 = 32;
+// Moving focus between two of our windows blurs one before focusing the other; wait this
+// long before treating a blur as the whole app losing focus
+const APP_FOCUS_SETTLE_MS: u64 = 50;
+// Queued events are dropped after this long, so windows that never become ready don't leak
+const DEFAULT_EVENT_QUEUE_TTL_MS: u64 = 30_000;
+// Unanswered close confirmations fall back to closing after this long
+const CLOSE_CONFIRMATION_TIMEOUT_MS: u64 = 10_000;
+// Past this the counter is compacted back down so bumps never overflow
+const Z_ORDER_RENORMALIZE_AT: u32 = u32::MAX - 1024;
+// A cycle nobody ended explicitly is committed after this much inactivity
+const CYCLE_SESSION_TIMEOUT_MS: u64 = 1500;
+const DEFAULT_MAGNETIC_SNAP_THRESHOLD: u32 = 12;
 
-        if let Ok(label) = result {
-            restored_labels.push(label.clone());
+// Window registry for state tracking
+pub struct WindowRegistry {
+    windows: HashMap<String, WindowState>,
+    z_order_counter: u32,
+    focused_window: Option<String>,
+    // Most recent focus at the back; closed windows are pruned lazily
+    focus_history: VecDeque<String>,
+    // Space left around snapped windows, in physical pixels
+    snap_gap: u32,
+    show_desktop: Option<ShowDesktopSnapshot>,
+    // Pull dragged windows onto nearby window and monitor edges
+    magnetic_snap_enabled: bool,
+    magnetic_snap_threshold: u32,
+    // Pull windows back onto a remaining display when one is unplugged
+    reclamp_on_display_change: bool,
+    // Apply the layout saved for a monitor setup as soon as it's plugged back in, rather
+    // than only telling the frontend one exists
+    auto_apply_monitor_layouts: bool,
+    // Minimum spacing between registry writes from one window's Moved/Resized events
+    event_throttle_ms: u64,
+    // Used by create_app_window when a config doesn't position the window itself
+    default_placement: WindowPlacement,
+    cycle_session: Option<CycleSession>,
+    // Maximum open windows per window_type; 1 makes a type a singleton
+    type_limits: HashMap<String, usize>,
+    // Windows whose close must be confirmed by the frontend, e.g. ones with unsaved edits
+    close_confirmation: HashSet<String>,
+    // Label -> when the unanswered close request came in
+    pending_closes: HashMap<String, u64>,
+    // Windows whose frontend has called window_ready
+    ready_windows: HashSet<String>,
+    // Windows created hidden that haven't been revealed yet
+    pending_reveals: HashSet<String>,
+    // Parents we've disabled natively for a modal; re-enabled once no modal blocks them
+    disabled_parents: HashSet<String>,
+    pending_events: HashMap<String, VecDeque<QueuedEvent>>,
+    event_queue_ttl_ms: u64,
+    // Whether any of our windows has OS focus; focused_window is kept as the last active one
+    app_focused: bool,
+    // The app focus state last announced to the frontend
+    app_focus_reported: bool,
+    // Label -> id of its running animation; a newer id cancels the older one
+    animations: HashMap<String, u64>,
+    animation_counter: u64,
+    // window_type -> last cascaded spawn, cleared when the type's last window closes
+    cascade_spawns: HashMap<String, CascadeSpawn>,
+    // Windows created with `managed: false`, kept apart so nothing else sees them
+    unmanaged: HashMap<String, WindowState>,
+}
 
-            // Restore window position and size
-            if let Some(x) = window_state.config.x {
-                if let Some(y) = window_state.config.y {
-                    let _ = move_window(label.clone(), x, y, app.clone()).await;
-                }
-            }
+impl Default for WindowRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-            let _ = resize_app_window(
-                label.clone(),
+impl WindowRegistry {
+    pub fn new() -> Self {
+        Self {
+            windows: HashMap::new(),
+            z_order_counter: 0,
+            focused_window: None,
+            focus_history: VecDeque::new(),
+            snap_gap: 0,
+            show_desktop: None,
+            magnetic_snap_enabled: false,
+            reclamp_on_display_change: false,
+            auto_apply_monitor_layouts: false,
+            event_throttle_ms: GEOMETRY_UPDATE_INTERVAL_MS,
+            default_placement: WindowPlacement::Absolute,
+            magnetic_snap_threshold: DEFAULT_MAGNETIC_SNAP_THRESHOLD,
+            cycle_session: None,
+            type_limits: HashMap::new(),
+            close_confirmation: HashSet::new(),
+            pending_closes: HashMap::new(),
+            ready_windows: HashSet::new(),
+            pending_reveals: HashSet::new(),
+            disabled_parents: HashSet::new(),
+            pending_events: HashMap::new(),
+            event_queue_ttl_ms: DEFAULT_EVENT_QUEUE_TTL_MS,
+            app_focused: true,
+            app_focus_reported: true,
+            animations: HashMap::new(),
+            unmanaged: HashMap::new(),
+            animation_counter: 0,
+            cascade_spawns: HashMap::new(),
+        }
+    }
+
+    fn push_focus_history(&mut self, label: &str) {
+        self.focus_history.retain(|l| l != label);
+        self.focus_history.push_back(label.to_string());
+        while self.focus_history.len() > FOCUS_HISTORY_LIMIT {
+            self.focus_history.pop_front();
+        }
+    }
+
+    // Most recently used first, without utility windows; what a window switcher should list
+    pub fn get_switcher_windows(&self) -> Vec<&WindowState> {
+        self.get_windows_by_recent_use().into_iter()
+            .filter(|w| w.config.switcher_visible)
+            .collect()
+    }
+
+    // Walks back through focus history for the last window that can take focus again,
+    // dropping entries for windows that have since been closed
+    pub fn previous_focus_candidate(&mut self) -> Option<String> {
+        let windows = &self.windows;
+        self.focus_history.retain(|l| windows.contains_key(l));
+
+        self.focus_history.iter().rev()
+            .filter(|l| self.focused_window.as_ref() != Some(*l))
+            .find(|l| self.windows.get(*l).is_some_and(|w| !w.is_minimized && w.is_visible) && self.blocking_modal(l).is_none())
+            .cloned()
+    }
+
+    // Hands focus back to the previously used window if `label` currently holds it
+    fn release_focus(&mut self, label: &str) {
+        if self.focused_window.as_deref() != Some(label) {
+            return;
+        }
+
+        if let Some(window) = self.windows.get_mut(label) {
+            window.is_focused = false;
+        }
+        self.focused_window = None;
+
+        if let Some(previous) = self.previous_focus_candidate() {
+            if let Some(window) = self.windows.get_mut(&previous) {
+                window.is_focused = true;
+            }
+            self.focused_window = Some(previous);
+        }
+    }
+
+    // Topmost window that could hold focus: shown, not minimized and not behind a modal
+    fn top_focusable_window(&self) -> Option<&WindowState> {
+        self.get_windows_by_z_order().into_iter()
+            .find(|w| w.is_visible && !w.is_minimized && self.blocking_modal(&w.label).is_none())
+    }
+
+    // A minimized window can't keep focus; it passes to whatever is now on top, or nowhere
+    fn release_focus_on_minimize(&mut self, label: &str) {
+        if self.focused_window.as_deref() != Some(label) {
+            return;
+        }
+
+        if let Some(window) = self.windows.get_mut(label) {
+            window.is_focused = false;
+        }
+        self.focused_window = self.top_focusable_window().map(|w| w.label.clone());
+        if let Some(next) = self.focused_window.clone() {
+            if let Some(window) = self.windows.get_mut(&next) {
+                window.is_focused = true;
+            }
+        }
+    }
+
+    pub fn add_window(&mut self, label: String, config: WindowConfig) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        let z_order = self.next_z_order();
+        // A window created hidden takes focus when it's revealed
+        let hidden = config.starts_hidden();
+        let mut window_state = WindowState::new(label.clone(), config, z_order, now);
+        window_state.is_focused = !hidden;
+
+        if hidden {
+            self.pending_reveals.insert(label.clone());
+            self.windows.insert(label, window_state);
+            return;
+        }
+
+        // Update focus
+        if let Some(prev_focused) = &self.focused_window {
+            if let Some(prev_window) = self.windows.get_mut(prev_focused) {
+                prev_window.is_focused = false;
+            }
+        }
+        self.focused_window = Some(label.clone());
+        self.push_focus_history(&label);
+
+        self.windows.insert(label, window_state);
+    }
+
+    // Returns whether the window was still registered
+    pub fn remove_window(&mut self, label: &str) -> bool {
+        self.unmanaged.remove(label);
+        let removed = self.windows.remove(label);
+        if let Some(window) = &removed {
+            let window_type = &window.config.window_type;
+            if !self.windows.values().any(|w| &w.config.window_type == window_type) {
+                self.cascade_spawns.remove(window_type);
+            }
+        }
+        self.close_confirmation.remove(label);
+        self.pending_closes.remove(label);
+        self.ready_windows.remove(label);
+        self.pending_reveals.remove(label);
+        self.disabled_parents.remove(label);
+        // A modal whose parent went away is just an ordinary window now
+        for window in self.windows.values_mut() {
+            if window.config.parent_label.as_deref() == Some(label) {
+                window.config.parent_label = None;
+            }
+        }
+        self.pending_events.remove(label);
+        self.animations.remove(label);
+        // Return focus to the window that was in use before this one
+        self.release_focus(label);
+        removed.is_some()
+    }
+
+    // Physical position for the next cascaded window of `window_type` on `monitor`. The
+    // chain starts over at the monitor's corner when the window would run off the
+    // monitor, or when the previous spawn was on a different monitor.
+    fn cascade_spawn_for(&self, window_type: &str, width: u32, height: u32, monitor: &MonitorInfo) -> CascadeSpawn {
+        let origin = (monitor.x + CASCADE_SPAWN_OFFSET, monitor.y + CASCADE_SPAWN_OFFSET);
+        let next = match self.cascade_spawns.get(window_type) {
+            Some(last) if last.monitor_id == monitor.id => (last.x + CASCADE_SPAWN_OFFSET, last.y + CASCADE_SPAWN_OFFSET),
+            _ => origin,
+        };
+
+        let right = monitor.x as i64 + monitor.width as i64;
+        let bottom = monitor.y as i64 + monitor.height as i64;
+        let fits = next.0 as i64 + width as i64 <= right && next.1 as i64 + height as i64 <= bottom;
+        let (x, y) = if fits { next } else { origin };
+        CascadeSpawn { monitor_id: monitor.id.clone(), x, y }
+    }
+
+    // Physical top-left for a SmartAvoidOverlap window, clear of this type's open windows
+    fn spawn_avoiding_overlap(&self, window_type: &str, width: u32, height: u32, monitor: &MonitorInfo) -> (i32, i32) {
+        let existing: Vec<(i32, i32, u32, u32)> = self.windows.values()
+            .filter(|w| w.config.window_type == window_type)
+            .filter_map(|w| match (w.current_x, w.current_y, w.current_width, w.current_height) {
+                (Some(x), Some(y), Some(width), Some(height)) => Some((x, y, width, height)),
+                _ => None,
+            })
+            .collect();
+        avoid_overlap_position(width, height, &existing, monitor, CASCADE_SPAWN_OFFSET)
+    }
+
+    // Only once the window actually opened, so a failed build doesn't skip a slot
+    fn record_cascade_spawn(&mut self, window_type: &str, spawn: CascadeSpawn) {
+        self.cascade_spawns.insert(window_type.to_string(), spawn);
+    }
+
+    // The OS took focus away from `label`. If focus went to another app no Focused(true)
+    // follows, so the app stays marked unfocused.
+    pub fn blur_window(&mut self, label: &str) {
+        if self.focused_window.as_deref() != Some(label) {
+            return;
+        }
+        if let Some(window) = self.windows.get_mut(label) {
+            window.is_focused = false;
+        }
+        self.app_focused = false;
+    }
+
+    pub fn app_focus_state(&self) -> AppFocusState {
+        AppFocusState {
+            app_focused: self.app_focused,
+            focused_window: self.app_focused.then(|| self.focused_window.clone()).flatten(),
+        }
+    }
+
+    // The new state if it differs from what was last announced
+    pub fn take_app_focus_change(&mut self) -> Option<AppFocusState> {
+        if self.app_focused == self.app_focus_reported {
+            return None;
+        }
+        self.app_focus_reported = self.app_focused;
+        Some(self.app_focus_state())
+    }
+
+    // Registers a new animation for `label`, superseding any that is still running
+    pub fn start_animation(&mut self, label: &str) -> u64 {
+        self.animation_counter += 1;
+        self.animations.insert(label.to_string(), self.animation_counter);
+        self.animation_counter
+    }
+
+    pub fn is_animation_current(&self, label: &str, id: u64) -> bool {
+        self.animations.get(label) == Some(&id)
+    }
+
+    pub fn finish_animation(&mut self, label: &str, id: u64) {
+        if self.is_animation_current(label, id) {
+            self.animations.remove(label);
+        }
+    }
+
+    // The topmost open modal whose parent is `label`
+    pub fn blocking_modal(&self, label: &str) -> Option<String> {
+        self.windows.values()
+            .filter(|w| w.config.modal && w.config.parent_label.as_deref() == Some(label))
+            .max_by_key(|w| w.z_order)
+            .map(|w| w.label.clone())
+    }
+
+    pub fn block_parent(&mut self, label: &str) {
+        self.disabled_parents.insert(label.to_string());
+    }
+
+    // Disabled parents no modal is holding anymore; the caller re-enables them natively
+    pub fn take_released_parents(&mut self) -> Vec<String> {
+        let mut released: Vec<String> = self.disabled_parents.iter()
+            .filter(|label| self.blocking_modal(label).is_none())
+            .cloned()
+            .collect();
+        released.sort();
+        for label in &released {
+            self.disabled_parents.remove(label);
+        }
+        released
+    }
+
+    pub fn is_reveal_pending(&self, label: &str) -> bool {
+        self.pending_reveals.contains(label)
+    }
+
+    // Whether window_ready should reveal the window
+    pub fn reveals_when_ready(&self, label: &str) -> bool {
+        self.is_reveal_pending(label)
+            && self.windows.get(label).is_some_and(|w| w.config.reveals_when_ready())
+    }
+
+    pub fn is_ready(&self, label: &str) -> bool {
+        self.ready_windows.contains(label)
+    }
+
+    // Marks the window ready and hands back whatever was queued for it, oldest first
+    pub fn mark_ready(&mut self, label: &str, now: u64) -> Vec<QueuedEvent> {
+        self.prune_event_queues(now);
+        self.ready_windows.insert(label.to_string());
+        self.pending_events.remove(label).map(Vec::from).unwrap_or_default()
+    }
+
+    pub fn queue_event(&mut self, label: &str, event: &str, payload: serde_json::Value, now: u64) {
+        self.prune_event_queues(now);
+        self.pending_events.entry(label.to_string()).or_default().push_back(QueuedEvent {
+            event: event.to_string(),
+            payload,
+            queued_at: now,
+        });
+    }
+
+    fn prune_event_queues(&mut self, now: u64) {
+        let ttl = self.event_queue_ttl_ms;
+        for queue in self.pending_events.values_mut() {
+            queue.retain(|queued| now.saturating_sub(queued.queued_at) <= ttl);
+        }
+        self.pending_events.retain(|_, queue| !queue.is_empty());
+    }
+
+    pub fn set_close_confirmation(&mut self, label: &str, enabled: bool) -> Result<(), WindowError> {
+        if !self.windows.contains_key(label) {
+            return Err(WindowError::NotFound { label: label.to_string() });
+        }
+        if enabled {
+            self.close_confirmation.insert(label.to_string());
+        } else {
+            self.close_confirmation.remove(label);
+            self.pending_closes.remove(label);
+        }
+        Ok(())
+    }
+
+    pub fn requires_close_confirmation(&self, label: &str) -> bool {
+        self.close_confirmation.contains(label)
+    }
+
+    pub fn request_close(&mut self, label: &str, now: u64) -> CloseDecision {
+        if !self.close_confirmation.contains(label) {
+            return CloseDecision::Proceed;
+        }
+        if self.pending_closes.contains_key(label) {
+            return CloseDecision::AlreadyAsked;
+        }
+        self.pending_closes.insert(label.to_string(), now);
+        CloseDecision::Ask { requested_at: now }
+    }
+
+    // The frontend answered; returns whether a request was pending
+    pub fn resolve_close(&mut self, label: &str) -> bool {
+        self.pending_closes.remove(label).is_some()
+    }
+
+    // True if the request made at `requested_at` is still unanswered, clearing it
+    pub fn expire_close(&mut self, label: &str, requested_at: u64) -> bool {
+        if self.pending_closes.get(label) == Some(&requested_at) {
+            self.pending_closes.remove(label);
+            return true;
+        }
+        false
+    }
+
+    // Marks every visible, non-minimized window as minimized and remembers which ones they
+    // were. Calling it again while everything is already down keeps the original snapshot.
+    pub fn begin_show_desktop(&mut self) -> Vec<String> {
+        let labels: Vec<String> = self.get_visible_windows_by_z_order().into_iter()
+            .filter(|w| !w.is_minimized)
+            .map(|w| w.label.clone())
+            .collect();
+
+        if labels.is_empty() {
+            return labels;
+        }
+
+        for label in &labels {
+            if let Some(window) = self.windows.get_mut(label) {
+                window.is_minimized = true;
+            }
+        }
+        self.show_desktop = Some(ShowDesktopSnapshot {
+            labels: labels.clone(),
+            focused: self.focused_window.clone(),
+        });
+
+        labels
+    }
+
+    // Takes the snapshot, dropping windows closed since, and marks them restored
+    pub fn end_show_desktop(&mut self) -> ShowDesktopSnapshot {
+        let mut snapshot = self.show_desktop.take().unwrap_or_default();
+        snapshot.labels.retain(|label| self.windows.contains_key(label));
+        if snapshot.focused.as_ref().is_some_and(|label| !self.windows.contains_key(label)) {
+            snapshot.focused = None;
+        }
+
+        for label in &snapshot.labels {
+            if let Some(window) = self.windows.get_mut(label) {
+                window.is_minimized = false;
+            }
+        }
+
+        snapshot
+    }
+
+    pub fn set_window_visible(&mut self, label: &str, visible: bool) {
+        let Some(window) = self.windows.get_mut(label) else {
+            return;
+        };
+        window.is_visible = visible;
+        // Showing or hiding it explicitly settles a pending reveal either way
+        self.pending_reveals.remove(label);
+
+        if visible {
+            self.focus_window(label);
+        } else {
+            self.release_focus(label);
+        }
+    }
+
+    pub fn focus_window(&mut self, label: &str) {
+        self.focus_window_at(label, now_millis());
+    }
+
+    // Focus changes during a cycle don't count as "use" until the cycle ends
+    pub fn focus_window_at(&mut self, label: &str, now: u64) {
+        self.expire_cycle_session(now);
+        let cycling = self.cycle_session.is_some();
+
+        // Unfocus previously focused window
+        if let Some(prev_focused) = &self.focused_window {
+            if let Some(prev_window) = self.windows.get_mut(prev_focused) {
+                prev_window.is_focused = false;
+            }
+        }
+
+        // Focus new window
+        if !self.windows.contains_key(label) {
+            return;
+        }
+        self.app_focused = true;
+        let z_order = self.next_z_order();
+        if let Some(window) = self.windows.get_mut(label) {
+            window.is_focused = true;
+            window.needs_attention = false;
+            if !cycling {
+                window.last_focused_at = now;
+            }
+            window.z_order = z_order;
+            self.focused_window = Some(label.to_string());
+            if !cycling {
+                self.push_focus_history(label);
+            }
+        }
+    }
+
+    // Returns the window to focus next, starting a cycle session if none is active
+    pub fn cycle_step(
+        &mut self,
+        mode: CycleMode,
+        forward: bool,
+        include_hidden: bool,
+        window_type: Option<&str>,
+        now: u64,
+    ) -> Option<String> {
+        self.expire_cycle_session(now);
+        // Switching to a different type's shortcut mid-cycle starts over
+        if self.cycle_session.as_ref().is_some_and(|session| session.window_type.as_deref() != window_type) {
+            self.end_cycle(now);
+        }
+
+        if self.cycle_session.is_none() {
+            let ordered = match mode {
+                CycleMode::ZOrder => self.get_windows_by_z_order(),
+                CycleMode::MostRecentlyUsed => self.get_windows_by_recent_use(),
+            };
+            let candidates: Vec<String> = ordered.into_iter()
+                .filter(|w| w.config.switcher_visible && (include_hidden || w.is_visible))
+                .filter(|w| self.blocking_modal(&w.label).is_none())
+                .filter(|w| window_type.is_none_or(|t| w.config.window_type == t))
+                .map(|w| w.label.clone())
+                .collect();
+            if candidates.is_empty() {
+                return None;
+            }
+            let start = self.focused_window.as_ref()
+                .and_then(|focused| candidates.iter().position(|c| c == focused));
+            let first = candidates.first().cloned();
+            self.cycle_session = Some(CycleSession {
+                window_type: window_type.map(str::to_string),
+                candidates,
+                index: start.unwrap_or(0),
+                last_step_at: now,
+            });
+            if start.is_none() {
+                return first;
+            }
+        }
+
+        let windows = &self.windows;
+        let session = self.cycle_session.as_mut()?;
+        session.last_step_at = now;
+        session.candidates.retain(|label| windows.contains_key(label));
+        let len = session.candidates.len();
+        if len == 0 {
+            self.cycle_session = None;
+            return None;
+        }
+        let index = session.index.min(len - 1);
+        session.index = if forward { (index + 1) % len } else { (index + len - 1) % len };
+        Some(session.candidates[session.index].clone())
+    }
+
+    // Commits the cycle: the window it landed on becomes the most recently used
+    pub fn end_cycle(&mut self, now: u64) {
+        if self.cycle_session.take().is_none() {
+            return;
+        }
+        if let Some(label) = self.focused_window.clone() {
+            if let Some(window) = self.windows.get_mut(&label) {
+                window.last_focused_at = now;
+            }
+            self.push_focus_history(&label);
+        }
+    }
+
+    fn expire_cycle_session(&mut self, now: u64) {
+        let expired = self.cycle_session.as_ref()
+            .is_some_and(|session| now.saturating_sub(session.last_step_at) > CYCLE_SESSION_TIMEOUT_MS);
+        if expired {
+            self.end_cycle(now);
+        }
+    }
+
+    pub fn get_visible_windows_by_z_order(&self) -> Vec<&WindowState> {
+        self.get_windows_by_z_order().into_iter().filter(|w| w.is_visible).collect()
+    }
+
+    pub fn get_windows_by_z_order(&self) -> Vec<&WindowState> {
+        let mut windows: Vec<&WindowState> = self.windows.values().collect();
+        windows.sort_by_key(|w| std::cmp::Reverse(w.z_order));
+        windows
+    }
+
+    // Topmost shown window whose bounds (label -> x, y, width, height) contain the point;
+    // windows without bounds are skipped
+    pub fn window_at_point(&self, x: i32, y: i32, bounds: &HashMap<String, (i32, i32, u32, u32)>) -> Option<String> {
+        self.get_windows_by_z_order().into_iter()
+            .filter(|w| w.is_visible && !w.is_minimized)
+            .find(|w| bounds.get(&w.label).is_some_and(|&(left, top, width, height)| {
+                let (x, y) = (x as i64, y as i64);
+                x >= left as i64 && x < left as i64 + width as i64 && y >= top as i64 && y < top as i64 + height as i64
+            }))
+            .map(|w| w.label.clone())
+    }
+
+    // Most-recently-used first; unlike z-order this is never touched by stacking changes
+    pub fn get_windows_by_recent_use(&self) -> Vec<&WindowState> {
+        let mut windows: Vec<&WindowState> = self.windows.values().collect();
+        windows.sort_by(|a, b| {
+            b.last_focused_at.cmp(&a.last_focused_at)
+                .then_with(|| b.created_at.cmp(&a.created_at))
+                .then_with(|| a.label.cmp(&b.label))
+        });
+        windows
+    }
+
+    pub fn get_labels_of_type(&self, window_type: &str) -> Vec<String> {
+        self.get_windows_by_z_order().into_iter()
+            .filter(|w| w.config.window_type == window_type)
+            .map(|w| w.label.clone())
+            .collect()
+    }
+
+    fn next_z_order(&mut self) -> u32 {
+        if self.z_order_counter >= Z_ORDER_RENORMALIZE_AT {
+            self.renormalize_z_order();
+        }
+        self.z_order_counter += 1;
+        self.z_order_counter
+    }
+
+    // Labels from the bottom of the stack to the top
+    fn stacking_order(&self) -> Vec<String> {
+        self.get_windows_by_z_order().into_iter().rev().map(|w| w.label.clone()).collect()
+    }
+
+    // Renumbers z_order 1..=n in the given bottom-to-top order
+    fn restack(&mut self, bottom_to_top: &[String]) {
+        for (index, label) in bottom_to_top.iter().enumerate() {
+            if let Some(window) = self.windows.get_mut(label) {
+                window.z_order = index as u32 + 1;
+            }
+        }
+        self.z_order_counter = bottom_to_top.len() as u32;
+    }
+
+    fn renormalize_z_order(&mut self) {
+        let order = self.stacking_order();
+        self.restack(&order);
+    }
+
+    pub fn raise_window(&mut self, label: &str) -> Result<(), WindowError> {
+        if !self.windows.contains_key(label) {
+            return Err(WindowError::NotFound { label: label.to_string() });
+        }
+        let z_order = self.next_z_order();
+        if let Some(window) = self.windows.get_mut(label) {
+            window.z_order = z_order;
+        }
+        Ok(())
+    }
+
+    pub fn assign_to_group(&mut self, label: &str, group_id: Option<String>) -> Result<(), WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        window.group_id = group_id;
+        Ok(())
+    }
+
+    // Topmost first, like get_windows_by_z_order
+    pub fn get_group_windows(&self, group_id: &str) -> Vec<&WindowState> {
+        self.get_windows_by_z_order().into_iter()
+            .filter(|w| w.group_id.as_deref() == Some(group_id))
+            .collect()
+    }
+
+    // Moves the group's shown windows above everything else, keeping their order among
+    // themselves; returns their labels bottom first for raising natively
+    pub fn raise_group(&mut self, group_id: &str) -> Vec<String> {
+        let labels: Vec<String> = self.get_group_windows(group_id).into_iter()
+            .rev()
+            .filter(|w| w.is_visible)
+            .map(|w| w.label.clone())
+            .collect();
+        for label in &labels {
+            let _ = self.raise_window(label);
+        }
+        labels
+    }
+
+    // The member to hand focus back to after raising the group
+    pub fn group_focus_target(&self, group_id: &str) -> Option<String> {
+        self.get_group_windows(group_id).into_iter()
+            .filter(|w| w.is_visible)
+            .max_by_key(|w| (w.last_focused_at, w.z_order))
+            .map(|w| w.label.clone())
+    }
+
+    pub fn lower_window(&mut self, label: &str) -> Result<(), WindowError> {
+        if !self.windows.contains_key(label) {
+            return Err(WindowError::NotFound { label: label.to_string() });
+        }
+        let mut order = self.stacking_order();
+        order.retain(|l| l != label);
+        order.insert(0, label.to_string());
+        self.restack(&order);
+        Ok(())
+    }
+
+    pub fn place_window(&mut self, label: &str, placement: ZPlacement, target: &str) -> Result<(), WindowError> {
+        for required in [label, target] {
+            if !self.windows.contains_key(required) {
+                return Err(WindowError::NotFound { label: required.to_string() });
+            }
+        }
+        if label == target {
+            return Ok(());
+        }
+        let mut order = self.stacking_order();
+        order.retain(|l| l != label);
+        let target_index = order.iter().position(|l| l == target).unwrap_or(0);
+        let index = match placement {
+            ZPlacement::Above => target_index + 1,
+            ZPlacement::Below => target_index,
+        };
+        order.insert(index, label.to_string());
+        self.restack(&order);
+        Ok(())
+    }
+
+    // Fails if another window of `window_type` would push it past its limit;
+    // `exclude` is the window being created or re-typed
+    pub fn check_type_limit(&self, window_type: &str, exclude: Option<&str>) -> Result<(), WindowError> {
+        let Some(&limit) = self.type_limits.get(window_type) else {
+            return Ok(());
+        };
+        let existing: Vec<String> = self.get_labels_of_type(window_type).into_iter()
+            .filter(|label| Some(label.as_str()) != exclude)
+            .collect();
+        if existing.len() >= limit {
+            return Err(WindowError::TypeLimitReached {
+                window_type: window_type.to_string(),
+                limit,
+                count: existing.len(),
+                existing,
+            });
+        }
+        Ok(())
+    }
+
+    // Caller-supplied labels must be valid and not clash with an open window
+    pub fn check_label(&self, label: &str) -> Result<(), WindowError> {
+        validate_window_label(label)?;
+        if self.windows.contains_key(label) || self.unmanaged.contains_key(label) {
+            return Err(WindowError::LabelTaken { label: label.to_string() });
+        }
+        Ok(())
+    }
+
+    // "<window_type>_<timestamp>", bumping the timestamp when two windows are created in the same millisecond
+    pub fn unique_label(&self, window_type: &str, now_ms: u128) -> String {
+        (now_ms..)
+            .map(|stamp| format!("{}_{}", window_type, stamp))
+            .find(|label| !self.windows.contains_key(label) && !self.unmanaged.contains_key(label))
+            .unwrap()
+    }
+
+    // An open window showing the same page as a saved one, so a restore can skip it; the
+    // oldest match wins and labels in `claimed` are passed over
+    pub fn reusable_window(&self, window_type: &str, url: Option<&str>, claimed: &[String]) -> Option<String> {
+        self.windows.values()
+            .filter(|w| w.config.window_type == window_type && w.config.url.as_deref() == url)
+            .filter(|w| !claimed.contains(&w.label))
+            .min_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.label.cmp(&b.label)))
+            .map(|w| w.label.clone())
+    }
+
+    pub fn oldest_of_type(&self, window_type: &str) -> Option<String> {
+        self.windows.values()
+            .filter(|w| w.config.window_type == window_type)
+            .min_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.label.cmp(&b.label)))
+            .map(|w| w.label.clone())
+    }
+
+    pub fn set_type_limit(&mut self, window_type: &str, limit: Option<usize>) {
+        match limit {
+            Some(limit) => self.type_limits.insert(window_type.to_string(), limit),
+            None => self.type_limits.remove(window_type),
+        };
+    }
+
+    pub fn set_window_type(&mut self, label: &str, new_type: &str) -> Result<(), WindowError> {
+        let current_type = match self.windows.get(label) {
+            Some(window) => window.config.window_type.clone(),
+            None => return Err(WindowError::NotFound { label: label.to_string() }),
+        };
+        if current_type == new_type {
+            return Ok(());
+        }
+        self.check_type_limit(new_type, Some(label))?;
+        if let Some(window) = self.windows.get_mut(label) {
+            window.config.window_type = new_type.to_string();
+        }
+        Ok(())
+    }
+
+    pub fn get_window(&self, label: &str) -> Option<&WindowState> {
+        self.windows.get(label)
+    }
+
+    pub fn add_unmanaged(&mut self, label: String, config: WindowConfig) {
+        self.unmanaged.insert(label.clone(), WindowState::new(label, config, 0, now_millis()));
+    }
+
+    // Whether the registry agrees with Tauri (`live`) that a window exists. Unmanaged windows count as tracked
+    fn presence(&self, label: &str, live: bool) -> WindowPresence {
+        let tracked = self.windows.contains_key(label) || self.unmanaged.contains_key(label);
+        match (tracked, live) {
+            (true, true) => WindowPresence::Present,
+            (false, false) => WindowPresence::Absent,
+            (true, false) => WindowPresence::RegistryOnly,
+            (false, true) => WindowPresence::LiveOnly,
+        }
+    }
+
+    pub fn is_unmanaged(&self, label: &str) -> bool {
+        self.unmanaged.contains_key(label)
+    }
+
+    pub fn get_unmanaged(&self, label: &str) -> Option<&WindowState> {
+        self.unmanaged.get(label)
+    }
+
+    // Sorted by label
+    pub fn unmanaged_windows(&self) -> Vec<&WindowState> {
+        let mut windows: Vec<&WindowState> = self.unmanaged.values().collect();
+        windows.sort_by(|a, b| a.label.cmp(&b.label));
+        windows
+    }
+
+    // Starts tracking an unmanaged window as if it had just been created managed
+    pub fn adopt_window(&mut self, label: &str) -> Result<(), WindowError> {
+        let window = self.unmanaged.remove(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        let config = WindowConfig { managed: true, ..window.config };
+        self.add_window(label.to_string(), config);
+        Ok(())
+    }
+
+    // A focused window that has since been minimized or hidden behind our back (show desktop,
+    // a missed event) isn't reported; the topmost window that could hold focus is instead
+    pub fn get_focused_window(&self) -> Option<&WindowState> {
+        let focused = self.focused_window.as_ref().and_then(|label| self.windows.get(label))?;
+        if focused.is_visible && !focused.is_minimized {
+            Some(focused)
+        } else {
+            self.top_focusable_window()
+        }
+    }
+
+    // sync_with_live_windows with a config inferred from each live window, which newly
+    // tracked ones take instead of one synthesized from the label alone. Their physical
+    // bounds are left for the caller to read off the live window.
+    pub fn reconcile(&mut self, live: Vec<(String, WindowConfig)>) -> RegistrySyncReport {
+        let live_labels: Vec<String> = live.iter().map(|(label, _)| label.clone()).collect();
+        let report = self.sync_with_live_windows(&live_labels);
+        for (label, config) in live {
+            if !report.added.contains(&label) {
+                continue;
+            }
+            if let Some(window) = self.windows.get_mut(&label) {
+                window.config = config;
+            }
+        }
+        report
+    }
+
+    // Drops entries with no live window and tracks live windows we don't know about yet
+    pub fn sync_with_live_windows(&mut self, live_labels: &[String]) -> RegistrySyncReport {
+        let mut report = RegistrySyncReport::default();
+
+        let mut stale: Vec<String> = self.windows.keys()
+            .filter(|label| !live_labels.contains(label))
+            .cloned()
+            .collect();
+        stale.sort();
+        for label in &stale {
+            self.remove_window(label);
+        }
+        report.removed = stale;
+        self.unmanaged.retain(|label, _| live_labels.contains(label));
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        let mut unknown: Vec<String> = live_labels.iter()
+            .filter(|label| !self.windows.contains_key(*label) && !self.unmanaged.contains_key(*label))
+            .cloned()
+            .collect();
+        unknown.sort();
+        for label in &unknown {
+            // Adopted windows take the next z-order, like any new window, but don't steal focus
+            let z_order = self.next_z_order();
+            self.windows.insert(label.clone(), WindowState {
+                label: label.clone(),
+                config: WindowConfig::synthesized(label),
+                z_order,
+                is_focused: false,
+                is_minimized: false,
+                is_maximized: false,
+                is_fullscreen: false,
+                pre_fullscreen_bounds: None,
+                needs_attention: false,
+                zoom: 1.0,
+                monitor_id: None,
+                group_id: None,
+                click_through: false,
+                metadata: HashMap::new(),
+                lost_monitor_id: None,
+                spanning: false,
+                displaced_from: None,
+                scale_factor: 1.0,
+                logical_bounds: None,
+                created_at: now,
+                last_focused_at: now,
+                current_x: None,
+                current_y: None,
+                current_width: None,
+                current_height: None,
+                is_visible: true,
+            });
+        }
+        report.added = unknown;
+
+        report
+    }
+
+    pub fn update_window_position(&mut self, label: &str, x: i32, y: i32) {
+        if let Some(window) = self.windows.get_mut(label) {
+            window.current_x = Some(x);
+            window.current_y = Some(y);
+        }
+    }
+
+    pub fn update_window_size(&mut self, label: &str, width: u32, height: u32) {
+        if let Some(window) = self.windows.get_mut(label) {
+            window.current_width = Some(width);
+            window.current_height = Some(height);
+        }
+    }
+
+    // Assigns the monitor showing most of the window, based on its tracked geometry
+    pub fn refresh_monitor_assignment(&mut self, label: &str, monitors: &[MonitorInfo]) {
+        if let Some(window) = self.windows.get_mut(label) {
+            let (Some(x), Some(y), Some(width), Some(height)) =
+                (window.current_x, window.current_y, window.current_width, window.current_height)
+            else {
+                return;
+            };
+            if let Some(monitor) = monitor_for_rect(x as f64, y as f64, width as f64, height as f64, monitors) {
+                window.monitor_id = Some(monitor.id.clone());
+                window.scale_factor = monitor.scale_factor;
+                window.logical_bounds = Some(physical_to_logical((x, y, width, height), monitor));
+            }
+            window.spanning = monitors.iter()
+                .filter(|m| overlap_area(x as f64, y as f64, width as f64, height as f64, m) > 0.0)
+                .count() > 1;
+        }
+    }
+
+    // A ScaleFactorChanged event. The window may still straddle two displays, so the factor
+    // the OS reports wins over the one of the monitor showing most of it. Returns the change
+    // if the factor actually moved
+    pub fn observe_scale_change(
+        &mut self,
+        label: &str,
+        width: u32,
+        height: u32,
+        scale_factor: f64,
+        monitors: &[MonitorInfo],
+    ) -> Option<WindowScaleChange> {
+        let old = self.windows.get(label)?.scale_factor;
+        self.update_window_size(label, width, height);
+        self.refresh_monitor_assignment(label, monitors);
+
+        let window = self.windows.get_mut(label)?;
+        window.scale_factor = scale_factor;
+        if let (Some(x), Some(y), Some(width), Some(height)) =
+            (window.current_x, window.current_y, window.current_width, window.current_height)
+        {
+            let monitor = monitor_for_rect(x as f64, y as f64, width as f64, height as f64, monitors)
+                .map(|m| MonitorInfo { scale_factor, ..m.clone() });
+            if let Some(monitor) = monitor {
+                window.logical_bounds = Some(physical_to_logical((x, y, width, height), &monitor));
+            }
+        }
+
+        ((old - scale_factor).abs() > f64::EPSILON).then(|| WindowScaleChange {
+            label: label.to_string(),
+            old_scale_factor: old,
+            new_scale_factor: scale_factor,
+        })
+    }
+
+    // Flags windows whose monitor was disconnected and reassigns every window to what's left.
+    // Returns the newly flagged labels
+    pub fn apply_monitor_changes(&mut self, changes: &MonitorChanges) -> Vec<String> {
+        let mut displaced = Vec::new();
+        for window in self.windows.values_mut() {
+            if window.lost_monitor_id.as_ref().is_some_and(|id| changes.added.iter().any(|m| &m.id == id)) {
+                window.lost_monitor_id = None;
+            }
+            if let Some(id) = &window.monitor_id {
+                if changes.removed.iter().any(|m| &m.id == id) {
+                    window.lost_monitor_id = Some(id.clone());
+                    displaced.push(window.label.clone());
+                }
+            }
+        }
+        let labels: Vec<String> = self.windows.keys().cloned().collect();
+        for label in &labels {
+            self.refresh_monitor_assignment(label, &changes.monitors);
+        }
+        displaced.sort();
+        displaced
+    }
+
+    // Cascades windows flagged by apply_monitor_changes onto the primary monitor's work area,
+    // bottom of the stack first, and remembers where each one was. Returns the new bounds
+    pub fn rehome_displaced(&mut self, labels: &[String], changes: &MonitorChanges) -> Vec<(String, (i32, i32, u32, u32))> {
+        let Some(primary) = resolve_monitor(None, &changes.monitors).cloned() else {
+            return Vec::new();
+        };
+        let windows: Vec<(String, u32, u32)> = self.get_windows_by_z_order().into_iter()
+            .rev()
+            .filter(|w| labels.contains(&w.label))
+            .filter_map(|w| {
+                let from = changes.removed.iter().find(|m| w.lost_monitor_id.as_ref() == Some(&m.id))?;
+                let (width, height) = size_on_monitor(w.current_width? as f64, w.current_height? as f64, from, &primary);
+                Some((w.label.clone(), width, height))
+            })
+            .collect();
+        if windows.is_empty() {
+            return Vec::new();
+        }
+
+        // Leave room for every step so windows as big as the work area don't end up stacked
+        let work = primary.usable(false);
+        let room = CASCADE_OFFSET * (windows.len() as u32 - 1);
+        let max_width = work.width.saturating_sub(room).max(1);
+        let max_height = work.height.saturating_sub(room).max(1);
+        let windows: Vec<(String, u32, u32)> = windows.into_iter()
+            .map(|(label, width, height)| (label, width.min(max_width), height.min(max_height)))
+            .collect();
+        let widest = windows.iter().map(|w| w.1).max().unwrap_or(0);
+        let tallest = windows.iter().map(|w| w.2).max().unwrap_or(0);
+        let positions = cascade_layout(windows.len(), &work, widest, tallest, CASCADE_OFFSET);
+
+        let mut moves = Vec::new();
+        for ((label, width, height), (x, y)) in windows.into_iter().zip(positions) {
+            let Some(window) = self.windows.get_mut(&label) else {
+                continue;
+            };
+            // A window displaced twice goes back to where it started
+            if window.displaced_from.is_none() {
+                if let (Some(id), Some(cx), Some(cy), Some(cw), Some(ch)) = (
+                    window.lost_monitor_id.clone(),
+                    window.current_x,
+                    window.current_y,
+                    window.current_width,
+                    window.current_height,
+                ) {
+                    window.displaced_from = Some(DisplacedFrom { monitor_id: id, bounds: (cx, cy, cw, ch) });
+                }
+            }
+            self.update_window_position(&label, x, y);
+            self.update_window_size(&label, width, height);
+            self.refresh_monitor_assignment(&label, &changes.monitors);
+            moves.push((label, (x, y, width, height)));
+        }
+        moves
+    }
+
+    // Rehomed windows whose original monitor is connected again
+    pub fn restorable_windows(&self, monitors: &[MonitorInfo]) -> Vec<String> {
+        let mut labels: Vec<String> = self.windows.values()
+            .filter(|w| w.displaced_from.as_ref().is_some_and(|from| monitors.iter().any(|m| m.id == from.monitor_id)))
+            .map(|w| w.label.clone())
+            .collect();
+        labels.sort();
+        labels
+    }
+
+    // Puts restorable windows back where they were before being rehomed. Returns the bounds to apply
+    pub fn restore_displaced(&mut self, monitors: &[MonitorInfo]) -> Vec<(String, (i32, i32, u32, u32))> {
+        let mut moves = Vec::new();
+        for label in self.restorable_windows(monitors) {
+            let Some(from) = self.windows.get_mut(&label).and_then(|w| w.displaced_from.take()) else {
+                continue;
+            };
+            let (x, y, width, height) = from.bounds;
+            self.update_window_position(&label, x, y);
+            self.update_window_size(&label, width, height);
+            self.refresh_monitor_assignment(&label, monitors);
+            moves.push((label, from.bounds));
+        }
+        moves
+    }
+
+    // Windows that are no longer sufficiently on screen, with the bounds that would bring them back
+    pub fn offscreen_windows(&self, monitors: &[MonitorInfo]) -> Vec<(String, (i32, i32, u32, u32))> {
+        let mut moves: Vec<(String, (i32, i32, u32, u32))> = self.windows.values()
+            .filter(|w| w.is_visible && !w.config.allow_offscreen)
+            .filter_map(|w| {
+                let current = (w.current_x?, w.current_y?, w.current_width?, w.current_height?);
+                let (x, y, width, height) = current;
+                let clamped = clamp_rect_to_visible(x as f64, y as f64, width as f64, height as f64, monitors);
+                (clamped != current).then(|| (w.label.clone(), clamped))
+            })
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    // Where a window being dragged should be pulled to, if it's close enough to an edge
+    pub fn magnetic_snap_target(&self, label: &str, monitors: &[MonitorInfo]) -> Option<(i32, i32)> {
+        if !self.magnetic_snap_enabled {
+            return None;
+        }
+
+        let window = self.windows.get(label)?;
+        let moving = (window.current_x?, window.current_y?, window.current_width?, window.current_height?);
+
+        let mut neighbors: Vec<(i32, i32, u32, u32)> = self.windows.values()
+            .filter(|w| w.label != label && w.is_visible && !w.is_minimized)
+            .filter_map(|w| Some((w.current_x?, w.current_y?, w.current_width?, w.current_height?)))
+            .collect();
+        neighbors.extend(monitors.iter().map(|m| (m.x, m.y, m.width, m.height)));
+
+        let snapped = magnetic_snap(moving, &neighbors, self.magnetic_snap_threshold);
+        (snapped != (moving.0, moving.1)).then_some(snapped)
+    }
+
+    // Everything except the ghost count, which needs the app handle
+    pub fn stats(&self, now: u64) -> RegistryStats {
+        let mut windows_by_type = HashMap::new();
+        for window in self.windows.values() {
+            *windows_by_type.entry(window.config.window_type.clone()).or_insert(0) += 1;
+        }
+
+        RegistryStats {
+            total_windows: self.windows.len(),
+            windows_by_type,
+            focused_window: self.focused_window.clone(),
+            highest_z_order: self.windows.values().map(|w| w.z_order).max().unwrap_or(0),
+            z_order_counter: self.z_order_counter,
+            ghost_windows: 0,
+            oldest_window_age_ms: self.windows.values()
+                .map(|w| now.saturating_sub(w.created_at))
+                .max(),
+        }
+    }
+
+    pub fn update_window_state(&mut self, label: &str, is_minimized: Option<bool>, is_maximized: Option<bool>, monitor_id: Option<String>) {
+        if let Some(window) = self.windows.get_mut(label) {
+            if let Some(minimized) = is_minimized {
+                window.is_minimized = minimized;
+            }
+            if let Some(maximized) = is_maximized {
+                window.is_maximized = maximized;
+            }
+            if let Some(monitor) = monitor_id {
+                window.monitor_id = Some(monitor);
+            }
+        }
+        if is_minimized == Some(true) {
+            self.release_focus_on_minimize(label);
+        }
+    }
+
+    pub fn set_needs_attention(&mut self, label: &str, needs_attention: bool) -> Result<(), WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        window.needs_attention = needs_attention;
+        Ok(())
+    }
+
+    pub fn enter_fullscreen(&mut self, label: &str, bounds: (i32, i32, u32, u32)) {
+        if let Some(window) = self.windows.get_mut(label) {
+            window.is_fullscreen = true;
+            window.pre_fullscreen_bounds = Some(bounds);
+        }
+    }
+
+    // Returns the bounds to restore, if they were recorded on entry
+    pub fn exit_fullscreen(&mut self, label: &str) -> Option<(i32, i32, u32, u32)> {
+        let window = self.windows.get_mut(label)?;
+        window.is_fullscreen = false;
+        window.pre_fullscreen_bounds.take()
+    }
+
+    pub fn set_window_title(&mut self, label: &str, title: &str) -> Result<(), WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        window.config.title = title.to_string();
+        Ok(())
+    }
+
+    // Transparent windows have to stay undecorated; see WindowConfig::validate
+    pub fn set_decorations(&mut self, label: &str, enabled: bool) -> Result<(), WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        if enabled && window.config.transparent {
+            return Err(WindowError::Failed {
+                label: label.to_string(),
+                message: "transparent windows can't have decorations".to_string(),
+            });
+        }
+        window.config.decorations = enabled;
+        Ok(())
+    }
+
+    pub fn set_skip_taskbar(&mut self, label: &str, skip: bool) -> Result<(), WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        window.config.skip_taskbar = skip;
+        Ok(())
+    }
+
+    pub fn set_window_url(&mut self, label: &str, url: &str) -> Result<(), WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        window.config.url = Some(url.to_string());
+        Ok(())
+    }
+
+    // Returns the zoom actually stored, after clamping
+    pub fn set_zoom(&mut self, label: &str, factor: f64) -> Result<f64, WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        window.zoom = clamp_zoom(factor);
+        Ok(window.zoom)
+    }
+
+    pub fn set_click_through(&mut self, label: &str, enabled: bool) -> Result<(), WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        window.click_through = enabled;
+        Ok(())
+    }
+
+    // A null value removes the key
+    pub fn set_metadata(&mut self, label: &str, key: &str, value: serde_json::Value) -> Result<(), WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        if value.is_null() {
+            window.metadata.remove(key);
+        } else {
+            window.metadata.insert(key.to_string(), value);
+        }
+        Ok(())
+    }
+
+    pub fn get_metadata(&self, label: &str, key: &str) -> Result<Option<serde_json::Value>, WindowError> {
+        let window = self.windows.get(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        Ok(window.metadata.get(key).cloned())
+    }
+
+    pub fn set_opacity(&mut self, label: &str, opacity: f64) -> Result<f64, WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        window.config.opacity = clamp_opacity(opacity);
+        Ok(window.config.opacity)
+    }
+
+    pub fn set_window_theme(&mut self, label: &str, theme: &str) -> Result<(), WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        window.config.theme = Some(theme.to_string());
+        Ok(())
+    }
+
+    // Replaces a window's size limits; they live on its config so saved layouts keep them
+    pub fn set_size_limits(&mut self, label: &str, min: Option<(u32, u32)>, max: Option<(u32, u32)>) -> Result<(), WindowError> {
+        self.set_constraints(
+            label,
+            min.map(|(width, _)| width as f64),
+            min.map(|(_, height)| height as f64),
+            max.map(|(width, _)| width as f64),
+            max.map(|(_, height)| height as f64),
+        )
+    }
+
+    // Like set_size_limits, but each bound is set or cleared on its own and gets the
+    // same checks as a new window's config
+    pub fn set_constraints(
+        &mut self,
+        label: &str,
+        min_width: Option<f64>,
+        min_height: Option<f64>,
+        max_width: Option<f64>,
+        max_height: Option<f64>,
+    ) -> Result<(), WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        let config = WindowConfig { min_width, min_height, max_width, max_height, ..window.config.clone() };
+        config.validate().map_err(|errors| WindowError::InvalidConfig { errors })?;
+        window.config = config;
+        Ok(())
+    }
+
+    pub fn set_resizable(&mut self, label: &str, resizable: bool) -> Result<(), WindowError> {
+        let window = self.windows.get_mut(label)
+            .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+        window.config.resizable = resizable;
+        Ok(())
+    }
+
+    pub fn apply_native_flags(&mut self, label: &str, flags: NativeWindowFlags) {
+        if let Some(window) = self.windows.get_mut(label) {
+            window.is_minimized = flags.minimized;
+            window.is_maximized = flags.maximized;
+            window.is_fullscreen = flags.fullscreen;
+        }
+        if flags.minimized {
+            self.release_focus_on_minimize(label);
+        }
+    }
+
+    // A Resized event with the flags read right after it. Only normal-state sizes are
+    // recorded, so minimizing (reported as 0x0 on Windows) or maximizing doesn't
+    // overwrite the bounds a restored layout should come back to.
+    pub fn observe_resize(&mut self, label: &str, width: u32, height: u32, flags: NativeWindowFlags) {
+        self.apply_native_flags(label, flags);
+        let normal = !flags.minimized && !flags.maximized && !flags.fullscreen;
+        if normal && width > 0 && height > 0 {
+            self.update_window_size(label, width, height);
+        }
+    }
+}
+
+fn native_window_flags(window: &tauri::WebviewWindow) -> tauri::Result<NativeWindowFlags> {
+    Ok(NativeWindowFlags {
+        minimized: window.is_minimized()?,
+        maximized: window.is_maximized()?,
+        fullscreen: window.is_fullscreen()?,
+    })
+}
+
+type WindowRegistryState = Mutex<WindowRegistry>;
+
+// Position for a moving rect (x, y, width, height) after pulling each axis onto the
+// closest neighbour edge within `threshold`; neighbours only attract along an axis
+// when they're within reach on the other one
+fn magnetic_snap(moving: (i32, i32, u32, u32), neighbors: &[(i32, i32, u32, u32)], threshold: u32) -> (i32, i32) {
+    fn consider(best: &mut Option<i32>, delta: i32, threshold: i32) {
+        if delta.abs() <= threshold && best.is_none_or(|b| delta.abs() < b.abs()) {
+            *best = Some(delta);
+        }
+    }
+
+    let (x, y, width, height) = moving;
+    let (right, bottom) = (x + width as i32, y + height as i32);
+    let threshold = threshold as i32;
+    let mut best_dx = None;
+    let mut best_dy = None;
+
+    for &(nx, ny, nw, nh) in neighbors {
+        let (n_right, n_bottom) = (nx + nw as i32, ny + nh as i32);
+
+        if y <= n_bottom + threshold && bottom >= ny - threshold {
+            for delta in [nx - x, n_right - x, nx - right, n_right - right] {
+                consider(&mut best_dx, delta, threshold);
+            }
+        }
+        if x <= n_right + threshold && right >= nx - threshold {
+            for delta in [ny - y, n_bottom - y, ny - bottom, n_bottom - bottom] {
+                consider(&mut best_dy, delta, threshold);
+            }
+        }
+    }
+
+    (x + best_dx.unwrap_or(0), y + best_dy.unwrap_or(0))
+}
+
+// Default minimum spacing between registry writes from Moved/Resized events for one window;
+// set_event_throttle_ms changes it
+const GEOMETRY_UPDATE_INTERVAL_MS: u64 = 100;
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+// Returns true (and records `now`) when at least `interval_ms` has passed since the last accepted update
+fn throttle_elapsed(last: &AtomicU64, now: u64, interval_ms: u64) -> bool {
+    if now.saturating_sub(last.load(Ordering::Relaxed)) < interval_ms {
+        return false;
+    }
+    last.store(now, Ordering::Relaxed);
+    true
+}
+
+// What to do with one Moved/Resized event
+#[derive(Debug, PartialEq)]
+enum Coalesced<T> {
+    Apply(T),
+    // Parked; apply whatever take_pending returns after this many milliseconds
+    FlushAfter(u64),
+    // Parked, replacing an earlier value that already has a flush scheduled
+    Parked,
+}
+
+// Throttles one window's geometry events without losing the last one: the first event in
+// a burst is applied straight away, later ones only keep the newest value, and that is
+// applied when the interval is up, so a drag always ends where it was dropped.
+#[derive(Default)]
+struct EventCoalescer<T> {
+    last_write: AtomicU64,
+    pending: Mutex<Option<T>>,
+    flush_scheduled: AtomicBool,
+}
+
+impl<T> EventCoalescer<T> {
+    fn offer(&self, value: T, now: u64, interval_ms: u64) -> Coalesced<T> {
+        let mut pending = self.pending.lock().unwrap();
+        if !self.flush_scheduled.load(Ordering::Relaxed) && throttle_elapsed(&self.last_write, now, interval_ms) {
+            *pending = None;
+            return Coalesced::Apply(value);
+        }
+        *pending = Some(value);
+        if self.flush_scheduled.swap(true, Ordering::Relaxed) {
+            return Coalesced::Parked;
+        }
+        let since = now.saturating_sub(self.last_write.load(Ordering::Relaxed));
+        Coalesced::FlushAfter(interval_ms.saturating_sub(since))
+    }
+
+    // The newest parked value, which counts as a write at `now`
+    fn take_pending(&self, now: u64) -> Option<T> {
+        let mut pending = self.pending.lock().unwrap();
+        self.flush_scheduled.store(false, Ordering::Relaxed);
+        let value = pending.take()?;
+        self.last_write.store(now, Ordering::Relaxed);
+        Some(value)
+    }
+
+    // For events that must get through at once; anything parked is older, so it's dropped
+    fn bypass(&self, now: u64) {
+        *self.pending.lock().unwrap() = None;
+        self.last_write.store(now, Ordering::Relaxed);
+    }
+}
+
+// Hands a value to `apply` now or once its flush is due
+fn coalesce<T: Send + 'static>(
+    coalescer: &Arc<EventCoalescer<T>>,
+    value: T,
+    interval_ms: u64,
+    apply: impl FnOnce(T) + Send + 'static,
+) {
+    match coalescer.offer(value, now_millis(), interval_ms) {
+        Coalesced::Apply(value) => apply(value),
+        Coalesced::FlushAfter(delay) => {
+            let coalescer = coalescer.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(delay));
+                if let Some(value) = coalescer.take_pending(now_millis()) {
+                    apply(value);
+                }
+            });
+        }
+        Coalesced::Parked => {}
+    }
+}
+
+fn apply_moved(app: &AppHandle, label: &str, position: tauri::PhysicalPosition<i32>) {
+    // Fetched before locking: a cache miss waits on the main thread
+    let monitors = enumerate_monitors(app).unwrap_or_default();
+    let registry_state = app.state::<WindowRegistryState>();
+    let snapped = {
+        let mut registry = registry_state.lock().unwrap();
+        registry.update_window_position(label, position.x, position.y);
+        registry.refresh_monitor_assignment(label, &monitors);
+        registry.magnetic_snap_target(label, &monitors)
+    };
+
+    // The lock is released first: moving the window raises another Moved event
+    if let Some((x, y)) = snapped {
+        if let Some(window) = app.get_webview_window(label) {
+            let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
+        }
+    }
+}
+
+fn apply_resized(app: &AppHandle, label: &str, size: tauri::PhysicalSize<u32>, flags: NativeWindowFlags) {
+    let monitors = enumerate_monitors(app).unwrap_or_default();
+    let registry_state = app.state::<WindowRegistryState>();
+    let mut registry = registry_state.lock().unwrap();
+    registry.observe_resize(label, size.width, size.height, flags);
+    registry.refresh_monitor_assignment(label, &monitors);
+}
+
+#[tauri::command]
+async fn set_event_throttle_ms(
+    interval_ms: u64,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    registry_state.lock().unwrap().event_throttle_ms = interval_ms;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_default_placement(
+    placement: WindowPlacement,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    registry_state.lock().unwrap().default_placement = placement;
+    Ok(())
+}
+
+// Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
+#[tauri::command]
+fn greet(name: &str) -> String {
+    format!("Hello, {}! You've been greeted from Rust!", name)
+}
+
+// Drops a window from the registry and emits `window-closed`. Several close paths can
+// race here (our commands, CloseRequested, Destroyed); only the one that actually
+// removes the entry emits, so the event fires once per window.
+fn forget_window(app: &AppHandle, registry_state: &WindowRegistryState, label: &str) {
+    let removed = registry_state.lock().unwrap().remove_window(label);
+    release_modal_parents(app, registry_state);
+    if removed {
+        let _ = app.emit("window-closed", label);
+    }
+}
+
+// Re-enables parents whose last modal has gone, however it went
+fn release_modal_parents(app: &AppHandle, registry_state: &WindowRegistryState) {
+    let released = registry_state.lock().unwrap().take_released_parents();
+    for label in released {
+        if let Some(window) = app.get_webview_window(&label) {
+            let _ = window.set_enabled(true);
+        }
+    }
+}
+
+// Advanced window creation and management APIs
+#[tauri::command]
+async fn create_app_window(
+    app: AppHandle,
+    window_type: String,
+    config: PartialWindowConfig,
+    label: Option<String>,
+    registry_state: State<'_, WindowRegistryState>,
+    templates_state: State<'_, WindowTemplatesState>,
+) -> Result<String, WindowError> {
+    let template = template_for(&templates_state.lock().unwrap(), &window_type);
+    let created = open_app_window(app, window_type, config.merge_over(&template), label, registry_state).await?;
+    Ok(created.label)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedWindow {
+    #[serde(flatten)]
+    pub window: WindowState,
+    // Problems that didn't stop the window opening, e.g. a missing monitor
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+// Same as create_app_window, but returns the registered WindowState (label, resolved
+// position, z_order, timestamps) so the frontend doesn't have to look it up afterwards
+#[tauri::command]
+async fn create_app_window_v2(
+    app: AppHandle,
+    window_type: String,
+    config: PartialWindowConfig,
+    label: Option<String>,
+    registry_state: State<'_, WindowRegistryState>,
+    templates_state: State<'_, WindowTemplatesState>,
+) -> Result<CreatedWindow, WindowError> {
+    let template = template_for(&templates_state.lock().unwrap(), &window_type);
+    let config = config.merge_over(&template);
+
+    let mut warnings = Vec::new();
+    if let Some(monitor_id) = config.monitor_id.as_deref().filter(|_| config.placement == WindowPlacement::Absolute) {
+        let monitors = enumerate_monitors(&app).unwrap_or_default();
+        warnings.extend(missing_monitor_warning(monitor_id, &monitors));
+    }
+
+    let window = open_app_window(app, window_type, config, label, registry_state).await?;
+    Ok(CreatedWindow { window, warnings })
+}
+
+// create_app_window without the template merge, for callers that already hold a full
+// config. Returns the window's registry entry as it was right after creation.
+async fn open_app_window(
+    app: AppHandle,
+    window_type: String,
+    config: WindowConfig,
+    label: Option<String>,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<WindowState, WindowError> {
+    config.validate().map_err(|errors| WindowError::InvalidConfig { errors })?;
+    if let Some(label) = &label {
+        validate_window_label(label)?;
+    }
+    let mut config = config;
+    config.placement = config.placement_or(&registry_state.lock().unwrap().default_placement);
+    let webview_url = resolve_window_url(
+        config.url.as_deref().unwrap_or("index.html"),
+        &app.state::<UrlAllowlistState>().lock().unwrap(),
+    )?;
+
+    // The type limit is checked before the label is claimed, so a singleton type can still
+    // hand back its existing window under FocusOldest
+    let limit_check = registry_state.lock().unwrap().check_type_limit(&window_type, None);
+    if let Err(error) = limit_check {
+        let oldest = registry_state.lock().unwrap().oldest_of_type(&window_type);
+        match oldest {
+            Some(existing) if config.on_type_limit == TypeLimitBehavior::FocusOldest => {
+                focus_app_window(existing.clone(), app, registry_state.clone()).await
+                    .map_err(|message| WindowError::Failed { label: existing.clone(), message })?;
+                let state = registry_state.lock().unwrap().get_window(&existing).cloned();
+                return state.ok_or(WindowError::NotFound { label: existing });
+            }
+            _ => return Err(error),
+        }
+    }
+
+    let label = {
+        let registry = registry_state.lock().unwrap();
+        match label {
+            Some(label) => registry.check_label(&label).map(|_| label)?,
+            None => registry.unique_label(&window_type, std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis()),
+        }
+    };
+    let focused_monitor_id = registry_state.lock().unwrap()
+        .get_focused_window()
+        .and_then(|w| w.monitor_id.clone());
+    let monitors = enumerate_monitors(&app).unwrap_or_default();
+    // Only ask for the cursor when it matters; without it the primary monitor is used
+    let cursor_monitor_id = (config.placement == WindowPlacement::CenterOnCursorMonitor)
+        .then(|| query_cursor(&app, &monitors).ok())
+        .flatten()
+        .map(|cursor| cursor.monitor.id);
+    let size_monitor = size_target_monitor(
+        &config,
+        focused_monitor_id.as_deref(),
+        cursor_monitor_id.as_deref(),
+        &monitors,
+    );
+    let (width, height) = config.resolve_size(size_monitor);
+    config.set_pixel_size(width, height);
+    let (mut width, mut height) = config.clamp_size(width, height);
+
+    let mut builder = tauri::webview::WebviewWindowBuilder::new(&app, &label, webview_url)
+        .title(&config.title)
+        .inner_size(width, height)
+        .resizable(config.resizable)
+        .minimizable(config.minimizable)
+        .maximizable(config.maximizable)
+        .closable(config.closable)
+        .always_on_top(config.always_on_top)
+        .decorations(config.decorations)
+        .transparent(config.transparent)
+        .focused(config.focus)
+        .fullscreen(config.fullscreen)
+        .theme(config.theme.as_deref().and_then(|theme| parse_theme(theme).ok().flatten()))
+        .skip_taskbar(config.skip_taskbar)
+        .visible(!config.starts_hidden());
+
+    if let Some(color) = config.background_color.as_deref().and_then(|color| parse_color(color).ok()) {
+        builder = builder.background_color(color);
+    }
+    if config.min_width.is_some() || config.min_height.is_some() {
+        builder = builder.min_inner_size(config.min_width.unwrap_or(0.0), config.min_height.unwrap_or(0.0));
+    }
+    if config.max_width.is_some() || config.max_height.is_some() {
+        let open = i32::MAX as f64;
+        builder = builder.max_inner_size(config.max_width.unwrap_or(open), config.max_height.unwrap_or(open));
+    }
+
+    // Nothing in the registry changes until the window has been built
+    let mut cascade = None;
+    let placed = {
+        let registry = registry_state.lock().unwrap();
+        let usable = usable_monitors(&monitors, config.use_full_area);
+        match resolve_monitor(focused_monitor_id.as_deref(), &usable) {
+            Some(monitor) if config.placement == WindowPlacement::Cascade => {
+                let scale = monitor.scale_factor;
+                let spawn = registry.cascade_spawn_for(
+                    &window_type,
+                    (width * scale) as u32,
+                    (height * scale) as u32,
+                    monitor,
+                );
+                let position = (spawn.x as f64 / scale, spawn.y as f64 / scale);
+                cascade = Some(spawn);
+                Some(position)
+            }
+            Some(monitor) if config.placement == WindowPlacement::SmartAvoidOverlap => {
+                let scale = monitor.scale_factor;
+                let (x, y) = registry.spawn_avoiding_overlap(
+                    &window_type,
+                    (width * scale) as u32,
+                    (height * scale) as u32,
+                    monitor,
+                );
+                Some((x as f64 / scale, y as f64 / scale))
+            }
+            _ => match config.monitor_id.as_deref() {
+                Some(monitor_id) if config.placement == WindowPlacement::Absolute => {
+                    monitor_relative_position(monitor_id, config.x, config.y, width, height, &monitors)
+                }
+                _ => resolve_placement(
+                    &config.placement,
+                    width,
+                    height,
+                    focused_monitor_id.as_deref(),
+                    cursor_monitor_id.as_deref(),
+                    &usable,
+                ),
+            },
+        }
+    };
+    let mut exact_position = None;
+    if let Some((x, y)) = placed {
+        builder = builder.position(x, y);
+    } else if let Some((x, y)) = config.x.zip(config.y) {
+        // x/y are in the target monitor's logical pixels (see logical_to_physical); clamping
+        // happens in physical ones
+        let monitors = enumerate_monitors(&app).unwrap_or_default();
+        let usable = usable_monitors(&monitors, config.use_full_area);
+        let requested = logical_rect_to_physical((x, y, width, height), None, &monitors);
+        let (px, py, pw, ph) = if config.allow_offscreen {
+            requested
+        } else {
+            clamp_rect_to_visible(requested.0 as f64, requested.1 as f64, requested.2 as f64, requested.3 as f64, &usable)
+        };
+        let scale = monitor_for_rect(px as f64, py as f64, pw as f64, ph as f64, &monitors).map_or(1.0, |m| m.scale_factor);
+        width = pw as f64 / scale;
+        height = ph as f64 / scale;
+        builder = builder.inner_size(width, height);
+        // The builder converts a logical position with a scale of its own choosing, so the
+        // exact spot is set again once the window exists
+        builder = builder.position(px as f64 / scale, py as f64 / scale);
+        exact_position = Some((px, py));
+    }
+
+    // Owned windows stay above their parent on every platform
+    let modal_parent = match config.parent_label.as_deref().filter(|_| config.modal) {
+        Some(parent_label) => {
+            let parent = app.get_webview_window(parent_label)
+                .ok_or_else(|| WindowError::NotFound { label: parent_label.to_string() })?;
+            builder = builder.parent(&parent)
+                .map_err(|e| WindowError::Failed { label: label.clone(), message: e.to_string() })?;
+            Some(parent)
+        }
+        None => None,
+    };
+
+    let window = builder.build().map_err(|e| WindowError::BuildFailed {
+        label: label.clone(),
+        window_type: window_type.clone(),
+        url: config.url.clone().unwrap_or_else(|| "index.html".to_string()),
+        message: e.to_string(),
+    })?;
+
+    if let Some((x, y)) = exact_position {
+        let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
+    }
+    let config_hidden = config.starts_hidden();
+    if let Some(zoom) = config.zoom {
+        let _ = window.set_zoom(clamp_zoom(zoom));
+    }
+    if config.opacity < 1.0 {
+        let _ = apply_window_opacity(&window, clamp_opacity(config.opacity));
+    }
+    if let Some(parent) = &modal_parent {
+        registry_state.lock().unwrap().block_parent(parent.label());
+        let _ = parent.set_enabled(false);
+    }
+
+    // Add to registry
+    let managed = config.managed;
+    let placement = managed.then(|| read_live_placement(&app, &window));
+    {
+        let mut registry = registry_state.lock().unwrap();
+        if let Some(spawn) = cascade {
+            registry.record_cascade_spawn(&window_type, spawn);
+        }
+        if let Some(placement) = &placement {
+            registry.add_window(label.clone(), config);
+            record_live_placement(&mut registry, &label, placement);
+        } else {
+            registry.add_unmanaged(label.clone(), config);
+        }
+    }
+
+    // Closed again before we got here; nothing to report
+    let created = {
+        let registry = registry_state.lock().unwrap();
+        if managed { registry.get_window(&label) } else { registry.get_unmanaged(&label) }.cloned()
+    }
+    .ok_or_else(|| WindowError::NotFound { label: label.clone() })?;
+    if managed {
+        app.emit("window-created", &created)
+            .map_err(|e| WindowError::Failed { label: label.clone(), message: e.to_string() })?;
+    }
+
+    // A frontend that crashes before revealing its window would leave it invisible forever
+    if config_hidden {
+        let app = app.clone();
+        let label = label.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(REVEAL_WATCHDOG_MS));
+            let registry_state = app.state::<WindowRegistryState>();
+            if registry_state.lock().unwrap().is_reveal_pending(&label) {
+                let _ = reveal_window_now(&app, &registry_state, &label);
+            }
+        });
+    }
+
+    // Set up window event listeners
+    let app_handle = app.clone();
+    let label_clone = label.clone();
+    let moves = Arc::new(EventCoalescer::default());
+    let resizes = Arc::new(EventCoalescer::default());
+    let last_native_flags = AtomicU8::new(NativeWindowFlags::default().bits());
+    window.on_window_event(move |event| {
+        let registry_state = app_handle.state::<WindowRegistryState>();
+        // Untracked windows only need their closes seen; focus and geometry stay out of the registry
+        let closing = matches!(event, tauri::WindowEvent::CloseRequested { .. } | tauri::WindowEvent::Destroyed);
+        let (unmanaged, throttle_ms) = {
+            let registry = registry_state.lock().unwrap();
+            (registry.is_unmanaged(&label_clone), registry.event_throttle_ms)
+        };
+        if !closing && unmanaged {
+            return;
+        }
+        match event {
+            // Drags and resizes fire continuously, so they're coalesced per window
+            tauri::WindowEvent::Moved(position) => {
+                let (app, label) = (app_handle.clone(), label_clone.clone());
+                coalesce(&moves, *position, throttle_ms, move |position| apply_moved(&app, &label, position));
+            }
+            // Titlebar minimize/maximize/fullscreen all resize the window. A change of state
+            // always gets through at once; plain resizes are coalesced like moves.
+            tauri::WindowEvent::Resized(size) => {
+                let Some(flags) = app_handle.get_webview_window(&label_clone)
+                    .and_then(|window| native_window_flags(&window).ok()) else {
+                    return;
+                };
+                let state_changed = last_native_flags.swap(flags.bits(), Ordering::Relaxed) != flags.bits();
+                if state_changed {
+                    resizes.bypass(now_millis());
+                    apply_resized(&app_handle, &label_clone, *size, flags);
+                } else {
+                    let (app, label) = (app_handle.clone(), label_clone.clone());
+                    coalesce(&resizes, (*size, flags), throttle_ms, move |(size, flags)| {
+                        apply_resized(&app, &label, size, flags)
+                    });
+                }
+            }
+            // Restoring from the taskbar or dock may only show up as a focus change
+            tauri::WindowEvent::Focused(true) => {
+                let flags = app_handle.get_webview_window(&label_clone)
+                    .and_then(|window| native_window_flags(&window).ok());
+                let (change, modal) = {
+                    let mut registry = registry_state.lock().unwrap();
+                    if let Some(flags) = flags {
+                        registry.apply_native_flags(&label_clone, flags);
+                    }
+                    // A parent behind a modal passes focus straight on to the modal
+                    let modal = registry.blocking_modal(&label_clone);
+                    if modal.is_none() {
+                        registry.focus_window(&label_clone);
+                    }
+                    (registry.take_app_focus_change(), modal)
+                };
+                if let Some(modal) = modal.and_then(|modal| app_handle.get_webview_window(&modal)) {
+                    let _ = modal.set_focus();
+                }
+                if let Some(state) = change {
+                    let _ = app_handle.emit("app-focus-changed", &state);
+                }
+            }
+            tauri::WindowEvent::Focused(false) => {
+                let flags = app_handle.get_webview_window(&label_clone)
+                    .and_then(|window| native_window_flags(&window).ok());
+                {
+                    let mut registry = registry_state.lock().unwrap();
+                    if let Some(flags) = flags {
+                        registry.apply_native_flags(&label_clone, flags);
+                    }
+                    registry.blur_window(&label_clone);
+                }
+
+                // Only announce the app losing focus if no other window of ours picked it up
+                let app_handle = app_handle.clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(std::time::Duration::from_millis(APP_FOCUS_SETTLE_MS));
+                    let registry_state = app_handle.state::<WindowRegistryState>();
+                    let change = registry_state.lock().unwrap().take_app_focus_change();
+                    if let Some(state) = change {
+                        let _ = app_handle.emit("app-focus-changed", &state);
+                    }
+                });
+            }
+            tauri::WindowEvent::CloseRequested { api, .. } => {
+                let decision = registry_state.lock().unwrap().request_close(&label_clone, now_millis());
+
+                // The registry entry stays until the frontend answers or the timeout closes it
+                match decision {
+                    CloseDecision::Proceed => forget_window(&app_handle, &registry_state, &label_clone),
+                    CloseDecision::AlreadyAsked => api.prevent_close(),
+                    CloseDecision::Ask { requested_at } => {
+                        api.prevent_close();
+                        let _ = app_handle.emit_to(label_clone.as_str(), "close-requested", &label_clone);
+
+                        let app_handle = app_handle.clone();
+                        let label = label_clone.clone();
+                        std::thread::spawn(move || {
+                            std::thread::sleep(std::time::Duration::from_millis(CLOSE_CONFIRMATION_TIMEOUT_MS));
+                            let registry_state = app_handle.state::<WindowRegistryState>();
+                            let expired = registry_state.lock().unwrap().expire_close(&label, requested_at);
+                            if expired {
+                                if let Some(window) = app_handle.get_webview_window(&label) {
+                                    let _ = window.destroy();
+                                }
+                            }
+                        });
+                    }
+                }
+            }
+            // Fired when the window lands on a display with a different scale, or a display's
+            // settings change under it; either way the cached monitor list is stale
+            tauri::WindowEvent::ScaleFactorChanged { scale_factor, new_inner_size, .. } => {
+                let _ = refresh_monitors(&app_handle);
+                let monitors = enumerate_monitors(&app_handle).unwrap_or_default();
+                let change = registry_state.lock().unwrap().observe_scale_change(
+                    &label_clone,
+                    new_inner_size.width,
+                    new_inner_size.height,
+                    *scale_factor,
+                    &monitors,
+                );
+                // For canvases that size their backing store in physical pixels
+                if let Some(change) = change {
+                    let _ = app_handle.emit("window-scale-changed", &change);
+                }
+            }
+            // Catches windows torn down outside our own close path (webview crash, native API)
+            tauri::WindowEvent::Destroyed => {
+                forget_window(&app_handle, &registry_state, &label_clone);
+            }
+            _ => {}
+        }
+    });
+
+    Ok(created)
+}
+
+// Where a window actually landed, read before the registry is locked: each getter is a
+// round trip to the main thread, whose window events lock the registry too
+struct LivePlacement {
+    bounds: Option<(i32, i32, u32, u32)>,
+    monitors: Option<Vec<MonitorInfo>>,
+}
+
+fn read_live_placement(app: &AppHandle, window: &tauri::WebviewWindow) -> LivePlacement {
+    LivePlacement {
+        bounds: window_rect(window).ok(),
+        monitors: enumerate_monitors(app).ok(),
+    }
+}
+
+// The OS may have placed a newly tracked window itself, so record where it actually landed
+fn record_live_placement(registry: &mut WindowRegistry, label: &str, placement: &LivePlacement) {
+    if let Some((x, y, width, height)) = placement.bounds {
+        registry.update_window_position(label, x, y);
+        registry.update_window_size(label, width, height);
+    }
+    if let Some(monitors) = &placement.monitors {
+        registry.refresh_monitor_assignment(label, monitors);
+    }
+}
+
+// Windows created with `managed: false`, for debugging; nothing else lists them
+#[tauri::command]
+async fn list_unmanaged_windows(
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<WindowState>, String> {
+    let registry = registry_state.lock().unwrap();
+    Ok(registry.unmanaged_windows().into_iter().cloned().collect())
+}
+
+// Starts tracking an unmanaged window, e.g. a splash screen that becomes the main window
+#[tauri::command]
+async fn adopt_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<WindowState, String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    let placement = read_live_placement(&app, &window);
+    let adopted = {
+        let mut registry = registry_state.lock().unwrap();
+        registry.adopt_window(&label)?;
+        record_live_placement(&mut registry, &label, &placement);
+        registry.get_window(&label).cloned()
+            .ok_or_else(|| WindowError::NotFound { label: label.clone() })?
+    };
+    app.emit("window-created", &adopted).map_err(|e| e.to_string())?;
+    Ok(adopted)
+}
+
+#[tauri::command]
+async fn close_app_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    // Windows that need confirmation only go away once the frontend agrees
+    let needs_confirmation = registry_state.lock().unwrap().requires_close_confirmation(&label);
+
+    if let Some(window) = app.get_webview_window(&label) {
+        window.close().map_err(|e| e.to_string())?;
+    }
+
+    if !needs_confirmation {
+        forget_window(&app, &registry_state, &label);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_close_confirmation(
+    label: String,
+    enabled: bool,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let mut registry = registry_state.lock().unwrap();
+    registry.set_close_confirmation(&label, enabled)?;
+    Ok(())
+}
+
+// The frontend agreed to a `close-requested` event; destroy() skips CloseRequested
+#[tauri::command]
+async fn confirm_close(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    registry_state.lock().unwrap().resolve_close(&label);
+
+    if let Some(window) = app.get_webview_window(&label) {
+        window.destroy().map_err(|e| e.to_string())?;
+    }
+
+    forget_window(&app, &registry_state, &label);
+    Ok(())
+}
+
+// The frontend declined, so the window stays open and the fallback timer is disarmed
+#[tauri::command]
+async fn cancel_close(
+    label: String,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<bool, String> {
+    let mut registry = registry_state.lock().unwrap();
+    Ok(registry.resolve_close(&label))
+}
+
+async fn close_windows(
+    labels: Vec<String>,
+    app: &AppHandle,
+    registry_state: &State<'_, WindowRegistryState>,
+) -> BulkCloseReport {
+    let mut report = BulkCloseReport::default();
+
+    for label in labels {
+        let needs_confirmation = registry_state.lock().unwrap().requires_close_confirmation(&label);
+        if let Some(window) = app.get_webview_window(&label) {
+            if window.close().is_err() || needs_confirmation {
+                report.skipped.push(label);
+                continue;
+            }
+        }
+
+        forget_window(app, registry_state, &label);
+        report.closed.push(label);
+    }
+
+    report
+}
+
+#[tauri::command]
+async fn close_windows_of_type(
+    window_type: String,
+    exclude: Option<Vec<String>>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<BulkCloseReport, String> {
+    let exclude = exclude.unwrap_or_default();
+    let labels: Vec<String> = {
+        let registry = registry_state.lock().unwrap();
+        registry.get_labels_of_type(&window_type).into_iter()
+            .filter(|label| !exclude.contains(label))
+            .collect()
+    };
+
+    Ok(close_windows(labels, &app, &registry_state).await)
+}
+
+#[tauri::command]
+async fn close_other_windows(
+    keep_label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<BulkCloseReport, String> {
+    let labels: Vec<String> = {
+        let registry = registry_state.lock().unwrap();
+        registry.get_windows_by_z_order().into_iter()
+            .map(|w| w.label.clone())
+            .filter(|label| *label != keep_label)
+            .collect()
+    };
+
+    Ok(close_windows(labels, &app, &registry_state).await)
+}
+
+// Changes a window's type in place, e.g. a draft turning into a published document,
+// so grouping follows without recreating the webview
+#[tauri::command]
+async fn set_window_type(
+    label: String,
+    new_type: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<WindowState, WindowError> {
+    registry_state.lock().unwrap().set_window_type(&label, &new_type)?;
+    emit_registry_changed(&app, &registry_state, &label)
+}
+
+#[tauri::command]
+async fn set_window_type_limit(
+    window_type: String,
+    limit: Option<usize>,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let mut registry = registry_state.lock().unwrap();
+    registry.set_type_limit(&window_type, limit);
+    Ok(())
+}
+
+// How far a duplicate is shifted down and right from its source, in logical pixels
+const DUPLICATE_WINDOW_OFFSET: f64 = 30.0;
+
+// The config for a duplicate of a window now at logical `position` with logical inner `size`
+fn duplicate_config(mut config: WindowConfig, position: Option<(f64, f64)>, size: Option<(f64, f64)>) -> WindowConfig {
+    if let Some((x, y)) = position {
+        config.x = Some(x);
+        config.y = Some(y);
+    }
+    if let Some((width, height)) = size {
+        config.set_pixel_size(width, height);
+    }
+    config.x = config.x.map(|x| x + DUPLICATE_WINDOW_OFFSET);
+    config.y = config.y.map(|y| y + DUPLICATE_WINDOW_OFFSET);
+    config.placement = WindowPlacement::Absolute;
+    // A duplicate that would exceed the limit should fail, not hand back some other window
+    config.on_type_limit = TypeLimitBehavior::Reject;
+    config
+}
+
+// Opens another window with the source's type, url and flags; returns the new label
+#[tauri::command]
+async fn duplicate_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<String, WindowError> {
+    let source = registry_state.lock().unwrap().get_window(&label)
+        .map(|w| w.config.clone())
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() })?;
+
+    // Start from where the source is now rather than where it was created
+    let (mut position, mut size) = (None, None);
+    if let Some(window) = app.get_webview_window(&label) {
+        let scale = window.scale_factor().unwrap_or(1.0);
+        if let (Ok(outer_position), Ok(outer_size)) = (window.outer_position(), window.outer_size()) {
+            let monitors = enumerate_monitors(&app).unwrap_or_default();
+            let (x, y, _, _) = physical_rect_to_logical(
+                (outer_position.x, outer_position.y, outer_size.width, outer_size.height),
+                &monitors,
+            );
+            position = Some((x, y));
+        }
+        if let Ok(inner_size) = window.inner_size() {
+            let inner_size = inner_size.to_logical::<f64>(scale);
+            size = Some((inner_size.width, inner_size.height));
+        }
+    }
+    let config = duplicate_config(source, position, size);
+
+    let window_type = config.window_type.clone();
+    let created = open_app_window(app, window_type, config, None, registry_state).await?;
+    Ok(created.label)
+}
+
+#[tauri::command]
+async fn focus_app_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(&label) {
+        window.set_focus().map_err(|e| e.to_string())?;
+    }
+
+    {
+        let mut registry = registry_state.lock().unwrap();
+        registry.focus_window(&label);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn focus_previous_window(
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Option<String>, String> {
+    let previous = {
+        let mut registry = registry_state.lock().unwrap();
+        registry.previous_focus_candidate()
+    };
+
+    if let Some(label) = &previous {
+        focus_app_window(label.clone(), app, registry_state).await?;
+    }
+
+    Ok(previous)
+}
+
+#[tauri::command]
+async fn minimize_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(&label) {
+        window.minimize().map_err(|e| e.to_string())?;
+    }
+
+    {
+        let mut registry = registry_state.lock().unwrap();
+        registry.update_window_state(&label, Some(true), None, None);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn maximize_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(&label) {
+        window.maximize().map_err(|e| e.to_string())?;
+    }
+
+    {
+        let mut registry = registry_state.lock().unwrap();
+        registry.update_window_state(&label, None, Some(true), None);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn minimize_all_windows(
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<String>, String> {
+    let labels = {
+        let mut registry = registry_state.lock().unwrap();
+        registry.begin_show_desktop()
+    };
+
+    for label in &labels {
+        if let Some(window) = app.get_webview_window(label) {
+            window.minimize().map_err(|e| e.to_string())?;
+        }
+    }
+
+    app.emit("windows-minimized", &labels).map_err(|e| e.to_string())?;
+
+    Ok(labels)
+}
+
+#[tauri::command]
+async fn restore_minimized_windows(
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<String>, String> {
+    restore_all_windows(app, registry_state).await
+}
+
+// Counterpart to minimize_all_windows: only windows it minimized come back
+#[tauri::command]
+async fn restore_all_windows(
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<String>, String> {
+    let snapshot = {
+        let mut registry = registry_state.lock().unwrap();
+        registry.end_show_desktop()
+    };
+
+    for label in &snapshot.labels {
+        if let Some(window) = app.get_webview_window(label) {
+            window.unminimize().map_err(|e| e.to_string())?;
+        }
+    }
+
+    if let Some(focused) = snapshot.focused {
+        focus_app_window(focused, app.clone(), registry_state).await?;
+    }
+
+    app.emit("windows-restored", &snapshot.labels).map_err(|e| e.to_string())?;
+
+    Ok(snapshot.labels)
+}
+
+#[tauri::command]
+async fn unmaximize_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(&label) {
+        window.unmaximize().map_err(|e| e.to_string())?;
+    }
+
+    {
+        let mut registry = registry_state.lock().unwrap();
+        registry.update_window_state(&label, None, Some(false), None);
+    }
+
+    Ok(())
+}
+
+// Returns the size actually applied after clamping to the window's limits,
+// or None if the window isn't open
+#[tauri::command]
+async fn resize_app_window(
+    label: String,
+    width: f64,
+    height: f64,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Option<AppliedSize>, String> {
+    let (width, height) = {
+        let registry = registry_state.lock().unwrap();
+        registry.get_window(&label).map_or((width, height), |w| w.config.clamp_size(width, height))
+    };
+
+    // Logical, so the OS scales it for whichever monitor the window is on
+    if let Some(window) = app.get_webview_window(&label) {
+        window.set_size(tauri::Size::Logical(tauri::LogicalSize { width, height }))
+            .map_err(|e| e.to_string())?;
+        return Ok(Some(AppliedSize { width, height }));
+    }
+
+    Ok(None)
+}
+
+// Flashes the taskbar entry (Windows) or bounces the dock icon (macOS); `critical`
+// keeps it going until the window is focused
+#[tauri::command]
+async fn request_user_attention(
+    label: String,
+    critical: bool,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+
+    let attention = if critical {
+        tauri::UserAttentionType::Critical
+    } else {
+        tauri::UserAttentionType::Informational
+    };
+    window.request_user_attention(Some(attention)).map_err(|e| e.to_string())?;
+
+    registry_state.lock().unwrap().set_needs_attention(&label, true)?;
+    Ok(())
+}
+
+// F11-style toggle; leaving fullscreen puts the window back where it was. Returns the new state.
+#[tauri::command]
+async fn toggle_fullscreen(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<bool, String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+
+    if !window.is_fullscreen().map_err(|e| e.to_string())? {
+        // Saved in the measure apply_rect restores, or each round trip grows by the frame
+        let bounds = window_rect(&window).map_err(|e| e.to_string())?;
+        registry_state.lock().unwrap().enter_fullscreen(&label, bounds);
+        window.set_fullscreen(true).map_err(|e| e.to_string())?;
+        return Ok(true);
+    }
+
+    window.set_fullscreen(false).map_err(|e| e.to_string())?;
+    let bounds = registry_state.lock().unwrap().exit_fullscreen(&label);
+    if let Some(bounds) = bounds {
+        apply_rect(&window, bounds).map_err(|e| e.to_string())?;
+    }
+    Ok(false)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowTitleChange {
+    pub label: String,
+    pub title: String,
+}
+
+// Updates the native title and the registry copy, so saved layouts get the latest one
+#[tauri::command]
+async fn set_window_title(
+    label: String,
+    title: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    window.set_title(&title).map_err(|e| e.to_string())?;
+
+    registry_state.lock().unwrap().set_window_title(&label, &title)?;
+    app.emit("window-title-changed", &WindowTitleChange { label, title }).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowDecorationsChange {
+    pub label: String,
+    pub decorations: bool,
+}
+
+// Shows or hides the title bar and frame, e.g. for focus mode; saved layouts keep the setting
+#[tauri::command]
+async fn set_window_decorations(
+    label: String,
+    enabled: bool,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    registry_state.lock().unwrap().set_decorations(&label, enabled)?;
+    window.set_decorations(enabled).map_err(|e| e.to_string())?;
+
+    app.emit("window-decorations-changed", &WindowDecorationsChange { label, decorations: enabled })
+        .map_err(|e| e.to_string())
+}
+
+// Tauri has no opacity API, so each platform's own window alpha is set directly.
+// GTK and AppKit both want this on the main thread.
+fn apply_window_opacity(window: &tauri::WebviewWindow, opacity: f64) -> Result<(), String> {
+    let target = window.clone();
+    window.run_on_main_thread(move || set_native_opacity(&target, opacity))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn set_native_opacity(window: &tauri::WebviewWindow, opacity: f64) {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use windows_sys::Win32::Foundation::HWND;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetWindowLongPtrW, SetLayeredWindowAttributes, SetWindowLongPtrW, GWL_EXSTYLE, LWA_ALPHA,
+        WS_EX_LAYERED,
+    };
+
+    let Ok(handle) = window.window_handle() else {
+        return;
+    };
+    let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+        return;
+    };
+    let hwnd = handle.hwnd.get() as HWND;
+    unsafe {
+        let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style | WS_EX_LAYERED as _);
+        SetLayeredWindowAttributes(hwnd, 0, (opacity * 255.0).round() as u8, LWA_ALPHA);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn set_native_opacity(window: &tauri::WebviewWindow, opacity: f64) {
+    use objc2::runtime::AnyObject;
+
+    let Ok(ns_window) = window.ns_window() else {
+        return;
+    };
+    unsafe {
+        let ns_window = &*(ns_window as *const AnyObject);
+        let _: () = objc2::msg_send![ns_window, setAlphaValue: opacity];
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_native_opacity(window: &tauri::WebviewWindow, opacity: f64) {
+    use gtk::prelude::WidgetExt;
+
+    if let Ok(gtk_window) = window.gtk_window() {
+        gtk_window.set_opacity(opacity);
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn set_native_opacity(_window: &tauri::WebviewWindow, _opacity: f64) {}
+
+// Fades a whole window, e.g. a reference chart kept over the map; clamped to 0.1-1.0
+// so a window can't be lost entirely. Returns the applied value.
+#[tauri::command]
+async fn set_window_opacity(
+    label: String,
+    opacity: f64,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<f64, String> {
+    if !opacity.is_finite() {
+        return Err(format!("Invalid opacity {}", opacity));
+    }
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    let opacity = clamp_opacity(opacity);
+    apply_window_opacity(&window, opacity)?;
+
+    Ok(registry_state.lock().unwrap().set_opacity(&label, opacity)?)
+}
+
+// Lets clicks fall through to the windows beneath, for overlays paired with always_on_top and
+// transparent; a click-through window can't take text input either
+#[tauri::command]
+async fn set_click_through(
+    label: String,
+    enabled: bool,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    window.set_ignore_cursor_events(enabled).map_err(|e| e.to_string())?;
+
+    registry_state.lock().unwrap().set_click_through(&label, enabled)?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_window_metadata(
+    label: String,
+    key: String,
+    value: serde_json::Value,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), WindowError> {
+    registry_state.lock().unwrap().set_metadata(&label, &key, value)
+}
+
+#[tauri::command]
+async fn get_window_metadata(
+    label: String,
+    key: String,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Option<serde_json::Value>, WindowError> {
+    registry_state.lock().unwrap().get_metadata(&label, &key)
+}
+
+// macOS has no per-window taskbar entry to hide; Tauri silently ignores the setting there
+const SKIP_TASKBAR_SUPPORTED: bool = !cfg!(target_os = "macos");
+
+#[tauri::command]
+async fn set_skip_taskbar(
+    label: String,
+    skip: bool,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    if !SKIP_TASKBAR_SUPPORTED {
+        return Err("Hiding windows from the taskbar isn't supported on this platform".to_string());
+    }
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    window.set_skip_taskbar(skip).map_err(|e| e.to_string())?;
+
+    registry_state.lock().unwrap().set_skip_taskbar(&label, skip)?;
+    Ok(())
+}
+
+// Scales one window's content; the factor is clamped to 0.5-3.0 and the window gets
+// `zoom-changed` with the applied value
+#[tauri::command]
+async fn set_window_zoom(
+    label: String,
+    factor: f64,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<f64, String> {
+    if !factor.is_finite() {
+        return Err(format!("Invalid zoom factor {}", factor));
+    }
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    let factor = clamp_zoom(factor);
+    window.set_zoom(factor).map_err(|e| e.to_string())?;
+
+    registry_state.lock().unwrap().set_zoom(&label, factor)?;
+    app.emit_to(label.as_str(), "zoom-changed", factor).map_err(|e| e.to_string())?;
+    Ok(factor)
+}
+
+// Asks the window itself, so it's right even mid-drag between displays
+#[tauri::command]
+async fn get_window_scale_factor(label: String, app: AppHandle) -> Result<f64, String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    window.scale_factor().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_window_zoom(
+    label: String,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<f64, String> {
+    let registry = registry_state.lock().unwrap();
+    let window = registry.get_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    Ok(window.zoom)
+}
+
+// Overrides the OS light/dark setting for one window; its webview gets `theme-changed`
+// so the CSS can follow
+#[tauri::command]
+async fn set_window_theme(
+    label: String,
+    theme: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let native_theme = parse_theme(&theme)?;
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    window.set_theme(native_theme).map_err(|e| e.to_string())?;
+
+    registry_state.lock().unwrap().set_window_theme(&label, &theme)?;
+    app.emit_to(label.as_str(), "theme-changed", &theme).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_window_size_constraints(
+    label: String,
+    min: Option<(u32, u32)>,
+    max: Option<(u32, u32)>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), WindowError> {
+    registry_state.lock().unwrap().set_size_limits(&label, min, max)?;
+
+    if let Some(window) = app.get_webview_window(&label) {
+        let failed = |e: tauri::Error| WindowError::Failed { label: label.clone(), message: e.to_string() };
+        let to_size = |(width, height): (u32, u32)| {
+            tauri::Size::Logical(tauri::LogicalSize { width: width as f64, height: height as f64 })
+        };
+        window.set_min_size(min.map(to_size)).map_err(failed)?;
+        window.set_max_size(max.map(to_size)).map_err(failed)?;
+    }
+
+    Ok(())
+}
+
+// Per-bound version of set_window_size_constraints, in logical pixels; a null bound is
+// cleared, so all nulls restore free resizing. A window outside the new limits is resized
+// into them. Emits `registry-changed` with the updated state.
+#[tauri::command]
+async fn set_window_constraints(
+    label: String,
+    min_w: Option<f64>,
+    min_h: Option<f64>,
+    max_w: Option<f64>,
+    max_h: Option<f64>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<WindowState, WindowError> {
+    let failed = |label: &str, e: tauri::Error| WindowError::Failed { label: label.to_string(), message: e.to_string() };
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() })?;
+    registry_state.lock().unwrap().set_constraints(&label, min_w, min_h, max_w, max_h)?;
+
+    // Each open axis gets 0 or "unbounded" when the other one is set
+    let to_size = |width: Option<f64>, height: Option<f64>, open: f64| {
+        (width.is_some() || height.is_some()).then(|| tauri::Size::Logical(tauri::LogicalSize {
+            width: width.unwrap_or(open),
+            height: height.unwrap_or(open),
+        }))
+    };
+    window.set_min_size(to_size(min_w, min_h, 0.0)).map_err(|e| failed(&label, e))?;
+    window.set_max_size(to_size(max_w, max_h, i32::MAX as f64)).map_err(|e| failed(&label, e))?;
+
+    let scale = window.scale_factor().map_err(|e| failed(&label, e))?;
+    let size = window.inner_size().map_err(|e| failed(&label, e))?.to_logical::<f64>(scale);
+    let (width, height) = (size.width, size.height);
+    let clamped = registry_state.lock().unwrap().get_window(&label)
+        .map(|w| w.config.clamp_size(width, height));
+    if let Some((width, height)) = clamped.filter(|&clamped| clamped != (width, height)) {
+        window.set_size(tauri::Size::Logical(tauri::LogicalSize { width, height }))
+            .map_err(|e| failed(&label, e))?;
+    }
+
+    emit_registry_changed(&app, &registry_state, &label)
+}
+
+// Locks or unlocks a window's size, e.g. for presentation mode; saved layouts keep the setting
+#[tauri::command]
+async fn set_window_resizable(
+    label: String,
+    resizable: bool,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<WindowState, WindowError> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() })?;
+    window.set_resizable(resizable)
+        .map_err(|e| WindowError::Failed { label: label.clone(), message: e.to_string() })?;
+    registry_state.lock().unwrap().set_resizable(&label, resizable)?;
+
+    emit_registry_changed(&app, &registry_state, &label)
+}
+
+fn emit_registry_changed(app: &AppHandle, registry_state: &WindowRegistryState, label: &str) -> Result<WindowState, WindowError> {
+    let updated = registry_state.lock().unwrap().get_window(label).cloned()
+        .ok_or_else(|| WindowError::NotFound { label: label.to_string() })?;
+    app.emit("registry-changed", &updated)
+        .map_err(|e| WindowError::Failed { label: label.to_string(), message: e.to_string() })?;
+    Ok(updated)
+}
+
+// Legacy commands still invoked by older frontend code. They delegate to the current
+// commands and flag their responses so callers can tell they should migrate.
+const LEGACY_WINDOW_TYPE: &str = "legacy";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LegacyResponse<T> {
+    pub value: T,
+    pub deprecated: bool,
+    pub replacement: &'static str,
+}
+
+impl<T> LegacyResponse<T> {
+    fn new(value: T, replacement: &'static str) -> Self {
+        Self { value, deprecated: true, replacement }
+    }
+}
+
+// The old create_window only took a title and size
+fn legacy_window_config(title: String, width: f64, height: f64) -> WindowConfig {
+    WindowConfig {
+        window_type: LEGACY_WINDOW_TYPE.to_string(),
+        title,
+        width,
+        height,
+        ..WindowConfig::default()
+    }
+}
+
+#[tauri::command]
+async fn create_window(
+    title: String,
+    width: f64,
+    height: f64,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<LegacyResponse<String>, String> {
+    let config = legacy_window_config(title, width, height);
+    let label = open_app_window(app, LEGACY_WINDOW_TYPE.to_string(), config, None, registry_state).await?.label;
+    Ok(LegacyResponse::new(label, "create_app_window"))
+}
+
+#[tauri::command]
+async fn close_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<LegacyResponse<()>, String> {
+    close_app_window(label, app, registry_state).await?;
+    Ok(LegacyResponse::new((), "close_app_window"))
+}
+
+#[tauri::command]
+async fn resize_window(
+    label: String,
+    width: f64,
+    height: f64,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<LegacyResponse<()>, String> {
+    resize_app_window(label, width, height, app, registry_state).await?;
+    Ok(LegacyResponse::new((), "resize_app_window"))
+}
+
+#[tauri::command]
+async fn move_window(
+    label: String,
+    x: f64,
+    y: f64,
+    allow_offscreen: Option<bool>,
+    app: AppHandle,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(&label) {
+        let scale = window.scale_factor().map_err(|e| e.to_string())?;
+        let size = window.outer_size().map_err(|e| e.to_string())?.to_logical::<f64>(scale);
+        let monitors = enumerate_monitors(&app)?;
+        let (x, y, width, height) = logical_rect_to_physical((x, y, size.width, size.height), None, &monitors);
+        let (x, y) = if allow_offscreen.unwrap_or(false) {
+            (x, y)
+        } else {
+            clamp_to_visible(x as f64, y as f64, width as f64, height as f64, &monitors)
+        };
+
+        window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+// Window animation
+const ANIMATION_FRAME_MS: u64 = 16;
+
+fn ease_in_out_cubic(t: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+// Rectangle (x, y, width, height) `progress` of the way from `from` to `to`, eased
+fn interpolate_rect(from: (i32, i32, u32, u32), to: (i32, i32, u32, u32), progress: f64) -> (i32, i32, u32, u32) {
+    let eased = ease_in_out_cubic(progress);
+    let lerp = |a: f64, b: f64| a + (b - a) * eased;
+    (
+        lerp(from.0 as f64, to.0 as f64).round() as i32,
+        lerp(from.1 as f64, to.1 as f64).round() as i32,
+        lerp(from.2 as f64, to.2 as f64).round() as u32,
+        lerp(from.3 as f64, to.3 as f64).round() as u32,
+    )
+}
+
+// A window's outer position with its inner size: the measure apply_rect writes back, so a
+// rect saved with this and restored with that comes back the same size
+fn window_rect(window: &tauri::WebviewWindow) -> tauri::Result<(i32, i32, u32, u32)> {
+    Ok(rect_of(window.outer_position()?, window.inner_size()?))
+}
+
+fn rect_of(position: tauri::PhysicalPosition<i32>, inner_size: tauri::PhysicalSize<u32>) -> (i32, i32, u32, u32) {
+    (position.x, position.y, inner_size.width, inner_size.height)
+}
+
+// `x`/`y` are the outer position and `width`/`height` the inner size, which is what
+// set_position and set_size take
+fn apply_rect(window: &tauri::WebviewWindow, (x, y, width, height): (i32, i32, u32, u32)) -> tauri::Result<()> {
+    window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))?;
+    window.set_size(tauri::Size::Physical(tauri::PhysicalSize { width, height }))
+}
+
+// Moves and resizes a window smoothly over `duration_ms`; 0 applies the target at once.
+// Starting another animation (or a 0ms one) for the same window cancels the running one.
+#[tauri::command]
+async fn animate_window(
+    label: String,
+    target: WindowPositionSize,
+    duration_ms: u64,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| format!("Window '{}' not found", label))?;
+
+    let monitors = enumerate_monitors(&app)?;
+    let (x, y, width, height) = logical_rect_to_physical(
+        (target.x, target.y, target.width, target.height),
+        target.monitor_key(),
+        &monitors,
+    );
+    let (x, y) = clamp_to_visible(x as f64, y as f64, width as f64, height as f64, &monitors);
+    let to = (x, y, width, height);
+
+    let animation_id = registry_state.lock().unwrap().start_animation(&label);
+
+    if duration_ms == 0 {
+        apply_rect(&window, to).map_err(|e| e.to_string())?;
+        registry_state.lock().unwrap().finish_animation(&label, animation_id);
+        return Ok(());
+    }
+
+    // Measured the way apply_rect writes, so the first frame doesn't jump by the frame size
+    let from = window_rect(&window).map_err(|e| e.to_string())?;
+
+    std::thread::spawn(move || {
+        let started = std::time::Instant::now();
+        let registry_state = app.state::<WindowRegistryState>();
+        loop {
+            if !registry_state.lock().unwrap().is_animation_current(&label, animation_id) {
+                return;
+            }
+            let progress = started.elapsed().as_millis() as f64 / duration_ms as f64;
+            if apply_rect(&window, interpolate_rect(from, to, progress)).is_err() || progress >= 1.0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(ANIMATION_FRAME_MS));
+        }
+        registry_state.lock().unwrap().finish_animation(&label, animation_id);
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn hide_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(&label) {
+        window.hide().map_err(|e| e.to_string())?;
+    }
+
+    {
+        let mut registry = registry_state.lock().unwrap();
+        registry.set_window_visible(&label, false);
+    }
+
+    app.emit("window-hidden", &label).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn show_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(&label) {
+        window.show().map_err(|e| e.to_string())?;
+        window.set_focus().map_err(|e| e.to_string())?;
+    }
+
+    {
+        let mut registry = registry_state.lock().unwrap();
+        registry.set_window_visible(&label, true);
+    }
+
+    app.emit("window-shown", &label).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_window_list(
+    include_hidden: Option<bool>,
+    include_live: Option<bool>,
+    registry_state: State<'_, WindowRegistryState>,
+    app: AppHandle,
+) -> Result<WindowListing, String> {
+    let mut windows: Vec<WindowState> = {
+        let registry = registry_state.lock().unwrap();
+        let windows = if include_hidden.unwrap_or(true) {
+            registry.get_windows_by_z_order()
+        } else {
+            registry.get_visible_windows_by_z_order()
+        };
+        windows.into_iter().cloned().collect()
+    };
+
+    if !include_live.unwrap_or(false) {
+        return Ok(WindowListing::Registry(windows));
+    }
+
+    // Native buttons change these without going through our commands
+    let mut errors = HashMap::new();
+    for state in windows.iter_mut() {
+        let Some(window) = app.get_webview_window(&state.label) else {
+            errors.insert(state.label.clone(), "Window not found".to_string());
+            continue;
+        };
+        match native_window_flags(&window) {
+            Ok(flags) => {
+                state.is_minimized = flags.minimized;
+                state.is_maximized = flags.maximized;
+                state.is_fullscreen = flags.fullscreen;
+            }
+            Err(e) => {
+                errors.insert(state.label.clone(), e.to_string());
+            }
+        }
+    }
+
+    {
+        let mut registry = registry_state.lock().unwrap();
+        for state in windows.iter().filter(|w| !errors.contains_key(&w.label)) {
+            registry.apply_native_flags(&state.label, NativeWindowFlags {
+                minimized: state.is_minimized,
+                maximized: state.is_maximized,
+                fullscreen: state.is_fullscreen,
+            });
+        }
+    }
+
+    Ok(WindowListing::Live { windows, errors })
+}
+
+#[tauri::command]
+async fn get_window_mru_list(
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<WindowState>, String> {
+    let registry = registry_state.lock().unwrap();
+    Ok(registry.get_switcher_windows().into_iter().cloned().collect())
+}
+
+#[tauri::command]
+async fn get_focused_window(
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Option<WindowState>, String> {
+    let registry = registry_state.lock().unwrap();
+    // While another application has focus none of our windows is focused
+    if !registry.app_focused {
+        return Ok(None);
+    }
+    Ok(registry.get_focused_window().cloned())
+}
+
+#[tauri::command]
+async fn get_app_focus_state(
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<AppFocusState, String> {
+    let registry = registry_state.lock().unwrap();
+    Ok(registry.app_focus_state())
+}
+
+#[tauri::command]
+async fn get_window_state(
+    label: String,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Option<WindowState>, String> {
+    let registry = registry_state.lock().unwrap();
+    Ok(registry.get_window(&label).cloned())
+}
+
+// Drop-target lookup for drags between windows; the point is in physical virtual-desktop
+// pixels. Bounds come from the OS since the registry's copy is throttled during drags
+#[tauri::command]
+async fn get_window_at_point(
+    x: i32,
+    y: i32,
+    registry_state: State<'_, WindowRegistryState>,
+    app: AppHandle,
+) -> Result<Option<String>, String> {
+    let labels: Vec<String> = registry_state.lock().unwrap().windows.keys().cloned().collect();
+
+    let mut bounds = HashMap::new();
+    for label in labels {
+        let Some(window) = app.get_webview_window(&label) else {
+            continue;
+        };
+        if window.is_minimized().unwrap_or(false) || !window.is_visible().unwrap_or(true) {
+            continue;
+        }
+        if let (Ok(position), Ok(size)) = (window.outer_position(), window.outer_size()) {
+            bounds.insert(label, (position.x, position.y, size.width, size.height));
+        }
+    }
+
+    Ok(registry_state.lock().unwrap().window_at_point(x, y, &bounds))
+}
+
+// Window capture
+
+// Packs rows of 32-bit BGRx pixels (GDI, X11 ZPixmap and CoreGraphics all hand these
+// out, padded to `stride` bytes) into RGBA. Captures are treated as opaque since the
+// fourth byte is undefined on GDI and X11.
+fn bgrx_to_rgba(pixels: &[u8], width: usize, height: usize, stride: usize) -> Result<Vec<u8>, String> {
+    if stride < width * 4 || pixels.len() < stride * height.saturating_sub(1) + width * 4 {
+        return Err(format!("Captured buffer is too small for {}x{}", width, height));
+    }
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for row in pixels.chunks(stride).take(height) {
+        for pixel in row[..width * 4].chunks_exact(4) {
+            rgba.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 0xff]);
+        }
+    }
+    Ok(rgba)
+}
+
+fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>, String> {
+    let mut png_bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(rgba).map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(png_bytes)
+}
+
+// Client area via PrintWindow; PW_RENDERFULLCONTENT is what makes WebView2's
+// composited content show up instead of a black rectangle
+#[cfg(target_os = "windows")]
+fn capture_native_window(window: &tauri::WebviewWindow) -> Result<(u32, u32, Vec<u8>), String> {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use windows_sys::Win32::Foundation::{HWND, RECT};
+    use windows_sys::Win32::Graphics::Gdi::{
+        CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, GetDIBits,
+        ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+    };
+    use windows_sys::Win32::Storage::Xps::{PrintWindow, PW_CLIENTONLY};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetClientRect, PW_RENDERFULLCONTENT};
+
+    let handle = window.window_handle().map_err(|e| e.to_string())?;
+    let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+        return Err("Window has no Win32 handle".to_string());
+    };
+    let hwnd = handle.hwnd.get() as HWND;
+
+    unsafe {
+        let mut rect: RECT = std::mem::zeroed();
+        if GetClientRect(hwnd, &mut rect) == 0 {
+            return Err("GetClientRect failed".to_string());
+        }
+        let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+        if width <= 0 || height <= 0 {
+            return Err("Window has no visible client area".to_string());
+        }
+
+        let screen_dc = GetDC(std::ptr::null_mut());
+        let memory_dc = CreateCompatibleDC(screen_dc);
+        let bitmap = CreateCompatibleBitmap(screen_dc, width, height);
+        let previous = SelectObject(memory_dc, bitmap);
+
+        let printed = PrintWindow(hwnd, memory_dc, PW_CLIENTONLY | PW_RENDERFULLCONTENT) != 0;
+
+        let mut info: BITMAPINFO = std::mem::zeroed();
+        info.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as u32;
+        info.bmiHeader.biWidth = width;
+        // Negative height asks for top-down rows
+        info.bmiHeader.biHeight = -height;
+        info.bmiHeader.biPlanes = 1;
+        info.bmiHeader.biBitCount = 32;
+        info.bmiHeader.biCompression = BI_RGB;
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        let copied = printed && GetDIBits(
+            memory_dc,
+            bitmap,
+            0,
+            height as u32,
+            pixels.as_mut_ptr().cast(),
+            &mut info,
+            DIB_RGB_COLORS,
+        ) == height;
+
+        SelectObject(memory_dc, previous);
+        DeleteObject(bitmap);
+        DeleteDC(memory_dc);
+        ReleaseDC(std::ptr::null_mut(), screen_dc);
+
+        if !copied {
+            return Err("PrintWindow failed to capture the window".to_string());
+        }
+        let rgba = bgrx_to_rgba(&pixels, width as usize, height as usize, width as usize * 4)?;
+        Ok((width as u32, height as u32, rgba))
+    }
+}
+
+// The window's own backing image by window number, without the drop shadow.
+// Capturing our own windows doesn't need the Screen Recording permission.
+#[cfg(target_os = "macos")]
+fn capture_native_window(window: &tauri::WebviewWindow) -> Result<(u32, u32, Vec<u8>), String> {
+    use core_graphics::display::CGRectNull;
+    use core_graphics::window::{
+        create_image, kCGWindowImageBoundsIgnoreFraming, kCGWindowListOptionIncludingWindow,
+    };
+    use objc2::runtime::AnyObject;
+
+    let ns_window = window.ns_window().map_err(|e| e.to_string())?;
+    let window_number: isize = unsafe {
+        let ns_window = &*(ns_window as *const AnyObject);
+        objc2::msg_send![ns_window, windowNumber]
+    };
+    if window_number <= 0 {
+        return Err("Window isn't on screen".to_string());
+    }
+
+    let image = create_image(
+        unsafe { CGRectNull },
+        kCGWindowListOptionIncludingWindow,
+        window_number as u32,
+        kCGWindowImageBoundsIgnoreFraming,
+    )
+    .ok_or_else(|| "CGWindowListCreateImage returned no image".to_string())?;
+    if image.bits_per_pixel() != 32 {
+        return Err(format!("Unsupported capture format ({} bits per pixel)", image.bits_per_pixel()));
+    }
+
+    let (width, height) = (image.width(), image.height());
+    let data = image.data();
+    let rgba = bgrx_to_rgba(data.bytes(), width, height, image.bytes_per_row())?;
+    Ok((width as u32, height as u32, rgba))
+}
+
+// GetImage on the window's X11 drawable over a fresh connection. Wayland has no
+// per-window capture outside the screencast portal, which needs the user's consent.
+#[cfg(target_os = "linux")]
+fn capture_native_window(window: &tauri::WebviewWindow) -> Result<(u32, u32, Vec<u8>), String> {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{ConnectionExt, ImageFormat};
+
+    let handle = window.window_handle().map_err(|e| e.to_string())?;
+    let xid = match handle.as_raw() {
+        RawWindowHandle::Xlib(handle) => handle.window as u32,
+        RawWindowHandle::Xcb(handle) => handle.window.get(),
+        RawWindowHandle::Wayland(_) => {
+            return Err("Window capture isn't supported on Wayland".to_string());
+        }
+        _ => return Err("Window has no X11 handle".to_string()),
+    };
+
+    let (connection, _) = x11rb::connect(None).map_err(|e| e.to_string())?;
+    let geometry = connection.get_geometry(xid).map_err(|e| e.to_string())?
+        .reply().map_err(|e| e.to_string())?;
+    let image = connection
+        .get_image(ImageFormat::Z_PIXMAP, xid, 0, 0, geometry.width, geometry.height, !0)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+
+    let bits_per_pixel = connection.setup().pixmap_formats.iter()
+        .find(|format| format.depth == image.depth)
+        .map(|format| format.bits_per_pixel);
+    if bits_per_pixel != Some(32) {
+        return Err(format!("Unsupported X11 visual depth {}", image.depth));
+    }
+
+    let (width, height) = (geometry.width as usize, geometry.height as usize);
+    let rgba = bgrx_to_rgba(&image.data, width, height, width * 4)?;
+    Ok((width as u32, height as u32, rgba))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn capture_native_window(_window: &tauri::WebviewWindow) -> Result<(u32, u32, Vec<u8>), String> {
+    Err("Window capture isn't supported on this platform".to_string())
+}
+
+// PNG bytes of one window's current contents, e.g. for sharing a chart or attaching
+// a snapshot to a report
+#[tauri::command]
+async fn capture_window(label: String, app: AppHandle) -> Result<Vec<u8>, String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    let (width, height, rgba) = capture_native_window(&window)?;
+    encode_png(width, height, &rgba)
+}
+
+// How long a window created hidden may wait for reveal_window or window_ready
+const REVEAL_WATCHDOG_MS: u64 = 5000;
+
+fn reveal_window_now(app: &AppHandle, registry_state: &WindowRegistryState, label: &str) -> Result<(), String> {
+    let window = app.get_webview_window(label)
+        .ok_or_else(|| WindowError::NotFound { label: label.to_string() }.to_string())?;
+    window.show().map_err(|e| e.to_string())?;
+    window.set_focus().map_err(|e| e.to_string())?;
+    registry_state.lock().unwrap().set_window_visible(label, true);
+    Ok(())
+}
+
+// Called by the frontend after first paint of a window created with `visible: false`
+#[tauri::command]
+async fn reveal_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    reveal_window_now(&app, &registry_state, &label)
+}
+
+// Readiness handshake: events sent before a new window's frontend is listening are
+// queued and delivered once it calls window_ready
+#[tauri::command]
+async fn window_ready(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<usize, String> {
+    let (queued, reveal) = {
+        let mut registry = registry_state.lock().unwrap();
+        let reveal = registry.reveals_when_ready(&label);
+        (registry.mark_ready(&label, now_millis()), reveal)
+    };
+    if reveal {
+        reveal_window_now(&app, &registry_state, &label)?;
+    }
+    for queued_event in &queued {
+        app.emit_to(label.as_str(), &queued_event.event, &queued_event.payload)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(queued.len())
+}
+
+#[tauri::command]
+async fn send_to_window(
+    label: String,
+    event: String,
+    payload: serde_json::Value,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    {
+        let mut registry = registry_state.lock().unwrap();
+        // Windows we don't track (like the main window) can't do the handshake
+        if registry.get_window(&label).is_some() && !registry.is_ready(&label) {
+            registry.queue_event(&label, &event, payload, now_millis());
+            return Ok(());
+        }
+    }
+    app.emit_to(label.as_str(), &event, &payload).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_event_queue_ttl(
+    ttl_ms: u64,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let mut registry = registry_state.lock().unwrap();
+    registry.event_queue_ttl_ms = ttl_ms;
+    Ok(())
+}
+
+#[tauri::command]
+async fn sync_registry(
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<RegistrySyncReport, String> {
+    Ok(reconcile_windows(&app, &registry_state))
+}
+
+// What can be read back from a window the registry didn't create, e.g. one declared in
+// tauri.conf.json; the type comes from the label as in WindowConfig::synthesized
+fn infer_window_config(window: &tauri::WebviewWindow) -> WindowConfig {
+    let mut config = WindowConfig::synthesized(window.label());
+    if let Ok(title) = window.title() {
+        config.title = title;
+    }
+    if let (Ok(size), Ok(scale)) = (window.inner_size(), window.scale_factor()) {
+        config.width = size.width as f64 / scale;
+        config.height = size.height as f64 / scale;
+    }
+    config.resizable = window.is_resizable().unwrap_or(config.resizable);
+    config.decorations = window.is_decorated().unwrap_or(config.decorations);
+    config.always_on_top = window.is_always_on_top().unwrap_or(config.always_on_top);
+    config.visible = window.is_visible().unwrap_or(config.visible);
+    config
+}
+
+// Drops registry entries whose window is gone and tracks live windows it doesn't know,
+// e.g. after a crash left the two out of step. Run at startup and by sync_registry
+fn reconcile_windows(app: &AppHandle, registry_state: &WindowRegistryState) -> RegistrySyncReport {
+    // Read outside the lock: each getter is a round trip to the main thread. Placement is
+    // only needed for windows the registry is about to start tracking
+    let windows = app.webview_windows();
+    let untracked: HashSet<String> = {
+        let registry = registry_state.lock().unwrap();
+        windows.keys()
+            .filter(|label| !registry.windows.contains_key(*label) && !registry.unmanaged.contains_key(*label))
+            .cloned()
+            .collect()
+    };
+    let placements: HashMap<String, LivePlacement> = windows.iter()
+        .filter(|(label, _)| untracked.contains(*label))
+        .map(|(label, window)| (label.clone(), read_live_placement(app, window)))
+        .collect();
+    let live: Vec<(String, WindowConfig)> = windows.iter()
+        .map(|(label, window)| (label.clone(), infer_window_config(window)))
+        .collect();
+
+    let report = {
+        let mut registry = registry_state.lock().unwrap();
+        let report = registry.reconcile(live);
+        for label in &report.added {
+            if let Some(placement) = placements.get(label) {
+                record_live_placement(&mut registry, label, placement);
+            }
+        }
+        report
+    };
+    release_modal_parents(app, registry_state);
+    report
+}
+
+#[tauri::command]
+async fn get_registry_stats(
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<RegistryStats, String> {
+    let (mut stats, labels) = {
+        let registry = registry_state.lock().unwrap();
+        let labels: Vec<String> = registry.windows.keys().cloned().collect();
+        (registry.stats(now_millis()), labels)
+    };
+
+    // Checked outside the lock so window lookups don't hold up event handlers
+    stats.ghost_windows = labels.iter()
+        .filter(|label| app.get_webview_window(label).is_none())
+        .count();
+
+    Ok(stats)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WindowPresence {
+    Present,
+    Absent,
+    // A leaked entry: the window is gone but was never forgotten
+    RegistryOnly,
+    // A window opened behind the registry's back
+    LiveOnly,
+}
+
+// True only when the window is open and tracked, i.e. when commands taking the label will
+// find it. A disagreement is logged; sync_registry resolves it
+#[tauri::command]
+async fn window_exists(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<bool, String> {
+    // Looked up before taking the lock, as in get_registry_stats
+    let live = app.get_webview_window(&label).is_some();
+    let presence = registry_state.lock().unwrap().presence(&label, live);
+    match presence {
+        WindowPresence::RegistryOnly => log::warn!("Window '{}' is registered but has no live window", label),
+        WindowPresence::LiveOnly => log::warn!("Window '{}' is open but not registered", label),
+        WindowPresence::Present | WindowPresence::Absent => {}
+    }
+    Ok(presence == WindowPresence::Present)
+}
+
+// Kept alongside get_monitor_info for older frontend code; both read the same cache
+#[tauri::command]
+async fn get_monitors(app: AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    enumerate_monitors(&app)
+}
+
+// Window persistence specific commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowPositionSize {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub is_maximized: bool,
+    pub is_minimized: bool,
+    pub is_fullscreen: bool,
+    pub monitor: u32,
+    pub monitor_name: Option<String>,
+    // MonitorInfo.id; states saved before it existed only have the name
+    #[serde(default)]
+    pub monitor_id: Option<String>,
+}
+
+impl WindowPositionSize {
+    fn monitor_key(&self) -> Option<&str> {
+        self.monitor_id.as_deref().or(self.monitor_name.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorInfoResponse {
+    pub monitors: Vec<MonitorInfo>,
+    // How long ago the list was enumerated; 0 right after a forced refresh
+    pub cache_age_ms: u64,
+}
+
+// Served from the cache, which the display watcher keeps current; `force` re-enumerates
+// first, emitting `monitors-changed` if that turns up a difference
+#[tauri::command]
+async fn get_monitor_info(force: Option<bool>, app: AppHandle) -> Result<MonitorInfoResponse, String> {
+    if force.unwrap_or(false) {
+        refresh_monitors(&app)?;
+    }
+    let snapshot = monitor_snapshot(&app)?;
+    Ok(MonitorInfoResponse {
+        monitors: snapshot.monitors.to_vec(),
+        cache_age_ms: snapshot.age_ms(now_millis()),
+    })
+}
+
+// One enumeration and when it was taken
+#[derive(Debug, Clone)]
+struct MonitorSnapshot {
+    monitors: Arc<Vec<MonitorInfo>>,
+    taken_at: u64,
+}
+
+impl MonitorSnapshot {
+    fn age_ms(&self, now: u64) -> u64 {
+        now.saturating_sub(self.taken_at)
+    }
+}
+
+// Last enumeration result; None until first asked for. The lock is only held to clone or
+// swap the Arc, so a refresh never holds up readers while it enumerates
+type MonitorCacheState = Mutex<Option<MonitorSnapshot>>;
+
+// Replaces the cached list, returning how it differs from the one it replaced (None if
+// there wasn't one). Readers holding the old Arc keep a consistent list
+fn store_monitor_snapshot(cache: &MonitorCacheState, monitors: Vec<MonitorInfo>, now: u64) -> Option<MonitorChanges> {
+    let snapshot = MonitorSnapshot { monitors: Arc::new(monitors), taken_at: now };
+    let previous = cache.lock().unwrap().replace(snapshot.clone());
+    previous.map(|previous| MonitorChanges::between(&previous.monitors, &snapshot.monitors))
+}
+
+fn monitor_snapshot(app: &AppHandle) -> Result<MonitorSnapshot, String> {
+    let cache = app.state::<MonitorCacheState>();
+    if let Some(snapshot) = cache.lock().unwrap().clone() {
+        return Ok(snapshot);
+    }
+    // Not under the cache lock: the query waits on the main thread, whose event handlers read the cache
+    let monitors = query_monitors(app)?;
+    store_monitor_snapshot(&cache, monitors, now_millis());
+    let snapshot = cache.lock().unwrap().clone();
+    snapshot.ok_or_else(|| "Monitor cache is empty".to_string())
+}
+
+// The one source of monitor data for commands, snapping and restore. Synchronous so
+// window event handlers can use it too
+fn enumerate_monitors(app: &AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    Ok(monitor_snapshot(app)?.monitors.to_vec())
+}
+
+// Display hotplug. Tauri doesn't surface WM_DISPLAYCHANGE, NSApplicationDidChangeScreenParameters
+// or RandR notifications, so a background thread re-enumerates and compares against the cache
+const DISPLAY_POLL_INTERVAL_MS: u64 = 2000;
+
+fn watch_displays(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(std::time::Duration::from_millis(DISPLAY_POLL_INTERVAL_MS));
+        let _ = refresh_monitors(&app);
+    });
+}
+
+// Re-enumerates monitors and, if the layout changed, tells the frontend. Returns whether it did
+fn refresh_monitors(app: &AppHandle) -> Result<bool, String> {
+    let monitors = query_monitors(app)?;
+    let changes = store_monitor_snapshot(&app.state::<MonitorCacheState>(), monitors, now_millis());
+    match changes {
+        Some(changes) if !changes.is_empty() => {
+            handle_monitors_changed(app, &changes);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+fn handle_monitors_changed(app: &AppHandle, changes: &MonitorChanges) {
+    let registry_state = app.state::<WindowRegistryState>();
+    let (rehomed, moves, restorable) = {
+        let mut registry = registry_state.lock().unwrap();
+        let displaced = registry.apply_monitor_changes(changes);
+        let mut moves = registry.rehome_displaced(&displaced, changes);
+        let rehomed: Vec<String> = moves.iter().map(|(label, _)| label.clone()).collect();
+        if registry.reclamp_on_display_change {
+            moves.extend(registry.offscreen_windows(&changes.monitors));
+        }
+        let restorable = if changes.added.is_empty() {
+            Vec::new()
+        } else {
+            registry.restorable_windows(&changes.monitors)
+        };
+        (rehomed, moves, restorable)
+    };
+
+    // The Moved/Resized handlers record the new geometry
+    apply_bounds(app, moves);
+
+    let _ = app.emit("monitors-changed", changes);
+    if !rehomed.is_empty() {
+        let _ = app.emit("windows-rehomed", &rehomed);
+    }
+    // The frontend decides whether to offer restore_displaced_windows
+    if !restorable.is_empty() {
+        let _ = app.emit("displaced-windows-restorable", &restorable);
+    }
+
+    // A rescale alone leaves the fingerprint as it was
+    if changes.added.is_empty() && changes.removed.is_empty() {
+        return;
+    }
+    let fingerprint = monitor_fingerprint(&changes.monitors);
+    if !std::path::Path::new(&monitor_layout_path(&fingerprint)).exists() {
+        return;
+    }
+    if registry_state.lock().unwrap().auto_apply_monitor_layouts {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let registry_state = app.state::<WindowRegistryState>();
+            if let Err(e) = apply_layout_for_current_monitors(app.clone(), registry_state).await {
+                log::error!("Failed to apply the layout for {}: {}", fingerprint, e);
+            }
+        });
+    } else {
+        let _ = app.emit("monitor-layout-available", &fingerprint);
+    }
+}
+
+fn apply_bounds(app: &AppHandle, moves: Vec<(String, (i32, i32, u32, u32))>) {
+    for (label, (x, y, width, height)) in moves {
+        if let Some(window) = app.get_webview_window(&label) {
+            let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
+            let _ = window.set_size(tauri::Size::Physical(tauri::PhysicalSize { width, height }));
+        }
+    }
+}
+
+// Moves windows rehomed after a disconnect back onto their monitor, if it's connected again.
+// Returns their labels
+#[tauri::command]
+async fn restore_displaced_windows(
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<String>, String> {
+    let monitors = enumerate_monitors(&app)?;
+    let moves = registry_state.lock().unwrap().restore_displaced(&monitors);
+    let labels = moves.iter().map(|(label, _)| label.clone()).collect();
+    apply_bounds(&app, moves);
+    Ok(labels)
+}
+
+// The monitor list the last `monitors-changed` was based on. Subscribe first, then call this,
+// and no change can slip in between
+#[tauri::command]
+async fn get_last_monitor_snapshot(app: AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    enumerate_monitors(&app)
+}
+
+fn query_cursor(app: &AppHandle, monitors: &[MonitorInfo]) -> Result<CursorPosition, String> {
+    let position = if cfg!(feature = "mock-monitors") {
+        tauri::PhysicalPosition::new(960.0, 540.0)
+    } else {
+        app.cursor_position().map_err(|e| e.to_string())?
+    };
+    cursor_position_on(position.x, position.y, monitors).ok_or_else(|| "No monitors found".to_string())
+}
+
+#[tauri::command]
+async fn get_cursor_position(app: AppHandle) -> Result<CursorPosition, String> {
+    let monitors = enumerate_monitors(&app)?;
+    query_cursor(&app, &monitors)
+}
+
+#[tauri::command]
+async fn get_cursor_monitor(app: AppHandle) -> Result<MonitorInfo, String> {
+    let monitors = enumerate_monitors(&app)?;
+    Ok(query_cursor(&app, &monitors)?.monitor)
+}
+
+#[tauri::command]
+async fn set_auto_apply_monitor_layouts(
+    enabled: bool,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    registry_state.lock().unwrap().auto_apply_monitor_layouts = enabled;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_reclamp_on_display_change(
+    enabled: bool,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    registry_state.lock().unwrap().reclamp_on_display_change = enabled;
+    Ok(())
+}
+
+fn query_monitors(app: &AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    if cfg!(feature = "mock-monitors") {
+        return Ok(vec![mock_monitor()]);
+    }
+
+    let primary = app.primary_monitor().map_err(|e| e.to_string())?;
+    let mut monitors: Vec<MonitorInfo> = app.available_monitors().map_err(|e| e.to_string())?
+        .iter()
+        .map(|monitor| to_monitor_info(monitor, primary.as_ref()))
+        .collect();
+    if monitors.is_empty() {
+        return Err("No monitors found".to_string());
+    }
+    ensure_primary(&mut monitors);
+    Ok(monitors)
+}
+
+fn to_monitor_info(monitor: &tauri::Monitor, primary: Option<&tauri::Monitor>) -> MonitorInfo {
+    let name = monitor.name().cloned().unwrap_or_else(|| "Unknown Monitor".to_string());
+    let position = *monitor.position();
+    let size = *monitor.size();
+    let is_primary = primary.is_some_and(|primary| *primary.position() == position && primary.name() == monitor.name());
+    let work = monitor.work_area();
+    let work_area_exact = work_area_reported() && work.size.width > 0 && work.size.height > 0;
+    let (work_x, work_y, work_width, work_height) = if work_area_exact {
+        (work.position.x, work.position.y, work.size.width, work.size.height)
+    } else {
+        (position.x, position.y, size.width, size.height)
+    };
+
+    MonitorInfo {
+        id: monitor_id(&name, position.x, position.y, size.width, size.height),
+        name,
+        width: size.width,
+        height: size.height,
+        x: position.x,
+        y: position.y,
+        scale_factor: monitor.scale_factor(),
+        is_primary,
+        work_x,
+        work_y,
+        work_width,
+        work_height,
+        work_area_exact,
+    }
+}
+
+// GDK reports a Wayland monitor's full geometry as its work area; X11, Windows and macOS
+// give the real one
+fn work_area_reported() -> bool {
+    if !cfg!(target_os = "linux") {
+        return true;
+    }
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let forced_x11 = std::env::var("GDK_BACKEND").is_ok_and(|backend| backend.starts_with("x11"));
+    !wayland || forced_x11
+}
+
+// Tauri has no persistent display id, and names repeat ("Generic PnP Monitor" twice), so
+// the id hashes name, position and resolution. FNV-1a rather than DefaultHasher, whose
+// output may change between Rust releases and would orphan every saved layout.
+fn monitor_id(name: &str, x: i32, y: i32, width: u32, height: u32) -> String {
+    format!("mon-{:016x}", fnv1a(&format!("{}@{},{}:{}x{}", name, x, y, width, height)))
+}
+
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+// Identifies a whole monitor setup (docked, undocked, ...) by its displays' ids and
+// geometries, whatever order they're enumerated in
+fn monitor_fingerprint(monitors: &[MonitorInfo]) -> String {
+    let mut keys: Vec<String> = monitors.iter()
+        .map(|m| format!("{}@{},{}:{}x{}", m.id, m.x, m.y, m.width, m.height))
+        .collect();
+    keys.sort();
+    format!("cfg-{:016x}", fnv1a(&keys.join(";")))
+}
+
+// Where a window saved on monitor `saved_id` belongs now. In order: that monitor; a
+// connected monitor that is the only one carrying the name, for layouts saved before ids
+// (the name may still have the "@x,y" position once appended to it); the monitor holding
+// the same place, left to right, as the saved one did in the layout's snapshot; the primary.
+fn resolve_saved_monitor<'a>(saved_id: &str, saved_monitors: &[MonitorInfo], monitors: &'a [MonitorInfo]) -> Option<&'a MonitorInfo> {
+    if let Some(monitor) = monitors.iter().find(|m| m.id == saved_id) {
+        return Some(monitor);
+    }
+
+    let legacy_name = saved_id.split_once('@').map_or(saved_id, |(name, _)| name);
+    let mut named = monitors.iter().filter(|m| m.name == legacy_name);
+    if let (Some(monitor), None) = (named.next(), named.next()) {
+        return Some(monitor);
+    }
+
+    let left_to_right = |list: &[MonitorInfo]| {
+        let mut ordered: Vec<usize> = (0..list.len()).collect();
+        ordered.sort_by_key(|&i| (list[i].x, list[i].y));
+        ordered
+    };
+    let rank = left_to_right(saved_monitors).into_iter().position(|i| saved_monitors[i].id == saved_id);
+    if let Some(index) = rank.and_then(|rank| left_to_right(monitors).get(rank).copied()) {
+        return monitors.get(index);
+    }
+
+    resolve_monitor(None, monitors)
+}
+
+// Wayland doesn't say which monitor is primary; placement needs one to fall back to
+fn ensure_primary(monitors: &mut [MonitorInfo]) {
+    if !monitors.iter().any(|m| m.is_primary) {
+        if let Some(first) = monitors.first_mut() {
+            first.is_primary = true;
+        }
+    }
+}
+
+fn mock_monitor() -> MonitorInfo {
+    MonitorInfo {
+        id: "primary".to_string(),
+        name: "Primary Monitor".to_string(),
+        width: 1920,
+        height: 1080,
+        x: 0,
+        y: 0,
+        scale_factor: 1.0,
+        is_primary: true,
+        work_x: 0,
+        work_y: 0,
+        work_width: 1920,
+        work_height: 1080,
+        work_area_exact: true,
+    }
+}
+
+// Height of the strip at the top of a window the user needs to grab it
+const TITLE_BAR_HEIGHT: f64 = 40.0;
+// How much of the title bar must be on a monitor for the window to count as reachable
+const MIN_VISIBLE_TITLE_BAR_WIDTH: f64 = 100.0;
+
+fn title_bar_visible_on(x: f64, y: f64, width: f64, monitor: &MonitorInfo) -> bool {
+    let left = x.max(monitor.x as f64);
+    let right = (x + width).min(monitor.x as f64 + monitor.width as f64);
+    let top = y.max(monitor.y as f64);
+    let bottom = (y + TITLE_BAR_HEIGHT).min(monitor.y as f64 + monitor.height as f64);
+
+    right - left >= MIN_VISIBLE_TITLE_BAR_WIDTH.min(width) && bottom - top >= TITLE_BAR_HEIGHT
+}
+
+fn distance_to_monitor(px: f64, py: f64, monitor: &MonitorInfo) -> f64 {
+    let left = monitor.x as f64;
+    let top = monitor.y as f64;
+    let dx = (left - px).max(0.0).max(px - (left + monitor.width as f64));
+    let dy = (top - py).max(0.0).max(py - (top + monitor.height as f64));
+    (dx * dx + dy * dy).sqrt()
+}
+
+// Keeps a window reachable: if no monitor shows its title bar, move it fully onto
+// the monitor nearest to the window's center
+fn clamp_to_visible(x: f64, y: f64, width: f64, height: f64, monitors: &[MonitorInfo]) -> (i32, i32) {
+    if monitors.iter().any(|m| title_bar_visible_on(x, y, width, m)) {
+        return (x as i32, y as i32);
+    }
+
+    let center_x = x + width / 2.0;
+    let center_y = y + height / 2.0;
+    let Some(nearest) = monitors.iter().min_by(|a, b| {
+        distance_to_monitor(center_x, center_y, a).total_cmp(&distance_to_monitor(center_x, center_y, b))
+    }) else {
+        return (x as i32, y as i32);
+    };
+
+    let left = nearest.x as f64;
+    let top = nearest.y as f64;
+    let max_x = (left + nearest.width as f64 - width).max(left);
+    let max_y = (top + nearest.height as f64 - height).max(top);
+
+    (x.clamp(left, max_x) as i32, y.clamp(top, max_y) as i32)
+}
+
+// Like clamp_to_visible, but also shrinks a window that's wider or taller than every
+// monitor so it can fit on one. Returns (x, y, width, height).
+fn clamp_rect_to_visible(x: f64, y: f64, width: f64, height: f64, monitors: &[MonitorInfo]) -> (i32, i32, u32, u32) {
+    let width = monitors.iter().map(|m| m.width as f64).reduce(f64::max).map_or(width, |max| width.min(max));
+    let height = monitors.iter().map(|m| m.height as f64).reduce(f64::max).map_or(height, |max| height.min(max));
+    let (x, y) = clamp_to_visible(x, y, width, height, monitors);
+    (x, y, width as u32, height as u32)
+}
+
+// The monitor a point lies on, or the closest one if it falls between displays
+fn monitor_for_point(px: f64, py: f64, monitors: &[MonitorInfo]) -> Option<&MonitorInfo> {
+    monitors.iter().min_by(|a, b| {
+        distance_to_monitor(px, py, a).total_cmp(&distance_to_monitor(px, py, b))
+    })
+}
+
+// Where the mouse is, in both coordinate systems, and the monitor it's on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CursorPosition {
+    pub physical_x: f64,
+    pub physical_y: f64,
+    // In the monitor's logical pixels, anchored at its origin (see logical_to_physical)
+    pub x: f64,
+    pub y: f64,
+    pub monitor: MonitorInfo,
+}
+
+// A cursor in the gap between two displays of different sizes belongs to the nearest one;
+// its logical position is then measured against that monitor even though it's outside it
+fn cursor_position_on(px: f64, py: f64, monitors: &[MonitorInfo]) -> Option<CursorPosition> {
+    let monitor = monitor_for_point(px, py, monitors)?;
+    Some(CursorPosition {
+        physical_x: px,
+        physical_y: py,
+        x: monitor.x as f64 + (px - monitor.x as f64) / monitor.scale_factor,
+        y: monitor.y as f64 + (py - monitor.y as f64) / monitor.scale_factor,
+        monitor: monitor.clone(),
+    })
+}
+
+fn overlap_area(x: f64, y: f64, width: f64, height: f64, monitor: &MonitorInfo) -> f64 {
+    let overlap_width = (x + width).min(monitor.x as f64 + monitor.width as f64) - x.max(monitor.x as f64);
+    let overlap_height = (y + height).min(monitor.y as f64 + monitor.height as f64) - y.max(monitor.y as f64);
+    overlap_width.max(0.0) * overlap_height.max(0.0)
+}
+
+// The monitor showing the largest part of a rectangle, the first listed on a tie (a window
+// split 50/50); off-screen rectangles get the nearest monitor
+fn monitor_for_rect(x: f64, y: f64, width: f64, height: f64, monitors: &[MonitorInfo]) -> Option<&MonitorInfo> {
+    let best = monitors.iter()
+        .map(|m| (m, overlap_area(x, y, width, height, m)))
+        .filter(|(_, area)| *area > 0.0)
+        .reduce(|best, next| if next.1 > best.1 { next } else { best })
+        .map(|(m, _)| m);
+
+    best.or_else(|| monitor_for_point(x + width / 2.0, y + height / 2.0, monitors))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorCoverage {
+    pub monitor_id: String,
+    pub monitor_name: String,
+    // Share of the window's area on this monitor, 0-100
+    pub percent: f64,
+}
+
+// How a window's area is split across monitors, in monitor order. Shares add up to less
+// than 100 when part of the window is off every screen
+fn monitor_coverage(x: f64, y: f64, width: f64, height: f64, monitors: &[MonitorInfo]) -> Vec<MonitorCoverage> {
+    let area = width * height;
+    monitors.iter()
+        .map(|m| MonitorCoverage {
+            monitor_id: m.id.clone(),
+            monitor_name: m.name.clone(),
+            percent: if area > 0.0 { overlap_area(x, y, width, height, m) / area * 100.0 } else { 0.0 },
+        })
+        .collect()
+}
+
+// Commands take logical coordinates: a monitor's origin as the OS reports it, plus offsets
+// and sizes in that monitor's own logical pixels. A window restored onto a display with
+// another scale keeps its size, and converting back and forth doesn't drift.
+fn logical_to_physical((x, y, width, height): (f64, f64, f64, f64), monitor: &MonitorInfo) -> (i32, i32, u32, u32) {
+    let scale = monitor.scale_factor;
+    (
+        (monitor.x as f64 + (x - monitor.x as f64) * scale).round() as i32,
+        (monitor.y as f64 + (y - monitor.y as f64) * scale).round() as i32,
+        (width * scale).round() as u32,
+        (height * scale).round() as u32,
+    )
+}
+
+fn physical_to_logical((x, y, width, height): (i32, i32, u32, u32), monitor: &MonitorInfo) -> (f64, f64, f64, f64) {
+    let scale = monitor.scale_factor;
+    (
+        monitor.x as f64 + (x - monitor.x) as f64 / scale,
+        monitor.y as f64 + (y - monitor.y) as f64 / scale,
+        width as f64 / scale,
+        height as f64 / scale,
+    )
+}
+
+// The monitor whose scale a logical rect is in: `preferred` (an id or name) if connected,
+// otherwise the one whose logical extent the rect overlaps most
+fn monitor_for_logical_rect<'a>(
+    (x, y, width, height): (f64, f64, f64, f64),
+    preferred: Option<&str>,
+    monitors: &'a [MonitorInfo],
+) -> Option<&'a MonitorInfo> {
+    if let Some(monitor) = preferred.and_then(|id| monitors.iter().find(|m| m.id == id || m.name == id)) {
+        return Some(monitor);
+    }
+    let logical: Vec<MonitorInfo> = monitors.iter()
+        .map(|m| MonitorInfo {
+            width: (m.width as f64 / m.scale_factor).round() as u32,
+            height: (m.height as f64 / m.scale_factor).round() as u32,
+            ..m.clone()
+        })
+        .collect();
+    let id = &monitor_for_rect(x, y, width, height, &logical)?.id;
+    monitors.iter().find(|m| &m.id == id)
+}
+
+fn logical_rect_to_physical(rect: (f64, f64, f64, f64), preferred: Option<&str>, monitors: &[MonitorInfo]) -> (i32, i32, u32, u32) {
+    match monitor_for_logical_rect(rect, preferred, monitors) {
+        Some(monitor) => logical_to_physical(rect, monitor),
+        None => (rect.0.round() as i32, rect.1.round() as i32, rect.2.round() as u32, rect.3.round() as u32),
+    }
+}
+
+fn physical_rect_to_logical(rect: (i32, i32, u32, u32), monitors: &[MonitorInfo]) -> (f64, f64, f64, f64) {
+    match monitor_for_rect(rect.0 as f64, rect.1 as f64, rect.2 as f64, rect.3 as f64, monitors) {
+        Some(monitor) => physical_to_logical(rect, monitor),
+        None => (rect.0 as f64, rect.1 as f64, rect.2 as f64, rect.3 as f64),
+    }
+}
+
+// Index and id of the monitor showing most of a rectangle, as persisted in WindowPositionSize
+fn monitor_index_for_rect(x: f64, y: f64, width: f64, height: f64, monitors: &[MonitorInfo]) -> (u32, Option<String>) {
+    monitor_for_rect(x, y, width, height, monitors)
+        .and_then(|monitor| {
+            let index = monitors.iter().position(|m| m.id == monitor.id)?;
+            Some((index as u32, Some(monitor.id.clone())))
+        })
+        .unwrap_or((0, None))
+}
+
+// Looks up a monitor by id, falling back to the primary (or any) monitor
+fn resolve_monitor<'a>(monitor_id: Option<&str>, monitors: &'a [MonitorInfo]) -> Option<&'a MonitorInfo> {
+    monitor_id
+        .and_then(|id| monitors.iter().find(|m| m.id == id))
+        .or_else(|| monitors.iter().find(|m| m.is_primary))
+        .or_else(|| monitors.first())
+}
+
+// Logical position for a new window of the given logical size, or None for Absolute
+// placement. Missing monitors fall back to the primary one.
+fn resolve_placement(
+    placement: &WindowPlacement,
+    width: f64,
+    height: f64,
+    focused_monitor_id: Option<&str>,
+    cursor_monitor_id: Option<&str>,
+    monitors: &[MonitorInfo],
+) -> Option<(f64, f64)> {
+    let monitor_id = match placement {
+        WindowPlacement::Absolute => return None,
+        WindowPlacement::CenterPrimary => None,
+        WindowPlacement::CenterOnFocusedMonitor => focused_monitor_id,
+        WindowPlacement::CenterOnCursorMonitor => cursor_monitor_id,
+        WindowPlacement::CenterOnMonitor(id) => Some(id.as_str()),
+        // Depend on earlier or open windows, so the registry resolves them
+        WindowPlacement::Cascade | WindowPlacement::SmartAvoidOverlap => return None,
+    };
+    let monitor = resolve_monitor(monitor_id, monitors)?;
+    let scale = monitor.scale_factor;
+    let (x, y) = centered_position(width * scale, height * scale, monitor);
+    Some((x as f64 / scale, y as f64 / scale))
+}
+
+// The monitor a new window will open on, as far as can be told before it's placed: the
+// configured monitor, else the one its placement centers on, else the primary
+fn size_target_monitor<'a>(
+    config: &WindowConfig,
+    focused_monitor_id: Option<&str>,
+    cursor_monitor_id: Option<&str>,
+    monitors: &'a [MonitorInfo],
+) -> Option<&'a MonitorInfo> {
+    let monitor_id = config.monitor_id.as_deref().or(match &config.placement {
+        WindowPlacement::CenterOnFocusedMonitor
+        | WindowPlacement::Cascade
+        | WindowPlacement::SmartAvoidOverlap => focused_monitor_id,
+        WindowPlacement::CenterOnCursorMonitor => cursor_monitor_id,
+        WindowPlacement::CenterOnMonitor(id) => Some(id.as_str()),
+        WindowPlacement::Absolute | WindowPlacement::CenterPrimary => None,
+    });
+    resolve_monitor(monitor_id, monitors)
+}
+
+// Logical position for a window opened on a given monitor. x/y are offsets from the
+// monitor's origin in its own logical pixels, so they mean the same thing whatever its
+// scale; without them the window is centered there.
+fn monitor_relative_position(
+    monitor_id: &str,
+    x: Option<f64>,
+    y: Option<f64>,
+    width: f64,
+    height: f64,
+    monitors: &[MonitorInfo],
+) -> Option<(f64, f64)> {
+    let monitor = resolve_monitor(Some(monitor_id), monitors)?;
+    let scale = monitor.scale_factor;
+    let (x, y) = match x.zip(y) {
+        Some((x, y)) => ((monitor.x as f64 + x * scale) as i32, (monitor.y as f64 + y * scale) as i32),
+        None => centered_position(width * scale, height * scale, monitor),
+    };
+    Some((x as f64 / scale, y as f64 / scale))
+}
+
+fn missing_monitor_warning(monitor_id: &str, monitors: &[MonitorInfo]) -> Option<String> {
+    (!monitors.iter().any(|m| m.id == monitor_id)).then(|| {
+        format!("Monitor '{}' isn't connected; the window was opened on the primary monitor", monitor_id)
+    })
+}
+
+fn centered_position(width: f64, height: f64, monitor: &MonitorInfo) -> (i32, i32) {
+    (
+        (monitor.x as f64 + (monitor.width as f64 - width) / 2.0) as i32,
+        (monitor.y as f64 + (monitor.height as f64 - height) / 2.0) as i32,
+    )
+}
+
+#[tauri::command]
+async fn center_window(
+    label: String,
+    use_full_area: Option<bool>,
+    app: AppHandle,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(&label) {
+        let position = window.outer_position().map_err(|e| e.to_string())?;
+        let size = window.outer_size().map_err(|e| e.to_string())?;
+        let (width, height) = (size.width as f64, size.height as f64);
+
+        let monitors = enumerate_monitors(&app)?;
+        let center_x = position.x as f64 + width / 2.0;
+        let center_y = position.y as f64 + height / 2.0;
+
+        if let Some(monitor) = monitor_for_point(center_x, center_y, &monitors) {
+            let (x, y) = centered_position(width, height, &monitor.usable(use_full_area.unwrap_or(false)));
+            window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+// Keeps the window's offset from its monitor's origin, then pulls it inside the target bounds
+fn translate_to_monitor(x: f64, y: f64, width: f64, height: f64, from: &MonitorInfo, to: &MonitorInfo) -> (i32, i32) {
+    let target_x = to.x as f64 + (x - from.x as f64);
+    let target_y = to.y as f64 + (y - from.y as f64);
+
+    let max_x = (to.x as f64 + to.width as f64 - width).max(to.x as f64);
+    let max_y = (to.y as f64 + to.height as f64 - height).max(to.y as f64);
+
+    (target_x.clamp(to.x as f64, max_x) as i32, target_y.clamp(to.y as f64, max_y) as i32)
+}
+
+#[tauri::command]
+async fn get_monitor_for_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Option<MonitorInfo>, String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| format!("Window '{}' not found", label))?;
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+
+    let monitors = enumerate_monitors(&app)?;
+    let monitor = monitor_for_rect(
+        position.x as f64,
+        position.y as f64,
+        size.width as f64,
+        size.height as f64,
+        &monitors,
+    ).cloned();
+
+    if let Some(monitor) = &monitor {
+        let mut registry = registry_state.lock().unwrap();
+        registry.update_window_state(&label, None, None, Some(monitor.id.clone()));
+    }
+
+    Ok(monitor)
+}
+
+#[tauri::command]
+async fn get_window_monitor_coverage(
+    label: String,
+    app: AppHandle,
+) -> Result<Vec<MonitorCoverage>, String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+
+    let monitors = enumerate_monitors(&app)?;
+    Ok(monitor_coverage(position.x as f64, position.y as f64, size.width as f64, size.height as f64, &monitors))
+}
+
+// Puts the window at the same fraction of the target's work area as it had on its own.
+// Only the position is mapped; the size keeps its logical dimensions across scale factors
+fn map_to_monitor(x: f64, y: f64, width: f64, height: f64, from: &MonitorInfo, to: &MonitorInfo) -> (i32, i32) {
+    let (from, to) = (from.usable(false), to.usable(false));
+    let fraction_x = ((x - from.x as f64) / from.width.max(1) as f64).clamp(0.0, 1.0);
+    let fraction_y = ((y - from.y as f64) / from.height.max(1) as f64).clamp(0.0, 1.0);
+
+    let target_x = to.x as f64 + fraction_x * to.width as f64;
+    let target_y = to.y as f64 + fraction_y * to.height as f64;
+    let max_x = (to.x as f64 + to.width as f64 - width).max(to.x as f64);
+    let max_y = (to.y as f64 + to.height as f64 - height).max(to.y as f64);
+
+    (target_x.clamp(to.x as f64, max_x).round() as i32, target_y.clamp(to.y as f64, max_y).round() as i32)
+}
+
+// A window's physical size on `to`: the same logical size, shrunk to fit the work area
+fn size_on_monitor(width: f64, height: f64, from: &MonitorInfo, to: &MonitorInfo) -> (u32, u32) {
+    let scale = to.scale_factor / from.scale_factor;
+    let work = to.usable(false);
+    (
+        (width * scale).min(work.width as f64).round() as u32,
+        (height * scale).min(work.height as f64).round() as u32,
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MonitorDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+    // Through all monitors left to right (then top to bottom), wrapping around
+    Next,
+    Previous,
+}
+
+// The monitor next to `current` in `direction`; None when there isn't one
+fn adjacent_monitor<'a>(current: &MonitorInfo, monitors: &'a [MonitorInfo], direction: MonitorDirection) -> Option<&'a MonitorInfo> {
+    let center = |m: &MonitorInfo| (m.x as f64 + m.width as f64 / 2.0, m.y as f64 + m.height as f64 / 2.0);
+    let (cx, cy) = center(current);
+
+    match direction {
+        MonitorDirection::Next | MonitorDirection::Previous => {
+            let mut ordered: Vec<&MonitorInfo> = monitors.iter().collect();
+            ordered.sort_by_key(|m| (m.x, m.y));
+            let index = ordered.iter().position(|m| m.id == current.id)?;
+            let step = if direction == MonitorDirection::Next { 1 } else { ordered.len() - 1 };
+            let target = ordered[(index + step) % ordered.len()];
+            (target.id != current.id).then_some(target)
+        }
+        _ => monitors.iter()
+            .filter(|m| m.id != current.id)
+            .filter_map(|m| {
+                let (dx, dy) = (center(m).0 - cx, center(m).1 - cy);
+                // Distance along the direction, with sideways offset counting double
+                let (along, across) = match direction {
+                    MonitorDirection::Left => (-dx, dy),
+                    MonitorDirection::Right => (dx, dy),
+                    MonitorDirection::Up => (-dy, dx),
+                    _ => (dy, dx),
+                };
+                (along > 0.0).then_some((m, along + across.abs() * 2.0))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(m, _)| m),
+    }
+}
+
+// With preserve_relative (the default) the window lands at the same fraction of the
+// target's work area; without it, at the same offset from the target's origin
+#[tauri::command]
+async fn move_window_to_monitor(
+    label: String,
+    monitor_id: String,
+    preserve_relative: Option<bool>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let monitors = enumerate_monitors(&app)?;
+    let target = monitors.iter()
+        .find(|m| m.id == monitor_id)
+        .ok_or_else(|| format!("Monitor '{}' not found", monitor_id))?;
+
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| format!("Window '{}' not found", label))?;
+
+    // Maximized windows have to be restored to move, then re-maximized on the new display
+    let was_maximized = window.is_maximized().map_err(|e| e.to_string())?;
+    if was_maximized {
+        window.unmaximize().map_err(|e| e.to_string())?;
+    }
+
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+    let (x, y) = (position.x as f64, position.y as f64);
+    let (width, height) = (size.width as f64, size.height as f64);
+
+    if let Some(current) = monitor_for_point(x + width / 2.0, y + height / 2.0, &monitors) {
+        let (new_width, new_height) = size_on_monitor(width, height, current, target);
+        let (x, y) = if preserve_relative.unwrap_or(true) {
+            map_to_monitor(x, y, new_width as f64, new_height as f64, current, target)
+        } else {
+            translate_to_monitor(x, y, new_width as f64, new_height as f64, current, target)
+        };
+        window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+            .map_err(|e| e.to_string())?;
+        if (new_width, new_height) != (size.width, size.height) {
+            window.set_size(tauri::Size::Physical(tauri::PhysicalSize { width: new_width, height: new_height }))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    if was_maximized {
+        window.maximize().map_err(|e| e.to_string())?;
+    }
+
+    {
+        let mut registry = registry_state.lock().unwrap();
+        registry.update_window_state(&label, None, Some(was_maximized), Some(monitor_id));
+    }
+
+    Ok(())
+}
+
+// For a "send to next monitor" shortcut; returns the monitor the window went to
+#[tauri::command]
+async fn move_window_to_next_monitor(
+    label: String,
+    direction: MonitorDirection,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<String, String> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() }.to_string())?;
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+
+    let monitors = enumerate_monitors(&app)?;
+    let current = monitor_for_rect(
+        position.x as f64,
+        position.y as f64,
+        size.width as f64,
+        size.height as f64,
+        &monitors,
+    ).ok_or_else(|| "No monitors available".to_string())?;
+    let target = adjacent_monitor(current, &monitors, direction)
+        .ok_or_else(|| format!("No monitor {:?} of '{}'", direction, current.id))?
+        .id.clone();
+
+    move_window_to_monitor(label, target.clone(), Some(true), app, registry_state).await?;
+    Ok(target)
+}
+
+#[tauri::command]
+async fn get_all_window_states(
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<std::collections::HashMap<String, WindowPositionSize>, String> {
+    let monitors = enumerate_monitors(&app)?;
+    let labels: Vec<String> = registry_state.lock().unwrap().windows.keys().cloned().collect();
+    let mut result = std::collections::HashMap::new();
+
+    for label in &labels {
+        if let Some(window) = app.get_webview_window(label) {
+            // The inner size, which set_window_state applies back
+            let position = window.outer_position().map_err(|e| e.to_string())?;
+            let size = window.inner_size().map_err(|e| e.to_string())?;
+            let is_maximized = window.is_maximized().map_err(|e| e.to_string())?;
+            let is_minimized = window.is_minimized().map_err(|e| e.to_string())?;
+            let is_fullscreen = window.is_fullscreen().map_err(|e| e.to_string())?;
+            let (monitor, monitor_id) = monitor_index_for_rect(
+                position.x as f64,
+                position.y as f64,
+                size.width as f64,
+                size.height as f64,
+                &monitors,
+            );
+            let monitor_name = monitor_id.as_ref().map(|_| monitors[monitor as usize].name.clone());
+
+            let (x, y, width, height) =
+                physical_rect_to_logical((position.x, position.y, size.width, size.height), &monitors);
+            result.insert(label.clone(), WindowPositionSize {
+                x,
+                y,
+                width,
+                height,
+                is_maximized,
+                is_minimized,
+                is_fullscreen,
+                monitor,
+                monitor_name,
+                monitor_id,
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+// What set_window_state would do with a saved state: the bounds it would apply after size
+// limits and on-screen clamping, and a warning for each correction. Geometry the window
+// config can't hold at all (NaN, non-positive sizes) is an error.
+#[derive(Debug, Clone, PartialEq)]
+struct WindowStatePlan {
+    rect: (i32, i32, u32, u32),
+    warnings: Vec<String>,
+}
+
+fn plan_window_state(
+    state: &WindowPositionSize,
+    config: &WindowConfig,
+    monitors: &[MonitorInfo],
+    allow_offscreen: bool,
+) -> Result<WindowStatePlan, WindowError> {
+    WindowConfig {
+        x: Some(state.x),
+        y: Some(state.y),
+        width: state.width,
+        height: state.height,
+        size_mode: SizeMode::Pixels,
+        ..config.clone()
+    }
+    .validate()
+    .map_err(|errors| WindowError::InvalidConfig { errors })?;
+
+    let mut warnings = Vec::new();
+    let monitor_connected = match state.monitor_key() {
+        Some(key) => monitors.iter().any(|m| m.id == key || m.name == key),
+        None => (state.monitor as usize) < monitors.len(),
+    };
+    if !monitor_connected {
+        let monitor = state.monitor_name.clone()
+            .or_else(|| state.monitor_id.clone())
+            .unwrap_or_else(|| format!("#{}", state.monitor));
+        warnings.push(format!("Monitor {} is not connected", monitor));
+    }
+
+    let (width, height) = config.clamp_size(state.width, state.height);
+    if width > state.width || height > state.height {
+        warnings.push(format!("Size {}x{} is below the window's minimum; it will be {}x{}", state.width, state.height, width, height));
+    }
+    if width < state.width || height < state.height {
+        warnings.push(format!("Size {}x{} is above the window's maximum; it will be {}x{}", state.width, state.height, width, height));
+    }
+
+    // Pull it back on-screen if its monitor is gone
+    let requested = logical_rect_to_physical((state.x, state.y, width, height), state.monitor_key(), monitors);
+    let rect = if allow_offscreen {
+        requested
+    } else {
+        clamp_rect_to_visible(requested.0 as f64, requested.1 as f64, requested.2 as f64, requested.3 as f64, monitors)
+    };
+    let (x, y, clamped_width, clamped_height) = physical_rect_to_logical(rect, monitors);
+    if (rect.0, rect.1) != (requested.0, requested.1) {
+        warnings.push(format!("Position ({}, {}) is off-screen; it will be moved to ({}, {})", state.x, state.y, x, y));
+    }
+    if (rect.2, rect.3) != (requested.2, requested.3) {
+        warnings.push(format!("Size {}x{} is larger than any monitor; it will be {}x{}", width, height, clamped_width, clamped_height));
+    }
+
+    Ok(WindowStatePlan { rect, warnings })
+}
+
+// Dry run of set_window_state: lists what it would have to correct without touching the window
+#[tauri::command]
+async fn validate_window_state(
+    label: String,
+    state: WindowPositionSize,
+    allow_offscreen: Option<bool>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<String>, String> {
+    let config = registry_state.lock().unwrap().get_window(&label)
+        .map_or_else(|| WindowConfig::synthesized(&label), |w| w.config.clone());
+    let plan = plan_window_state(&state, &config, &enumerate_monitors(&app)?, allow_offscreen.unwrap_or(false))?;
+    Ok(plan.warnings)
+}
+
+#[tauri::command]
+async fn set_window_state(
+    label: String,
+    state: WindowPositionSize,
+    allow_offscreen: Option<bool>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Option<WindowPositionSize>, String> {
+    // Check the requested geometry against the window's own config, flags included
+    let config = registry_state.lock().unwrap().get_window(&label)
+        .map_or_else(|| WindowConfig::synthesized(&label), |w| w.config.clone());
+    let monitors = enumerate_monitors(&app)?;
+    let plan = plan_window_state(&state, &config, &monitors, allow_offscreen.unwrap_or(false))?;
+
+    if let Some(window) = app.get_webview_window(&label) {
+        let (x, y, width, height) = plan.rect;
+        window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+            .map_err(|e| e.to_string())?;
+
+        // Set size
+        window.set_size(tauri::Size::Physical(tauri::PhysicalSize { width, height }))
+            .map_err(|e| e.to_string())?;
+
+        // Set maximized state
+        if state.is_maximized {
+            window.maximize().map_err(|e| e.to_string())?;
+        } else {
+            window.unmaximize().map_err(|e| e.to_string())?;
+        }
+
+        // Set minimized state
+        if state.is_minimized {
+            window.minimize().map_err(|e| e.to_string())?;
+        } else {
+            window.unminimize().map_err(|e| e.to_string())?;
+        }
+
+        // Set fullscreen state
+        window.set_fullscreen(state.is_fullscreen).map_err(|e| e.to_string())?;
+
+        // Update registry
+        {
+            let mut registry = registry_state.lock().unwrap();
+            let (x, y, width, height) = plan.rect;
+            let monitor_id = monitor_for_rect(x as f64, y as f64, width as f64, height as f64, &monitors).map(|m| m.id.clone());
+            registry.update_window_state(&label, Some(state.is_minimized), Some(state.is_maximized), monitor_id);
+        }
+
+        let (x, y, width, height) = physical_rect_to_logical(plan.rect, &monitors);
+        return Ok(Some(WindowPositionSize { x, y, width, height, ..state }));
+    }
+
+    Ok(None)
+}
+
+// Window switching and keyboard shortcuts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum CycleMode {
+    #[serde(alias = "z")]
+    ZOrder,
+    #[default]
+    #[serde(alias = "mru")]
+    MostRecentlyUsed,
+}
+
+// `order` is the older name for `mode` and is still accepted
+#[tauri::command]
+async fn cycle_windows(
+    forward: bool,
+    mode: Option<CycleMode>,
+    order: Option<CycleMode>,
+    include_hidden: Option<bool>,
+    window_type: Option<String>,
+    registry_state: State<'_, WindowRegistryState>,
+    app: AppHandle,
+) -> Result<(), String> {
+    let next_window = {
+        let mut registry = registry_state.lock().unwrap();
+        registry.cycle_step(
+            mode.or(order).unwrap_or_default(),
+            forward,
+            include_hidden.unwrap_or(false),
+            window_type.as_deref(),
+            now_millis(),
+        )
+    };
+
+    if let Some(window_label) = next_window {
+        focus_app_window(window_label, app, registry_state).await?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn end_window_cycle(registry_state: State<'_, WindowRegistryState>) -> Result<(), String> {
+    let mut registry = registry_state.lock().unwrap();
+    registry.end_cycle(now_millis());
+    Ok(())
+}
+
+// Window snapping and arrangement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SnapPosition {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+    Maximize,
+    LeftThird,
+    CenterThird,
+    RightThird,
+    LeftTwoThirds,
+    RightTwoThirds,
+}
+
+// Target rectangle (x, y, width, height) for a snap on the given monitor, or None for
+// Maximize, which is left to the OS. Splits use integer boundaries so adjacent snaps
+// tile the monitor with no gaps or overlaps before `gap` is applied.
+fn compute_snap_rect(position: &SnapPosition, monitor: &MonitorInfo, gap: u32) -> Option<(i32, i32, u32, u32)> {
+    let (width, height) = (monitor.width, monitor.height);
+    let half_width = width / 2;
+    let half_height = height / 2;
+    let third = width / 3;
+    let two_thirds = width * 2 / 3;
+
+    let (x, y, w, h) = match position {
+        SnapPosition::Left => (0, 0, half_width, height),
+        SnapPosition::Right => (half_width, 0, width - half_width, height),
+        SnapPosition::Top => (0, 0, width, half_height),
+        SnapPosition::Bottom => (0, half_height, width, height - half_height),
+        SnapPosition::TopLeft => (0, 0, half_width, half_height),
+        SnapPosition::TopRight => (half_width, 0, width - half_width, half_height),
+        SnapPosition::BottomLeft => (0, half_height, half_width, height - half_height),
+        SnapPosition::BottomRight => (half_width, half_height, width - half_width, height - half_height),
+        SnapPosition::Center => {
+            // Rounded: 1440 * 0.7 comes out a hair under 1008
+            let center_width = (width as f64 * 0.7).round() as u32;
+            let center_height = (height as f64 * 0.7).round() as u32;
+            ((width - center_width) / 2, (height - center_height) / 2, center_width, center_height)
+        }
+        SnapPosition::Maximize => return None,
+        SnapPosition::LeftThird => (0, 0, third, height),
+        SnapPosition::CenterThird => (third, 0, two_thirds - third, height),
+        SnapPosition::RightThird => (two_thirds, 0, width - two_thirds, height),
+        SnapPosition::LeftTwoThirds => (0, 0, two_thirds, height),
+        SnapPosition::RightTwoThirds => (third, 0, width - third, height),
+    };
+
+    let (x, y, w, h) = inset_for_gap((x, y, w, h), width, height, gap);
+    Some((monitor.x + x as i32, monitor.y + y as i32, w, h))
+}
+
+// Where a window with physical bounds `current` goes for a snap, or None to maximize. It
+// snaps within the work area of the monitor showing most of it, wherever that monitor sits
+fn snap_rect_for_window(
+    position: &SnapPosition,
+    (x, y, width, height): (i32, i32, u32, u32),
+    monitors: &[MonitorInfo],
+    use_full_area: bool,
+    gap: u32,
+) -> Result<Option<(i32, i32, u32, u32)>, String> {
+    let monitor = monitor_for_rect(x as f64, y as f64, width as f64, height as f64, monitors)
+        .ok_or_else(|| "No monitors available".to_string())?;
+    Ok(compute_snap_rect(position, &monitor.usable(use_full_area), gap))
+}
+
+// Insets a monitor-relative rectangle by the full gap along monitor edges and half the
+// gap along edges shared with a neighbouring snap, so neighbours end up `gap` apart
+fn inset_for_gap(rect: (u32, u32, u32, u32), monitor_width: u32, monitor_height: u32, gap: u32) -> (u32, u32, u32, u32) {
+    let (x, y, w, h) = rect;
+    let inset = |at_edge: bool| if at_edge { gap } else { gap / 2 };
+
+    let left = inset(x == 0);
+    let top = inset(y == 0);
+    let right = inset(x + w == monitor_width);
+    let bottom = inset(y + h == monitor_height);
+
+    (x + left, y + top, w.saturating_sub(left + right), h.saturating_sub(top + bottom))
+}
+
+#[tauri::command]
+async fn snap_window(
+    label: String,
+    position: SnapPosition,
+    use_full_area: Option<bool>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let gap = registry_state.lock().unwrap().snap_gap;
+
+    if let Some(window) = app.get_webview_window(&label) {
+        let current_position = window.outer_position().map_err(|e| e.to_string())?;
+        let current_size = window.outer_size().map_err(|e| e.to_string())?;
+        let monitors = enumerate_monitors(&app)?;
+        let current = (current_position.x, current_position.y, current_size.width, current_size.height);
+
+        match snap_rect_for_window(&position, current, &monitors, use_full_area.unwrap_or(false), gap)? {
+            Some((x, y, width, height)) => {
+                window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+                    .map_err(|e| e.to_string())?;
+                window.set_size(tauri::Size::Physical(tauri::PhysicalSize { width, height }))
+                    .map_err(|e| e.to_string())?;
+            }
+            None => {
+                window.maximize().map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_snap_gap(
+    px: u32,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let mut registry = registry_state.lock().unwrap();
+    registry.snap_gap = px;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_magnetic_snap(
+    enabled: bool,
+    threshold: u32,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let mut registry = registry_state.lock().unwrap();
+    registry.magnetic_snap_enabled = enabled;
+    registry.magnetic_snap_threshold = threshold;
+    Ok(())
+}
+
+// Toggling always-on-top raises the window above other apps without stealing focus;
+// windows pinned on top stay in that band
+fn raise_natively(window: &tauri::WebviewWindow, label: &str, registry_state: &WindowRegistryState) -> Result<(), String> {
+    let pinned = {
+        let registry = registry_state.lock().unwrap();
+        registry.get_window(label).is_some_and(|w| w.config.always_on_top)
+    };
+    window.set_always_on_top(true).map_err(|e| e.to_string())?;
+    if !pinned {
+        window.set_always_on_top(false).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// There's no native "lower", so after a restack every visible window from `label`
+// upwards is raised again, bottom first, to make the OS match the registry
+fn sync_native_stacking(label: &str, app: &AppHandle, registry_state: &WindowRegistryState) -> Result<(), String> {
+    let labels = {
+        let registry = registry_state.lock().unwrap();
+        let windows = registry.get_windows_by_z_order();
+        let end = windows.iter().position(|w| w.label == label).map_or(0, |index| index + 1);
+        raise_order(&windows[..end], false)
+    };
+    for label in &labels {
+        if let Some(window) = app.get_webview_window(label) {
+            raise_natively(&window, label, registry_state)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ZPlacement {
+    Above,
+    Below,
+}
+
+// Reorders a window without focusing it
+#[tauri::command]
+async fn raise_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    registry_state.lock().unwrap().raise_window(&label)?;
+    sync_native_stacking(&label, &app, &registry_state)
+}
+
+#[tauri::command]
+async fn lower_window(
+    label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    registry_state.lock().unwrap().lower_window(&label)?;
+    sync_native_stacking(&label, &app, &registry_state)
+}
+
+#[tauri::command]
+async fn place_window(
+    label: String,
+    placement: ZPlacement,
+    target_label: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    registry_state.lock().unwrap().place_window(&label, placement, &target_label)?;
+    sync_native_stacking(&label, &app, &registry_state)
+}
+
+// Labels to raise, bottom of our stack first, so raising each in turn leaves the
+// topmost window on top instead of reversing the stacking
+fn raise_order(windows_by_z_order: &[&WindowState], include_minimized: bool) -> Vec<String> {
+    windows_by_z_order.iter()
+        .rev()
+        .filter(|w| w.is_visible && (include_minimized || !w.is_minimized))
+        .map(|w| w.label.clone())
+        .collect()
+}
+
+#[tauri::command]
+async fn bring_all_to_front(
+    include_minimized: Option<bool>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<String>, String> {
+    let (labels, focused_label) = {
+        let registry = registry_state.lock().unwrap();
+        let windows = registry.get_windows_by_z_order();
+        (
+            raise_order(&windows, include_minimized.unwrap_or(false)),
+            registry.get_focused_window().map(|w| w.label.clone()),
+        )
+    };
+
+    for label in &labels {
+        let Some(window) = app.get_webview_window(label) else {
+            continue;
+        };
+
+        if window.is_minimized().map_err(|e| e.to_string())? {
+            window.unminimize().map_err(|e| e.to_string())?;
+            let mut registry = registry_state.lock().unwrap();
+            registry.update_window_state(label, Some(false), None, None);
+        }
+
+        raise_natively(&window, label, &registry_state)?;
+    }
+
+    if let Some(window) = focused_label.and_then(|label| app.get_webview_window(&label)) {
+        window.set_focus().map_err(|e| e.to_string())?;
+    }
+
+    Ok(labels)
+}
+
+#[tauri::command]
+async fn assign_window_to_group(
+    label: String,
+    group_id: Option<String>,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    Ok(registry_state.lock().unwrap().assign_to_group(&label, group_id)?)
+}
+
+#[tauri::command]
+async fn get_group_windows(
+    group_id: String,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<WindowState>, String> {
+    let registry = registry_state.lock().unwrap();
+    Ok(registry.get_group_windows(&group_id).into_iter().cloned().collect())
+}
+
+// Raises a group's shown windows above everything else, bottom first, restoring minimized
+// ones. Returns the raised labels, bottom first.
+fn raise_group_natively(
+    group_id: &str,
+    app: &AppHandle,
+    registry_state: &WindowRegistryState,
+) -> Result<Vec<String>, String> {
+    let labels = registry_state.lock().unwrap().raise_group(group_id);
+
+    for label in &labels {
+        let Some(window) = app.get_webview_window(label) else {
+            continue;
+        };
+        if window.is_minimized().map_err(|e| e.to_string())? {
+            window.unminimize().map_err(|e| e.to_string())?;
+            registry_state.lock().unwrap().update_window_state(label, Some(false), None, None);
+        }
+        raise_natively(&window, label, registry_state)?;
+    }
+    Ok(labels)
+}
+
+// Brings a whole group forward together and focuses its topmost window
+#[tauri::command]
+async fn focus_group(
+    group_id: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<String>, String> {
+    let labels = raise_group_natively(&group_id, &app, &registry_state)?;
+    if let Some(top) = labels.last() {
+        focus_app_window(top.clone(), app, registry_state).await?;
+    }
+    Ok(labels)
+}
+
+// Like focus_group, but focus goes back to the member the user last worked in rather
+// than whichever happens to be on top
+#[tauri::command]
+async fn raise_group(
+    group_id: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<String>, String> {
+    let labels = raise_group_natively(&group_id, &app, &registry_state)?;
+    let target = registry_state.lock().unwrap().group_focus_target(&group_id);
+    if let Some(label) = target {
+        focus_app_window(label, app, registry_state).await?;
+    }
+    Ok(labels)
+}
+
+// Cell rectangles (x, y, width, height) for `count` windows in an even grid with
+// ceil(sqrt(count)) columns, `gap` pixels between cells and around the edges
+fn grid_layout(count: usize, monitor: &MonitorInfo, gap: u32) -> Vec<(i32, i32, u32, u32)> {
+    if count == 0 {
+        return vec![];
+    }
+
+    let columns = (count as f64).sqrt().ceil() as u32;
+    let rows = (count as u32).div_ceil(columns);
+    let cell_width = monitor.width.saturating_sub(gap * (columns + 1)) / columns;
+    let cell_height = monitor.height.saturating_sub(gap * (rows + 1)) / rows;
+
+    (0..count as u32)
+        .map(|i| {
+            let (column, row) = (i % columns, i / columns);
+            (
+                monitor.x + (gap + column * (cell_width + gap)) as i32,
+                monitor.y + (gap + row * (cell_height + gap)) as i32,
+                cell_width,
+                cell_height,
+            )
+        })
+        .collect()
+}
+
+#[tauri::command]
+async fn tile_windows(
+    gap: Option<u32>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<usize, String> {
+    let (labels, focused_monitor_id) = {
+        let registry = registry_state.lock().unwrap();
+        let labels: Vec<String> = registry.get_visible_windows_by_z_order().into_iter()
+            .filter(|w| !w.is_minimized)
+            .map(|w| w.label.clone())
+            .collect();
+        let focused_monitor_id = registry.get_focused_window().and_then(|w| w.monitor_id.clone());
+        (labels, focused_monitor_id)
+    };
+
+    let monitors = enumerate_monitors(&app)?;
+    let monitor = resolve_monitor(focused_monitor_id.as_deref(), &monitors)
+        .ok_or_else(|| "No monitors available".to_string())?;
+
+    // Fullscreen windows keep their place
+    let mut windows = Vec::new();
+    for label in labels {
+        if let Some(window) = app.get_webview_window(&label) {
+            if !window.is_fullscreen().map_err(|e| e.to_string())? {
+                windows.push((label, window));
+            }
+        }
+    }
+
+    let cells = grid_layout(windows.len(), monitor, gap.unwrap_or(0));
+    for ((label, window), (x, y, width, height)) in windows.iter().zip(cells) {
+        if window.is_maximized().map_err(|e| e.to_string())? {
+            window.unmaximize().map_err(|e| e.to_string())?;
+            let mut registry = registry_state.lock().unwrap();
+            registry.update_window_state(label, None, Some(false), None);
+        }
+        window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+            .map_err(|e| e.to_string())?;
+        window.set_size(tauri::Size::Physical(tauri::PhysicalSize { width, height }))
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(windows.len())
+}
+
+const CASCADE_OFFSET: u32 = 30;
+// Cascaded windows are resized to this fraction of the monitor
+const CASCADE_SIZE_RATIO: f64 = 0.6;
+
+// Top-left corners for `count` cascaded windows of the given size, stepping `offset`
+// pixels diagonally and wrapping back to the monitor's corner before running off it
+fn cascade_layout(count: usize, monitor: &MonitorInfo, width: u32, height: u32, offset: u32) -> Vec<(i32, i32)> {
+    let offset = offset.max(1);
+    let steps_x = monitor.width.saturating_sub(width) / offset;
+    let steps_y = monitor.height.saturating_sub(height) / offset;
+    let per_cycle = steps_x.min(steps_y) + 1;
+
+    (0..count as u32)
+        .map(|i| {
+            let step = (i % per_cycle) * offset;
+            (monitor.x + step as i32, monitor.y + step as i32)
+        })
+        .collect()
+}
+
+// Top-left for a new width x height window on `monitor`: centered if no existing window's
+// corner is within `offset` of that spot, otherwise the first `offset` diagonal step (down
+// and right, then up and left) that is. When every step is taken, the one furthest from
+// any existing corner wins.
+fn avoid_overlap_position(
+    width: u32,
+    height: u32,
+    existing: &[(i32, i32, u32, u32)],
+    monitor: &MonitorInfo,
+    offset: i32,
+) -> (i32, i32) {
+    let offset = offset.max(1);
+    let center = centered_position(width as f64, height as f64, monitor);
+    let fits = |(x, y): (i32, i32)| {
+        x >= monitor.x
+            && y >= monitor.y
+            && x as i64 + width as i64 <= monitor.x as i64 + monitor.width as i64
+            && y as i64 + height as i64 <= monitor.y as i64 + monitor.height as i64
+    };
+    let step = |k: i32| (center.0 + k * offset, center.1 + k * offset);
+    let candidates = std::iter::once(center)
+        .chain((1..).map(step).take_while(|&p| fits(p)))
+        .chain((1..).map(|k| step(-k)).take_while(|&p| fits(p)));
+    // Chebyshev distance to the nearest existing corner
+    let clearance = |(x, y): (i32, i32)| {
+        existing.iter()
+            .map(|&(ex, ey, _, _)| (x - ex).abs().max((y - ey).abs()))
+            .min()
+            .unwrap_or(i32::MAX)
+    };
+
+    let mut best = (center, clearance(center));
+    for candidate in candidates {
+        let distance = clearance(candidate);
+        if distance >= offset {
+            return candidate;
+        }
+        if distance > best.1 {
+            best = (candidate, distance);
+        }
+    }
+    best.0
+}
+
+#[tauri::command]
+async fn cascade_windows(
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<usize, String> {
+    let (labels, focused_label, focused_monitor_id) = {
+        let registry = registry_state.lock().unwrap();
+        // Bottom of the stack first so the focused window ends up with the last slot, on top
+        let labels: Vec<String> = registry.get_visible_windows_by_z_order().into_iter()
+            .rev()
+            .filter(|w| !w.is_minimized)
+            .map(|w| w.label.clone())
+            .collect();
+        let focused = registry.get_focused_window();
+        (
+            labels,
+            focused.map(|w| w.label.clone()),
+            focused.and_then(|w| w.monitor_id.clone()),
+        )
+    };
+
+    let monitors = enumerate_monitors(&app)?;
+    let monitor = resolve_monitor(focused_monitor_id.as_deref(), &monitors)
+        .ok_or_else(|| "No monitors available".to_string())?;
+
+    let windows: Vec<_> = labels.iter()
+        .filter_map(|label| app.get_webview_window(label).map(|window| (label, window)))
+        .collect();
+
+    let width = (monitor.width as f64 * CASCADE_SIZE_RATIO) as u32;
+    let height = (monitor.height as f64 * CASCADE_SIZE_RATIO) as u32;
+    let positions = cascade_layout(windows.len(), monitor, width, height, CASCADE_OFFSET);
+
+    for ((label, window), (x, y)) in windows.iter().zip(positions) {
+        if window.is_maximized().map_err(|e| e.to_string())? {
+            window.unmaximize().map_err(|e| e.to_string())?;
+            let mut registry = registry_state.lock().unwrap();
+            registry.update_window_state(label, None, Some(false), None);
+        }
+        window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+            .map_err(|e| e.to_string())?;
+        window.set_size(tauri::Size::Physical(tauri::PhysicalSize { width, height }))
+            .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(window) = focused_label.and_then(|label| app.get_webview_window(&label)) {
+        window.set_focus().map_err(|e| e.to_string())?;
+    }
+
+    Ok(windows.len())
+}
+
+// Batched window operations
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op")]
+pub enum WindowLayoutOp {
+    Create {
+        window_type: String,
+        #[serde(default)]
+        config: Box<PartialWindowConfig>,
+        #[serde(default)]
+        label: Option<String>,
+    },
+    Move { label: String, x: f64, y: f64 },
+    Resize { label: String, width: f64, height: f64 },
+    Focus { label: String },
+    Minimize { label: String },
+    Snap { label: String, position: SnapPosition },
+}
+
+impl WindowLayoutOp {
+    // The window the op acts on; None for a Create that lets the label be generated
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            WindowLayoutOp::Create { label, .. } => label.as_deref(),
+            WindowLayoutOp::Move { label, .. }
+            | WindowLayoutOp::Resize { label, .. }
+            | WindowLayoutOp::Focus { label }
+            | WindowLayoutOp::Minimize { label }
+            | WindowLayoutOp::Snap { label, .. } => Some(label),
+        }
+    }
+}
+
+// Applies a whole arrangement in one IPC call. Moves and resizes go straight to the native
+// windows and are recorded in the registry together at the end; each op gets its own result
+// (the created label for Create) so one bad label doesn't abort the rest of the batch.
+#[tauri::command]
+async fn apply_window_layout(
+    ops: Vec<WindowLayoutOp>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+    templates_state: State<'_, WindowTemplatesState>,
+) -> Result<Vec<Result<Option<String>, WindowError>>, String> {
+    let monitors = enumerate_monitors(&app)?;
+    let mut configs: HashMap<String, WindowConfig> = registry_state.lock().unwrap().windows.iter()
+        .map(|(label, window)| (label.clone(), window.config.clone()))
+        .collect();
+    let mut positions: HashMap<String, (i32, i32)> = HashMap::new();
+    let mut sizes: HashMap<String, (u32, u32)> = HashMap::new();
+    let mut results = Vec::with_capacity(ops.len());
+
+    for op in ops {
+        if let WindowLayoutOp::Create { window_type, config, label } = op {
+            let template = template_for(&templates_state.lock().unwrap(), &window_type);
+            let created = open_app_window(app.clone(), window_type, (*config).merge_over(&template), label, registry_state.clone()).await;
+            results.push(created.map(|state| {
+                configs.insert(state.label.clone(), state.config);
+                Some(state.label)
+            }));
+            continue;
+        }
+
+        let label = op.label().unwrap_or_default().to_string();
+        let Some(window) = app.get_webview_window(&label) else {
+            results.push(Err(WindowError::NotFound { label }));
+            continue;
+        };
+
+        let result = match op {
+            WindowLayoutOp::Create { .. } => unreachable!("Create ops are handled above"),
+            WindowLayoutOp::Move { x, y, .. } => layout_move(&window, x, y, &monitors).map(|position| {
+                positions.insert(label.clone(), position);
+            }),
+            WindowLayoutOp::Resize { width, height, .. } => {
+                let (width, height) = configs.get(&label).map_or((width, height), |c| c.clamp_size(width, height));
+                layout_resize(&window, width, height, &monitors).map(|size| {
+                    sizes.insert(label.clone(), size);
+                })
+            }
+            WindowLayoutOp::Focus { .. } => focus_app_window(label.clone(), app.clone(), registry_state.clone()).await,
+            WindowLayoutOp::Minimize { .. } => minimize_window(label.clone(), app.clone(), registry_state.clone()).await,
+            WindowLayoutOp::Snap { position, .. } => {
+                snap_window(label.clone(), position, None, app.clone(), registry_state.clone()).await
+            }
+        };
+
+        results.push(result.map(|_| None).map_err(|message| WindowError::Failed { label, message }));
+    }
+
+    // The Moved/Resized handlers are throttled, so record the whole layout at once
+    {
+        let mut registry = registry_state.lock().unwrap();
+        for (label, (x, y)) in &positions {
+            registry.update_window_position(label, *x, *y);
+        }
+        for (label, (width, height)) in &sizes {
+            registry.update_window_size(label, *width, *height);
+        }
+        for label in positions.keys().chain(sizes.keys()) {
+            registry.refresh_monitor_assignment(label, &monitors);
+        }
+    }
+
+    Ok(results)
+}
+
+// A layout Move, taking logical coordinates like move_window; returns the physical position set
+fn layout_move(window: &tauri::WebviewWindow, x: f64, y: f64, monitors: &[MonitorInfo]) -> Result<(i32, i32), String> {
+    let scale = window.scale_factor().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?.to_logical::<f64>(scale);
+    let (x, y, width, height) = logical_rect_to_physical((x, y, size.width, size.height), None, monitors);
+    let (x, y) = clamp_to_visible(x as f64, y as f64, width as f64, height as f64, monitors);
+    window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+        .map_err(|e| e.to_string())?;
+    Ok((x, y))
+}
+
+// A layout Resize to a logical size, scaled for the monitor the window is on; returns the
+// physical size set
+fn layout_resize(window: &tauri::WebviewWindow, width: f64, height: f64, monitors: &[MonitorInfo]) -> Result<(u32, u32), String> {
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+    let (x, y, _, _) = physical_rect_to_logical((position.x, position.y, size.width, size.height), monitors);
+    let (_, _, width, height) = logical_rect_to_physical((x, y, width, height), None, monitors);
+    window.set_size(tauri::Size::Physical(tauri::PhysicalSize { width, height }))
+        .map_err(|e| e.to_string())?;
+    Ok((width, height))
+}
+
+// Older name for apply_window_layout
+#[tauri::command]
+async fn apply_window_operations(
+    ops: Vec<WindowLayoutOp>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+    templates_state: State<'_, WindowTemplatesState>,
+) -> Result<Vec<Result<Option<String>, WindowError>>, String> {
+    apply_window_layout(ops, app, registry_state, templates_state).await
+}
+
+// Window state persistence
+const WINDOW_STATE_FILE_PREFIX: &str = "politicail_windows";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedLayout {
+    pub windows: Vec<WindowState>,
+    #[serde(default)]
+    pub snap_gap: u32,
+    // The window that had focus when the layout was saved
+    #[serde(default)]
+    pub focused_label: Option<String>,
+    // The displays at save time, so windows on one that's gone can go to its counterpart
+    #[serde(default)]
+    pub monitors: Vec<MonitorInfo>,
+}
+
+// Layout files used to be a bare array of windows. Entries stay raw JSON here so each
+// one is decoded on its own.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedLayoutFile {
+    Layout {
+        windows: Vec<serde_json::Value>,
+        #[serde(default)]
+        snap_gap: u32,
+        #[serde(default)]
+        focused_label: Option<String>,
+        #[serde(default)]
+        monitors: Vec<MonitorInfo>,
+    },
+    Legacy(Vec<serde_json::Value>),
+}
+
+// Returns the windows that decoded and a description of each entry that didn't, so one
+// bad entry costs only that window rather than the whole layout
+fn decode_saved_windows(entries: Vec<serde_json::Value>) -> (Vec<WindowState>, Vec<String>) {
+    let mut windows = Vec::new();
+    let mut dropped = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let label = entry.get("label").and_then(|label| label.as_str()).map(str::to_string);
+        match serde_json::from_value::<WindowState>(entry) {
+            Ok(window) => windows.push(window),
+            Err(e) => dropped.push(match label {
+                Some(label) => format!("'{}': {}", label, e),
+                None => format!("#{}: {}", index, e),
+            }),
+        }
+    }
+    (windows, dropped)
+}
+
+fn parse_saved_layout(json_data: &str) -> Result<SavedLayout, String> {
+    let (entries, snap_gap, focused_label, monitors) = match serde_json::from_str(json_data).map_err(|e| e.to_string())? {
+        SavedLayoutFile::Layout { windows, snap_gap, focused_label, monitors } => (windows, snap_gap, focused_label, monitors),
+        SavedLayoutFile::Legacy(windows) => (windows, 0, None, Vec::new()),
+    };
+    let (windows, dropped) = decode_saved_windows(entries);
+    for entry in dropped {
+        log::warn!("Skipping saved window {}", entry);
+    }
+    Ok(SavedLayout { windows, snap_gap, focused_label, monitors })
+}
+
+fn window_state_dir() -> String {
+    std::env::var("APPDATA").unwrap_or_else(|_| "/tmp".to_string())
+}
+
+// Profile names become part of a file name, so keep them to a safe character set
+fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!(
+            "Invalid profile name '{}': only letters, digits, '-' and '_' are allowed",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn window_state_file_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(name) => format!("{}_{}.json", WINDOW_STATE_FILE_PREFIX, name),
+        None => format!("{}.json", WINDOW_STATE_FILE_PREFIX),
+    }
+}
+
+fn profile_from_file_name(file_name: &str) -> Option<String> {
+    let name = file_name
+        .strip_prefix(WINDOW_STATE_FILE_PREFIX)?
+        .strip_prefix('_')?
+        .strip_suffix(".json")?;
+    validate_profile_name(name).ok()?;
+    Some(name.to_string())
+}
+
+// Not a profile: the '@' keeps these out of list_window_profiles
+fn monitor_layout_file_name(fingerprint: &str) -> String {
+    format!("{}@{}.json", WINDOW_STATE_FILE_PREFIX, fingerprint)
+}
+
+fn monitor_layout_path(fingerprint: &str) -> String {
+    format!("{}/{}", window_state_dir(), monitor_layout_file_name(fingerprint))
+}
+
+fn window_state_path(profile: Option<&str>) -> Result<String, String> {
+    if let Some(name) = profile {
+        validate_profile_name(name)?;
+    }
+    Ok(format!("{}/{}", window_state_dir(), window_state_file_name(profile)))
+}
+
+#[tauri::command]
+async fn save_window_state(
+    profile: Option<String>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<(), String> {
+    let save_path = window_state_path(profile.as_deref())?;
+    let monitors = enumerate_monitors(&app).unwrap_or_default();
+    let layout = current_layout(&registry_state.lock().unwrap(), monitors);
+
+    // Save to a JSON file (simplified implementation)
+    let json_data = serde_json::to_string_pretty(&layout).map_err(|e| e.to_string())?;
+    std::fs::write(save_path, &json_data).map_err(|e| e.to_string())?;
+    // Also the latest arrangement for this monitor setup, for when it's plugged back in
+    if !layout.monitors.is_empty() {
+        std::fs::write(monitor_layout_path(&monitor_fingerprint(&layout.monitors)), json_data)
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn current_layout(registry: &WindowRegistry, monitors: Vec<MonitorInfo>) -> SavedLayout {
+    SavedLayout {
+        windows: registry.get_windows_by_z_order().into_iter().cloned().collect(),
+        snap_gap: registry.snap_gap,
+        focused_label: registry.get_focused_window().map(|w| w.label.clone()),
+        monitors,
+    }
+}
+
+// Saves the arrangement for the connected monitors only, leaving profiles alone. Returns
+// the setup's fingerprint
+#[tauri::command]
+async fn save_layout_for_current_monitors(
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<String, String> {
+    let monitors = enumerate_monitors(&app)?;
+    let fingerprint = monitor_fingerprint(&monitors);
+    let layout = current_layout(&registry_state.lock().unwrap(), monitors);
+
+    let json_data = serde_json::to_string_pretty(&layout).map_err(|e| e.to_string())?;
+    std::fs::write(monitor_layout_path(&fingerprint), json_data).map_err(|e| e.to_string())?;
+    Ok(fingerprint)
+}
+
+// Rearranges open windows to the layout saved for the connected monitors, opening any
+// that are missing. Nothing happens if none was saved
+#[tauri::command]
+async fn apply_layout_for_current_monitors(
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<RestoreReport, String> {
+    let monitors = enumerate_monitors(&app)?;
+    let path = monitor_layout_path(&monitor_fingerprint(&monitors));
+    if !std::path::Path::new(&path).exists() {
+        return Ok(RestoreReport::default());
+    }
+
+    let json_data = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let layout = parse_saved_layout(&json_data)?;
+    restore_layout(layout, false, true, true, app, registry_state).await
+}
+
+#[tauri::command]
+async fn list_window_profiles() -> Result<Vec<String>, String> {
+    let entries = match std::fs::read_dir(window_state_dir()) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut profiles: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| profile_from_file_name(&entry.file_name().to_string_lossy()))
+        .collect();
+    profiles.sort();
+
+    Ok(profiles)
+}
+
+#[tauri::command]
+async fn delete_window_profile(name: String) -> Result<(), String> {
+    let save_path = window_state_path(Some(&name))?;
+
+    if !std::path::Path::new(&save_path).exists() {
+        return Err(format!("Window profile '{}' not found", name));
+    }
+
+    std::fs::remove_file(save_path).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RestoreReport {
+    pub created: Vec<String>,
+    // Open windows with the saved window's type and url, focused instead of duplicated
+    pub reused: Vec<String>,
+}
+
+// Live geometry reflects where the user left the window, not where it was created, so it
+// replaces the saved config's. Layouts from before logical bounds were kept only have the
+// physical ones
+fn apply_saved_geometry(window_state: &mut WindowState) {
+    if let Some((x, y, width, height)) = window_state.logical_bounds {
+        window_state.config.x = Some(x);
+        window_state.config.y = Some(y);
+        window_state.config.set_pixel_size(width, height);
+    } else {
+        if let (Some(x), Some(y)) = (window_state.current_x, window_state.current_y) {
+            window_state.config.x = Some(x as f64);
+            window_state.config.y = Some(y as f64);
+        }
+        if let (Some(width), Some(height)) = (window_state.current_width, window_state.current_height) {
+            window_state.config.set_pixel_size(width as f64, height as f64);
+        }
+    }
+}
+
+// Moves a restored window's logical bounds from saved monitor `saved_id` to wherever
+// resolve_saved_monitor says it belongs, keeping its relative spot and logical size.
+// Returns the new monitor's id; None leaves the window to the usual on-screen clamping.
+fn relocate_saved_window(
+    config: &mut WindowConfig,
+    saved_id: &str,
+    saved_monitors: &[MonitorInfo],
+    monitors: &[MonitorInfo],
+) -> Option<String> {
+    let target = resolve_saved_monitor(saved_id, saved_monitors, monitors)?;
+    let (Some(x), Some(y)) = (config.x, config.y) else {
+        return Some(target.id.clone());
+    };
+    // The same display, or one known only by name: its logical coordinates still hold
+    let Some(saved) = saved_monitors.iter().find(|m| m.id == saved_id).filter(|m| m.id != target.id) else {
+        return Some(target.id.clone());
+    };
+
+    let (px, py, pw, ph) = logical_to_physical((x, y, config.width, config.height), saved);
+    let (width, height) = size_on_monitor(pw as f64, ph as f64, saved, target);
+    let (nx, ny) = map_to_monitor(px as f64, py as f64, width as f64, height as f64, saved, target);
+    let (x, y, width, height) = physical_to_logical((nx, ny, width, height), target);
+    config.x = Some(x);
+    config.y = Some(y);
+    config.width = width;
+    config.height = height;
+    Some(target.id.clone())
+}
+
+// The window to focus once a layout is restored: the one focused when it was saved, under
+// its restored label, or failing that the restored window that was topmost
+fn restore_focus_target(focused_label: Option<&str>, restored: &[(String, String, u32)]) -> Option<String> {
+    focused_label
+        .and_then(|focused| restored.iter().find(|(saved, _, _)| saved == focused))
+        .or_else(|| restored.iter().max_by_key(|(_, _, z_order)| *z_order))
+        .map(|(_, label, _)| label.clone())
+}
+
+#[tauri::command]
+async fn load_window_state(
+    profile: Option<String>,
+    allow_offscreen: Option<bool>,
+    skip_existing: Option<bool>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<Vec<String>, String> {
+    let report = load_window_state_v2(profile, allow_offscreen, skip_existing, app, registry_state).await?;
+    Ok(report.created.into_iter().chain(report.reused).collect())
+}
+
+// Same as load_window_state, but says which windows were opened and which were already there
+#[tauri::command]
+async fn load_window_state_v2(
+    profile: Option<String>,
+    allow_offscreen: Option<bool>,
+    skip_existing: Option<bool>,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<RestoreReport, String> {
+    let save_path = window_state_path(profile.as_deref())?;
+
+    if !std::path::Path::new(&save_path).exists() {
+        return Ok(RestoreReport::default());
+    }
+
+    let json_data = std::fs::read_to_string(save_path).map_err(|e| e.to_string())?;
+    let layout = parse_saved_layout(&json_data)?;
+    restore_layout(layout, allow_offscreen.unwrap_or(false), skip_existing.unwrap_or(false), false, app, registry_state).await
+}
+
+// With `rearrange_existing`, reused windows are also moved to their saved bounds
+async fn restore_layout(
+    layout: SavedLayout,
+    allow_offscreen: bool,
+    skip_existing: bool,
+    rearrange_existing: bool,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+) -> Result<RestoreReport, String> {
+    let saved_windows = layout.windows;
+    let monitors = enumerate_monitors(&app)?;
+
+    registry_state.lock().unwrap().snap_gap = layout.snap_gap;
+
+    let mut report = RestoreReport::default();
+    // Windows this restore has already accounted for, so each is reused at most once
+    let mut claimed: Vec<String> = Vec::new();
+    // (saved label, label now, saved z-order) for every window the layout accounts for
+    let mut restored: Vec<(String, String, u32)> = Vec::new();
+
+    for mut window_state in saved_windows {
+        apply_saved_geometry(&mut window_state);
+
+        // A window whose display is gone moves to the display that took its place
+        if let Some(target) = window_state.monitor_id.as_deref()
+            .and_then(|saved_id| relocate_saved_window(&mut window_state.config, saved_id, &layout.monitors, &monitors))
+        {
+            window_state.monitor_id = Some(target);
+        }
+
+        // Zoom changed at runtime lives on the window state; creation reads it from the config
+        window_state.config.zoom = Some(window_state.zoom);
+
+        // The layout may have been saved with a display that's no longer connected
+        if allow_offscreen {
+            window_state.config.allow_offscreen = true;
+        } else if let (Some(x), Some(y)) = (window_state.config.x, window_state.config.y) {
+            let rect = (x, y, window_state.config.width, window_state.config.height);
+            let (x, y, width, height) = logical_rect_to_physical(rect, window_state.monitor_id.as_deref(), &monitors);
+            let clamped = clamp_rect_to_visible(x as f64, y as f64, width as f64, height as f64, &monitors);
+            let (x, y, width, height) = physical_rect_to_logical(clamped, &monitors);
+            window_state.config.x = Some(x);
+            window_state.config.y = Some(y);
+            window_state.config.set_pixel_size(width, height);
+        }
+
+        if skip_existing {
+            let existing = registry_state.lock().unwrap()
+                .reusable_window(&window_state.config.window_type, window_state.config.url.as_deref(), &claimed);
+            if let Some(existing) = existing {
+                if let (true, Some(x), Some(y)) = (rearrange_existing, window_state.config.x, window_state.config.y) {
+                    let _ = move_window(existing.clone(), x, y, Some(allow_offscreen), app.clone()).await;
+                    let _ = resize_app_window(
+                        existing.clone(),
+                        window_state.config.width,
+                        window_state.config.height,
+                        app.clone(),
+                        registry_state.clone(),
+                    ).await;
+                }
+                let _ = focus_app_window(existing.clone(), app.clone(), registry_state.clone()).await;
+                restored.push((window_state.label.clone(), existing.clone(), window_state.z_order));
+                claimed.push(existing.clone());
+                report.reused.push(existing);
+                continue;
+            }
+        }
+
+        // Keep the saved label so supplied labels survive a restore; fall back to a fresh one if it's in use
+        let saved_label = registry_state.lock().unwrap().check_label(&window_state.label).is_ok()
+            .then(|| window_state.label.clone());
+        let result = open_app_window(
+            app.clone(),
+            window_state.config.window_type.clone(),
+            window_state.config.clone(),
+            saved_label,
+            registry_state.clone()
+        ).await;
+
+        if let Ok(created) = result {
+            let label = created.label;
+            restored.push((window_state.label.clone(), label.clone(), window_state.z_order));
+            claimed.push(label.clone());
+            report.created.push(label.clone());
+            let _ = registry_state.lock().unwrap().assign_to_group(&label, window_state.group_id.clone());
+            if let Some(window) = registry_state.lock().unwrap().windows.get_mut(&label) {
+                window.metadata = window_state.metadata.clone();
+            }
+            if window_state.click_through {
+                let _ = set_click_through(label.clone(), true, app.clone(), registry_state.clone()).await;
+            }
+
+            // Restore window position, unless the window already landed there
+            if let (Some(x), Some(y)) = (window_state.config.x, window_state.config.y) {
+                let landed = created.logical_bounds.map(|(cx, cy, _, _)| (cx.round(), cy.round()));
+                if landed != Some((x.round(), y.round())) {
+                    let _ = move_window(label.clone(), x, y, Some(allow_offscreen), app.clone()).await;
+                }
+            }
+
+            let _ = resize_app_window(
+                label.clone(),
                 window_state.config.width,
                 window_state.config.height,
-                app.clone()
+                app.clone(),
+                registry_state.clone(),
             ).await;
 
-            // Restore window state
-            if window_state.is_minimized {
-                let _ = minimize_window(label.clone(), app.clone(), registry_state.clone()).await;
-            } else if window_state.is_maximized {
-                let _ = maximize_window(label.clone(), app.clone(), registry_state.clone()).await;
+            // Restore window state
+            if !window_state.is_visible {
+                let _ = hide_window(label.clone(), app.clone(), registry_state.clone()).await;
+            } else if window_state.is_minimized {
+                let _ = minimize_window(label.clone(), app.clone(), registry_state.clone()).await;
+            } else if window_state.is_maximized {
+                let _ = maximize_window(label.clone(), app.clone(), registry_state.clone()).await;
+            }
+        }
+    }
+
+    if let Some(label) = restore_focus_target(layout.focused_label.as_deref(), &restored) {
+        let _ = focus_app_window(label, app.clone(), registry_state.clone()).await;
+    }
+
+    Ok(report)
+}
+
+// External URL allowlist
+type UrlAllowlistState = Mutex<Vec<String>>;
+
+fn url_allowlist_path() -> String {
+    format!("{}/window_url_allowlist.json", window_state_dir())
+}
+
+// A JSON array of hosts; without the file no external URL can be opened
+fn load_url_allowlist() -> Vec<String> {
+    std::fs::read_to_string(url_allowlist_path()).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+// "*.example.org" matches example.org's subdomains but not example.org itself
+fn host_allowed(host: &str, allowed_hosts: &[String]) -> bool {
+    allowed_hosts.iter().any(|allowed| match allowed.strip_prefix("*.") {
+        Some(domain) => host.len() > domain.len() + 1
+            && host.to_ascii_lowercase().ends_with(&format!(".{}", domain.to_ascii_lowercase())),
+        None => host.eq_ignore_ascii_case(allowed),
+    })
+}
+
+// App-relative paths load from the bundle; absolute http(s) URLs have to be on an allowlisted host
+fn resolve_window_url(url: &str, allowed_hosts: &[String]) -> Result<tauri::WebviewUrl, WindowError> {
+    let not_allowed = || WindowError::UrlNotAllowed { url: url.to_string() };
+    let Some(scheme) = url_scheme(url) else {
+        if escapes_app(url) {
+            return Err(not_allowed());
+        }
+        return Ok(tauri::WebviewUrl::App(url.into()));
+    };
+    if !ALLOWED_URL_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) {
+        return Err(not_allowed());
+    }
+
+    let parsed = url.parse::<tauri::Url>()
+        .map_err(|e| WindowError::InvalidUrl { url: url.to_string(), message: e.to_string() })?;
+    match parsed.host_str() {
+        Some(host) if host_allowed(host, allowed_hosts) => Ok(tauri::WebviewUrl::External(parsed)),
+        Some(_) => Err(not_allowed()),
+        None => Err(WindowError::InvalidUrl { url: url.to_string(), message: "missing host".to_string() }),
+    }
+}
+
+// Our own pages are served from tauri://localhost, http(s)://tauri.localhost (Windows)
+// or the localhost dev server
+fn is_app_url(url: &tauri::Url) -> bool {
+    url.scheme() == "tauri" || matches!(url.host_str(), Some("localhost" | "tauri.localhost"))
+}
+
+// Points an existing window at another page, with the same rules as config.url
+#[tauri::command]
+async fn navigate_window(
+    label: String,
+    url: String,
+    app: AppHandle,
+    registry_state: State<'_, WindowRegistryState>,
+    allowlist_state: State<'_, UrlAllowlistState>,
+) -> Result<(), WindowError> {
+    let window = app.get_webview_window(&label)
+        .ok_or_else(|| WindowError::NotFound { label: label.clone() })?;
+    let failed = |message: String| WindowError::Failed { label: label.clone(), message };
+
+    let target = match resolve_window_url(&url, &allowlist_state.lock().unwrap())? {
+        tauri::WebviewUrl::External(target) => target,
+        _ => {
+            // Relative paths resolve against the app's origin, even if the window is showing an external page
+            let base = std::iter::once(window.clone())
+                .chain(app.webview_windows().into_values())
+                .filter_map(|w| w.url().ok())
+                .find(is_app_url)
+                .ok_or_else(|| failed("no app page to resolve a relative URL against".to_string()))?;
+            base.join(&format!("/{}", url.trim_start_matches('/'))).map_err(|e| failed(e.to_string()))?
+        }
+    };
+    window.navigate(target).map_err(|e| failed(e.to_string()))?;
+
+    registry_state.lock().unwrap().set_window_url(&label, &url)?;
+    Ok(())
+}
+
+// Per-type window templates
+type WindowTemplatesState = Mutex<HashMap<String, WindowConfig>>;
+
+fn templates_path() -> String {
+    format!("{}/window_templates.json", window_state_dir())
+}
+
+// Used for types the templates file doesn't mention, and as the base each template is merged over
+fn builtin_window_template(window_type: &str) -> WindowConfig {
+    WindowConfig {
+        window_type: window_type.to_string(),
+        title: window_type.to_string(),
+        ..WindowConfig::default()
+    }
+}
+
+fn template_for(templates: &HashMap<String, WindowConfig>, window_type: &str) -> WindowConfig {
+    templates.get(window_type).cloned().unwrap_or_else(|| builtin_window_template(window_type))
+}
+
+// The file maps window type -> partial config, so templates only list what differs from the defaults
+fn parse_window_templates(json: &str) -> Result<HashMap<String, WindowConfig>, String> {
+    let partials: HashMap<String, PartialWindowConfig> =
+        serde_json::from_str(json).map_err(|e| format!("Invalid window templates: {}", e))?;
+
+    partials.into_iter().map(|(window_type, partial)| {
+        let template = partial.merge_over(&builtin_window_template(&window_type));
+        template.validate().map_err(|errors| {
+            let fields: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            format!("Invalid window template '{}': {}", window_type, fields.join("; "))
+        })?;
+        Ok((window_type, template))
+    }).collect()
+}
+
+// A missing file just means every type uses the built-in template
+fn load_window_templates() -> Result<HashMap<String, WindowConfig>, String> {
+    match std::fs::read_to_string(templates_path()) {
+        Ok(json) => parse_window_templates(&json),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(format!("Failed to read window templates: {}", e)),
+    }
+}
+
+#[tauri::command]
+async fn get_window_template(
+    window_type: String,
+    templates_state: State<'_, WindowTemplatesState>,
+) -> Result<WindowConfig, String> {
+    Ok(template_for(&templates_state.lock().unwrap(), &window_type))
+}
+
+// Re-reads the templates file; on a bad file the previous templates stay in place
+#[tauri::command]
+async fn reload_window_templates(
+    templates_state: State<'_, WindowTemplatesState>,
+) -> Result<Vec<String>, String> {
+    let templates = load_window_templates()?;
+    let mut window_types: Vec<String> = templates.keys().cloned().collect();
+    window_types.sort();
+    *templates_state.lock().unwrap() = templates;
+    Ok(window_types)
+}
+
+// Global keyboard shortcuts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ShortcutAction {
+    CycleForward,
+    CycleBackward,
+    SnapLeft,
+    SnapRight,
+    MinimizeAll,
+    RestoreAll,
+    FocusPrevious,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortcutBinding {
+    pub accelerator: String,
+    pub action: ShortcutAction,
+}
+
+type ShortcutBindingsState = Mutex<Vec<ShortcutBinding>>;
+
+fn shortcuts_path() -> String {
+    format!("{}/politicail_shortcuts.json", window_state_dir())
+}
+
+fn parse_shortcut(accelerator: &str) -> Result<Shortcut, String> {
+    accelerator.parse().map_err(|e| format!("Invalid shortcut '{}': {}", accelerator, e))
+}
+
+// Bindings are matched by parsed shortcut, so "ctrl+alt+left" and "Alt+Ctrl+Left" are the same key
+fn binding_for<'a>(bindings: &'a [ShortcutBinding], shortcut: &Shortcut) -> Option<&'a ShortcutBinding> {
+    bindings.iter().find(|b| parse_shortcut(&b.accelerator).is_ok_and(|s| s.id() == shortcut.id()))
+}
+
+fn load_shortcut_bindings() -> Vec<ShortcutBinding> {
+    std::fs::read_to_string(shortcuts_path()).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_shortcut_bindings(bindings: &[ShortcutBinding]) -> Result<(), String> {
+    let json_data = serde_json::to_string_pretty(bindings).map_err(|e| e.to_string())?;
+    std::fs::write(shortcuts_path(), json_data).map_err(|e| e.to_string())
+}
+
+fn run_shortcut_action(app: &AppHandle, action: ShortcutAction) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let registry_state = app.state::<WindowRegistryState>();
+        let focused = registry_state.lock().unwrap().get_focused_window().map(|w| w.label.clone());
+        let _ = match action {
+            ShortcutAction::CycleForward => cycle_windows(true, None, None, None, None, registry_state, app.clone()).await,
+            ShortcutAction::CycleBackward => cycle_windows(false, None, None, None, None, registry_state, app.clone()).await,
+            ShortcutAction::SnapLeft | ShortcutAction::SnapRight => {
+                let position = if action == ShortcutAction::SnapLeft { SnapPosition::Left } else { SnapPosition::Right };
+                match focused {
+                    Some(label) => snap_window(label, position, None, app.clone(), registry_state).await,
+                    None => Ok(()),
+                }
+            }
+            ShortcutAction::MinimizeAll => minimize_all_windows(app.clone(), registry_state).await.map(|_| ()),
+            ShortcutAction::RestoreAll => restore_all_windows(app.clone(), registry_state).await.map(|_| ()),
+            ShortcutAction::FocusPrevious => focus_previous_window(app.clone(), registry_state).await.map(|_| ()),
+        };
+    });
+}
+
+fn handle_global_shortcut(app: &AppHandle, shortcut: &Shortcut, state: ShortcutState) {
+    if state != ShortcutState::Pressed {
+        return;
+    }
+    let action = {
+        let shortcuts_state = app.state::<ShortcutBindingsState>();
+        let bindings = shortcuts_state.lock().unwrap();
+        binding_for(&bindings, shortcut).map(|b| b.action)
+    };
+    if let Some(action) = action {
+        run_shortcut_action(app, action);
+    }
+}
+
+// Re-registers saved bindings on startup; ones another app has grabbed since stay saved but inactive
+fn restore_window_shortcuts(app: &AppHandle) {
+    let bindings = load_shortcut_bindings();
+    for binding in &bindings {
+        if let Ok(shortcut) = parse_shortcut(&binding.accelerator) {
+            let _ = app.global_shortcut().register(shortcut);
+        }
+    }
+    *app.state::<ShortcutBindingsState>().lock().unwrap() = bindings;
+}
+
+#[tauri::command]
+async fn register_window_shortcut(
+    accelerator: String,
+    action: ShortcutAction,
+    app: AppHandle,
+    shortcuts_state: State<'_, ShortcutBindingsState>,
+) -> Result<(), String> {
+    let shortcut = parse_shortcut(&accelerator)?;
+
+    // Re-binding one of our own shortcuts just swaps the action
+    if !app.global_shortcut().is_registered(shortcut) {
+        app.global_shortcut().register(shortcut).map_err(|e| {
+            format!("Could not register '{}', it may already be in use by another application: {}", accelerator, e)
+        })?;
+    }
+
+    let mut bindings = shortcuts_state.lock().unwrap();
+    bindings.retain(|b| parse_shortcut(&b.accelerator).map_or(true, |s| s.id() != shortcut.id()));
+    bindings.push(ShortcutBinding { accelerator, action });
+    save_shortcut_bindings(&bindings)
+}
+
+#[tauri::command]
+async fn unregister_window_shortcut(
+    accelerator: String,
+    app: AppHandle,
+    shortcuts_state: State<'_, ShortcutBindingsState>,
+) -> Result<(), String> {
+    let shortcut = parse_shortcut(&accelerator)?;
+    if app.global_shortcut().is_registered(shortcut) {
+        app.global_shortcut().unregister(shortcut).map_err(|e| e.to_string())?;
+    }
+
+    let mut bindings = shortcuts_state.lock().unwrap();
+    bindings.retain(|b| parse_shortcut(&b.accelerator).map_or(true, |s| s.id() != shortcut.id()));
+    save_shortcut_bindings(&bindings)
+}
+
+fn main() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_log::Builder::new().level(log::LevelFilter::Info).build())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app, shortcut, event| handle_global_shortcut(app, shortcut, event.state))
+                .build(),
+        )
+        .manage(WindowRegistryState::new(WindowRegistry::new()))
+        .manage(ShortcutBindingsState::new(Vec::new()))
+        .manage(MonitorCacheState::new(None))
+        .manage(UrlAllowlistState::new(load_url_allowlist()))
+        .manage(WindowTemplatesState::new(load_window_templates().unwrap_or_default()))
+        .setup(|app| {
+            restore_window_shortcuts(app.handle());
+            // Filled here on the main thread so later lookups, often made with the registry
+            // locked, don't have to wait on it
+            let _ = enumerate_monitors(app.handle());
+            let report = reconcile_windows(app.handle(), &app.state::<WindowRegistryState>());
+            if !report.is_empty() {
+                log::info!("Reconciled windows at startup: added {:?}, removed {:?}", report.added, report.removed);
+            }
+            watch_displays(app.handle().clone());
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            create_window,
+            close_window,
+            resize_window,
+            create_app_window,
+            create_app_window_v2,
+            list_unmanaged_windows,
+            adopt_window,
+            get_window_template,
+            duplicate_window,
+            navigate_window,
+            reload_window_templates,
+            close_app_window,
+            close_windows_of_type,
+            close_other_windows,
+            set_close_confirmation,
+            confirm_close,
+            cancel_close,
+            set_window_type,
+            set_window_type_limit,
+            focus_app_window,
+            focus_previous_window,
+            minimize_window,
+            maximize_window,
+            unmaximize_window,
+            minimize_all_windows,
+            restore_minimized_windows,
+            restore_all_windows,
+            hide_window,
+            show_window,
+            resize_app_window,
+            set_window_size_constraints,
+            set_window_constraints,
+            set_window_resizable,
+            set_window_title,
+            set_window_theme,
+            set_window_zoom,
+            get_window_zoom,
+            get_window_scale_factor,
+            set_skip_taskbar,
+            set_click_through,
+            set_window_metadata,
+            get_window_metadata,
+            set_window_decorations,
+            set_window_opacity,
+            toggle_fullscreen,
+            request_user_attention,
+            move_window,
+            animate_window,
+            center_window,
+            move_window_to_monitor,
+            move_window_to_next_monitor,
+            get_monitor_for_window,
+            get_window_monitor_coverage,
+            get_window_list,
+            get_window_mru_list,
+            get_focused_window,
+            get_app_focus_state,
+            get_window_state,
+            get_window_at_point,
+            capture_window,
+            window_ready,
+            reveal_window,
+            send_to_window,
+            set_event_queue_ttl,
+            sync_registry,
+            get_registry_stats,
+            window_exists,
+            get_monitors,
+            get_monitor_info,
+            set_reclamp_on_display_change,
+            set_event_throttle_ms,
+            set_default_placement,
+            set_auto_apply_monitor_layouts,
+            save_layout_for_current_monitors,
+            apply_layout_for_current_monitors,
+            get_last_monitor_snapshot,
+            restore_displaced_windows,
+            get_cursor_position,
+            get_cursor_monitor,
+            get_all_window_states,
+            set_window_state,
+            validate_window_state,
+            cycle_windows,
+            end_window_cycle,
+            snap_window,
+            set_snap_gap,
+            set_magnetic_snap,
+            tile_windows,
+            cascade_windows,
+            bring_all_to_front,
+            assign_window_to_group,
+            get_group_windows,
+            focus_group,
+            raise_group,
+            raise_window,
+            lower_window,
+            place_window,
+            apply_window_layout,
+            apply_window_operations,
+            save_window_state,
+            load_window_state,
+            load_window_state_v2,
+            list_window_profiles,
+            delete_window_profile,
+            register_window_shortcut,
+            unregister_window_shortcut
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(window_type: &str) -> WindowConfig {
+        WindowConfig {
+            window_type: window_type.to_string(),
+            title: window_type.to_string(),
+            ..WindowConfig::default()
+        }
+    }
+
+    // What load_window_state would read back after save_window_state
+    fn round_trip(registry: &WindowRegistry) -> SavedLayout {
+        let layout = current_layout(registry, Vec::new());
+        parse_saved_layout(&serde_json::to_string(&layout).unwrap()).unwrap()
+    }
+
+    // Pins a window's timestamps so ordering tests don't depend on the wall clock
+    fn set_times(registry: &mut WindowRegistry, label: &str, created_at: u64, last_focused_at: u64) {
+        let window = registry.windows.get_mut(label).unwrap();
+        window.created_at = created_at;
+        window.last_focused_at = last_focused_at;
+    }
+
+    fn labels(windows: Vec<&WindowState>) -> Vec<String> {
+        windows.into_iter().map(|w| w.label.clone()).collect()
+    }
+
+    #[test]
+    fn profile_file_names_round_trip() {
+        assert_eq!(window_state_file_name(None), "politicail_windows.json");
+        assert_eq!(window_state_file_name(Some("campaign")), "politicail_windows_campaign.json");
+
+        assert_eq!(profile_from_file_name("politicail_windows_campaign.json"), Some("campaign".to_string()));
+        assert_eq!(profile_from_file_name("politicail_windows_governing-2.json"), Some("governing-2".to_string()));
+        assert_eq!(profile_from_file_name("politicail_windows.json"), None);
+        assert_eq!(profile_from_file_name("politicail_windows_.json"), None);
+        assert_eq!(profile_from_file_name("other_campaign.json"), None);
+        assert_eq!(profile_from_file_name(&monitor_layout_file_name("cfg-0123456789abcdef")), None);
+    }
+
+    #[test]
+    fn profile_names_cannot_escape_the_data_dir() {
+        assert!(validate_profile_name("campaign").is_ok());
+        assert!(validate_profile_name("").is_err());
+        assert!(validate_profile_name("../secrets").is_err());
+        assert!(validate_profile_name("a/b").is_err());
+        assert!(window_state_path(Some("..")).is_err());
+    }
+
+    #[test]
+    fn recent_use_orders_by_last_focus_not_z_order() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("a".to_string(), test_config("map"));
+        registry.add_window("b".to_string(), test_config("map"));
+        registry.add_window("c".to_string(), test_config("map"));
+        set_times(&mut registry, "a", 100, 400);
+        set_times(&mut registry, "b", 200, 300);
+        set_times(&mut registry, "c", 300, 350);
+
+        assert_eq!(labels(registry.get_windows_by_z_order()), vec!["c", "b", "a"]);
+        assert_eq!(labels(registry.get_windows_by_recent_use()), vec!["a", "c", "b"]);
+    }
+
+    #[test]
+    fn recent_use_breaks_ties_on_creation_time() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("first".to_string(), test_config("inbox"));
+        registry.add_window("second".to_string(), test_config("inbox"));
+        // Neither window was focused after creation, so last_focused_at == created_at
+        set_times(&mut registry, "first", 100, 100);
+        set_times(&mut registry, "second", 200, 200);
+
+        assert_eq!(labels(registry.get_windows_by_recent_use()), vec!["second", "first"]);
+
+        // Identical timestamps still produce a stable order
+        set_times(&mut registry, "first", 100, 100);
+        set_times(&mut registry, "second", 100, 100);
+        assert_eq!(labels(registry.get_windows_by_recent_use()), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn sync_removes_ghosts_and_adopts_unknown_windows() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map_100".to_string(), test_config("map"));
+        registry.add_window("inbox_200".to_string(), test_config("inbox"));
+
+        let live = vec!["map_100".to_string(), "main".to_string(), "news_300".to_string()];
+        let report = registry.sync_with_live_windows(&live);
+
+        assert_eq!(report.removed, vec!["inbox_200"]);
+        assert_eq!(report.added, vec!["main", "news_300"]);
+        assert!(registry.get_window("inbox_200").is_none());
+        assert_eq!(registry.get_window("news_300").unwrap().config.window_type, "news");
+        assert_eq!(registry.get_window("main").unwrap().config.window_type, "main");
+
+        // The removed window held focus, so it moves to a surviving tracked window
+        assert_eq!(registry.get_focused_window().unwrap().label, "map_100");
+
+        let report = registry.sync_with_live_windows(&live);
+        assert!(report.removed.is_empty() && report.added.is_empty());
+    }
+
+    #[test]
+    fn reconcile_tracks_live_windows_with_their_inferred_config() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map_100".to_string(), WindowConfig { title: "Map".to_string(), ..test_config("map") });
+        registry.add_window("inbox_200".to_string(), test_config("inbox"));
+
+        let main = WindowConfig { title: "PoliticAIl".to_string(), width: 1280.0, height: 720.0, ..WindowConfig::synthesized("main") };
+        let report = registry.reconcile(vec![
+            ("main".to_string(), main),
+            ("map_100".to_string(), WindowConfig { title: "Changed".to_string(), ..WindowConfig::synthesized("map_100") }),
+        ]);
+
+        assert_eq!((report.added, report.removed), (vec!["main".to_string()], vec!["inbox_200".to_string()]));
+        let main = registry.get_window("main").unwrap();
+        assert_eq!((main.config.window_type.as_str(), main.config.title.as_str()), ("main", "PoliticAIl"));
+        assert_eq!((main.config.width, main.config.height), (1280.0, 720.0));
+        // Logical config sizes aren't passed off as physical bounds
+        assert_eq!((main.current_width, main.current_height), (None, None));
+        // Windows already tracked keep the config they were created with
+        assert_eq!(registry.get_window("map_100").unwrap().config.title, "Map");
+    }
+
+    fn monitor(id: &str, x: i32, y: i32, width: u32, height: u32) -> MonitorInfo {
+        MonitorInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            width,
+            height,
+            x,
+            y,
+            scale_factor: 1.0,
+            is_primary: x == 0 && y == 0,
+            work_x: x,
+            work_y: y,
+            work_width: width,
+            work_height: height,
+            work_area_exact: true,
+        }
+    }
+
+    #[test]
+    fn clamp_leaves_visible_windows_alone() {
+        let monitors = vec![monitor("primary", 0, 0, 1920, 1080)];
+        assert_eq!(clamp_to_visible(100.0, 100.0, 800.0, 600.0, &monitors), (100, 100));
+        // Hanging off the right edge is fine while enough of the title bar remains
+        assert_eq!(clamp_to_visible(1800.0, 100.0, 800.0, 600.0, &monitors), (1800, 100));
+    }
+
+    #[test]
+    fn clamp_pulls_off_screen_window_onto_nearest_monitor() {
+        let monitors = vec![monitor("primary", 0, 0, 1920, 1080)];
+        // Saved on a since-disconnected monitor to the right
+        assert_eq!(clamp_to_visible(2500.0, 200.0, 800.0, 600.0, &monitors), (1120, 200));
+        // Title bar above the top edge
+        assert_eq!(clamp_to_visible(100.0, -50.0, 800.0, 600.0, &monitors), (100, 0));
+    }
+
+    #[test]
+    fn clamp_picks_the_closest_of_several_monitors() {
+        let monitors = vec![
+            monitor("left", -1280, 0, 1280, 1024),
+            monitor("primary", 0, 0, 1920, 1080),
+        ];
+        assert_eq!(clamp_to_visible(-2000.0, 100.0, 400.0, 300.0, &monitors), (-1280, 100));
+        assert_eq!(clamp_to_visible(-600.0, 100.0, 400.0, 300.0, &monitors), (-600, 100));
+    }
+
+    #[test]
+    fn geometry_updates_track_live_position_and_size() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), WindowConfig {
+            x: Some(10.0),
+            y: Some(20.0),
+            ..test_config("map")
+        });
+
+        // The logical config isn't passed off as physical bounds
+        let window = registry.get_window("map").unwrap();
+        assert_eq!((window.current_x, window.current_y), (None, None));
+        assert_eq!((window.current_width, window.current_height), (None, None));
+
+        registry.update_window_position("map", 300, 400);
+        registry.update_window_size("map", 1024, 768);
+
+        let window = registry.get_window("map").unwrap();
+        assert_eq!((window.current_x, window.current_y), (Some(300), Some(400)));
+        assert_eq!((window.current_width, window.current_height), (Some(1024), Some(768)));
+        // The creation-time config is left untouched
+        assert_eq!((window.config.x, window.config.width), (Some(10.0), 800.0));
+    }
+
+    #[test]
+    fn throttle_only_accepts_updates_after_the_interval() {
+        let last = AtomicU64::new(0);
+        assert!(throttle_elapsed(&last, 1_000, 100));
+        assert!(!throttle_elapsed(&last, 1_050, 100));
+        assert!(!throttle_elapsed(&last, 1_099, 100));
+        assert!(throttle_elapsed(&last, 1_100, 100));
+    }
+
+    #[test]
+    fn coalescer_applies_the_first_event_and_flushes_the_last() {
+        let coalescer = EventCoalescer::default();
+        assert_eq!(coalescer.offer((10, 10), 1_000, 100), Coalesced::Apply((10, 10)));
+
+        // The rest of the burst is parked behind one flush, newest value winning
+        assert_eq!(coalescer.offer((20, 20), 1_030, 100), Coalesced::FlushAfter(70));
+        assert_eq!(coalescer.offer((30, 30), 1_060, 100), Coalesced::Parked);
+        assert_eq!(coalescer.offer((40, 40), 1_110, 100), Coalesced::Parked);
+        assert_eq!(coalescer.take_pending(1_100), Some((40, 40)));
+        assert_eq!(coalescer.take_pending(1_100), None);
+
+        // The flush counts as a write
+        assert_eq!(coalescer.offer((50, 50), 1_150, 100), Coalesced::FlushAfter(50));
+        coalescer.bypass(1_160);
+        assert_eq!(coalescer.take_pending(1_200), None);
+
+        // No throttling at all with a zero interval
+        assert_eq!(coalescer.offer((60, 60), 1_160, 0), Coalesced::Apply((60, 60)));
+    }
+
+    #[test]
+    fn centering_uses_the_monitor_under_the_window_center() {
+        let monitors = vec![
+            monitor("primary", 0, 0, 1920, 1080),
+            monitor("right", 1920, 0, 2560, 1440),
+        ];
+
+        let target = monitor_for_point(2500.0, 300.0, &monitors).unwrap();
+        assert_eq!(target.id, "right");
+        assert_eq!(centered_position(800.0, 600.0, target), (1920 + 880, 420));
+
+        // A point in the dead zone below the shorter monitor picks the nearest one
+        assert_eq!(monitor_for_point(1000.0, 1200.0, &monitors).unwrap().id, "primary");
+    }
+
+    #[test]
+    fn closing_a_dialog_returns_focus_to_its_parent() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("parent".to_string(), test_config("editor"));
+        registry.add_window("news".to_string(), test_config("news"));
+        registry.focus_window("parent");
+        registry.add_window("dialog".to_string(), test_config("dialog"));
+
+        // Make "news" look the most recently used by timestamp; history must win anyway
+        set_times(&mut registry, "news", 0, u64::MAX);
+
+        registry.remove_window("dialog");
+        assert_eq!(registry.get_focused_window().unwrap().label, "parent");
+        assert!(registry.get_window("parent").unwrap().is_focused);
+    }
+
+    #[test]
+    fn previous_focus_skips_closed_and_minimized_windows() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("a".to_string(), test_config("map"));
+        registry.add_window("b".to_string(), test_config("map"));
+        registry.add_window("c".to_string(), test_config("map"));
+        registry.add_window("d".to_string(), test_config("map"));
+
+        registry.update_window_state("c", Some(true), None, None);
+        registry.windows.remove("b");
+
+        assert_eq!(registry.previous_focus_candidate(), Some("a".to_string()));
+        assert!(!registry.focus_history.contains(&"b".to_string()));
+
+        // Toggling back and forth behaves like a stack of recent focus
+        registry.focus_window("a");
+        assert_eq!(registry.previous_focus_candidate(), Some("d".to_string()));
+    }
+
+    #[test]
+    fn focus_history_is_bounded() {
+        let mut registry = WindowRegistry::new();
+        for i in 0..(FOCUS_HISTORY_LIMIT + 10) {
+            registry.add_window(format!("w{}", i), test_config("map"));
+        }
+        assert_eq!(registry.focus_history.len(), FOCUS_HISTORY_LIMIT);
+        assert_eq!(registry.focus_history.back().unwrap(), &format!("w{}", FOCUS_HISTORY_LIMIT + 9));
+    }
+
+    #[test]
+    fn hiding_and_showing_tracks_visibility_and_focus() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("shell".to_string(), test_config("shell"));
+        registry.add_window("dashboard".to_string(), test_config("dashboard"));
+
+        registry.set_window_visible("dashboard", false);
+        assert!(!registry.get_window("dashboard").unwrap().is_visible);
+        assert_eq!(registry.get_focused_window().unwrap().label, "shell");
+        assert_eq!(labels(registry.get_visible_windows_by_z_order()), vec!["shell"]);
+        assert_eq!(registry.get_windows_by_z_order().len(), 2);
+
+        let z_before = registry.get_window("shell").unwrap().z_order;
+        registry.set_window_visible("dashboard", true);
+        let dashboard = registry.get_window("dashboard").unwrap();
+        assert!(dashboard.is_visible && dashboard.is_focused);
+        assert!(dashboard.z_order > z_before);
+    }
+
+    #[test]
+    fn translating_between_monitors_keeps_the_relative_offset() {
+        let primary = monitor("primary", 0, 0, 1920, 1080);
+        let left = monitor("left", -1280, 0, 1280, 1024);
+
+        assert_eq!(translate_to_monitor(100.0, 50.0, 800.0, 600.0, &primary, &left), (-1180, 50));
+        // Too far right for the smaller monitor, so it gets pulled back inside
+        assert_eq!(translate_to_monitor(1000.0, 400.0, 800.0, 600.0, &primary, &left), (-800, 400));
+        // Larger than the target monitor: pinned to its origin
+        assert_eq!(translate_to_monitor(0.0, 0.0, 1600.0, 1080.0, &primary, &left), (-1280, 0));
+    }
+
+    #[test]
+    fn monitor_assignment_follows_the_largest_overlap() {
+        let monitors = vec![
+            monitor("primary", 0, 0, 1920, 1080),
+            monitor("right", 1920, 0, 1920, 1080),
+        ];
+
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+
+        // Mostly on the right-hand monitor even though its origin is on the primary
+        registry.update_window_position("map", 1700, 100);
+        registry.update_window_size("map", 800, 600);
+        registry.refresh_monitor_assignment("map", &monitors);
+        assert_eq!(registry.get_window("map").unwrap().monitor_id.as_deref(), Some("right"));
+
+        registry.update_window_position("map", 1300, 100);
+        registry.refresh_monitor_assignment("map", &monitors);
+        assert_eq!(registry.get_window("map").unwrap().monitor_id.as_deref(), Some("primary"));
+
+        // Entirely off-screen: nearest monitor wins
+        assert_eq!(monitor_for_rect(5000.0, 0.0, 100.0, 100.0, &monitors).unwrap().id, "right");
+    }
+
+    #[test]
+    fn grid_layout_for_one_window_fills_the_monitor() {
+        let primary = monitor("primary", 0, 0, 1920, 1080);
+        assert_eq!(grid_layout(1, &primary, 0), vec![(0, 0, 1920, 1080)]);
+        assert_eq!(grid_layout(1, &primary, 10), vec![(10, 10, 1900, 1060)]);
+        assert!(grid_layout(0, &primary, 10).is_empty());
+    }
+
+    #[test]
+    fn grid_layout_for_two_windows_splits_side_by_side() {
+        let primary = monitor("primary", 0, 0, 1920, 1080);
+        assert_eq!(grid_layout(2, &primary, 0), vec![(0, 0, 960, 1080), (960, 0, 960, 1080)]);
+    }
+
+    #[test]
+    fn grid_layout_for_three_windows_uses_two_rows() {
+        let primary = monitor("primary", 0, 0, 1920, 1080);
+        assert_eq!(grid_layout(3, &primary, 10), vec![
+            (10, 10, 945, 525),
+            (965, 10, 945, 525),
+            (10, 545, 945, 525),
+        ]);
+    }
+
+    #[test]
+    fn grid_layout_for_five_windows_on_an_offset_monitor() {
+        let right = monitor("right", 1920, 0, 1920, 1080);
+        assert_eq!(grid_layout(5, &right, 0), vec![
+            (1920, 0, 640, 540),
+            (2560, 0, 640, 540),
+            (3200, 0, 640, 540),
+            (1920, 540, 640, 540),
+            (2560, 540, 640, 540),
+        ]);
+    }
+
+    #[test]
+    fn window_ops_deserialize_from_tagged_json() {
+        let ops: Vec<WindowLayoutOp> = serde_json::from_str(r#"[
+            {"op": "Create", "window_type": "polling", "config": {"width": 640}, "label": "polls"},
+            {"op": "Create", "window_type": "news"},
+            {"op": "Move", "label": "map", "x": 10, "y": 20},
+            {"op": "Resize", "label": "map", "width": 800, "height": 600},
+            {"op": "Focus", "label": "inbox"},
+            {"op": "Minimize", "label": "news"},
+            {"op": "Snap", "label": "map", "position": "Left"}
+        ]"#).unwrap();
+
+        let labels: Vec<Option<&str>> = ops.iter().map(|op| op.label()).collect();
+        assert_eq!(labels, vec![Some("polls"), None, Some("map"), Some("map"), Some("inbox"), Some("news"), Some("map")]);
+        assert!(matches!(ops[6], WindowLayoutOp::Snap { position: SnapPosition::Left, .. }));
+        match &ops[0] {
+            WindowLayoutOp::Create { window_type, config, .. } => {
+                assert_eq!((window_type.as_str(), config.width, config.height), ("polling", Patch::Set(640.0), Patch::Inherit));
+            }
+            other => panic!("expected a Create op, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn window_errors_serialize_with_a_kind_tag() {
+        let error = WindowError::NotFound { label: "map".to_string() };
+        assert_eq!(serde_json::to_value(&error).unwrap(), serde_json::json!({"kind": "NotFound", "label": "map"}));
+        assert_eq!(String::from(error), "Window 'map' not found");
+    }
+
+    #[test]
+    fn cascade_layout_steps_diagonally_and_wraps() {
+        let primary = monitor("primary", 0, 0, 1000, 800);
+        // 700x500 leaves room for 300/100 px of horizontal steps, 300/100 vertical: 4 slots per cycle
+        assert_eq!(cascade_layout(6, &primary, 700, 500, 100), vec![
+            (0, 0), (100, 100), (200, 200), (300, 300),
+            (0, 0), (100, 100),
+        ]);
+
+        let left = monitor("left", -1280, 0, 1280, 1024);
+        assert_eq!(cascade_layout(2, &left, 768, 614, 30), vec![(-1280, 0), (-1250, 30)]);
+
+        // A window as large as the monitor never steps at all
+        assert_eq!(cascade_layout(2, &primary, 1000, 800, 30), vec![(0, 0), (0, 0)]);
+    }
+
+    #[test]
+    fn thirds_tile_the_monitor_without_gaps() {
+        for width in [1920, 2561, 3440] {
+            let wide = monitor("wide", 100, 0, width, 1440);
+            let rect = |p: SnapPosition| compute_snap_rect(&p, &wide, 0).unwrap();
+
+            let (lx, _, lw, lh) = rect(SnapPosition::LeftThird);
+            let (cx, _, cw, _) = rect(SnapPosition::CenterThird);
+            let (rx, _, rw, _) = rect(SnapPosition::RightThird);
+            assert_eq!(lx, 100);
+            assert_eq!(lx + lw as i32, cx);
+            assert_eq!(cx + cw as i32, rx);
+            assert_eq!(rx + rw as i32, 100 + width as i32);
+            assert_eq!(lh, 1440);
+
+            // Two-thirds snaps line up with the single-third boundaries
+            assert_eq!(rect(SnapPosition::LeftTwoThirds), (lx, 0, lw + cw, 1440));
+            assert_eq!(rect(SnapPosition::RightTwoThirds), (cx, 0, cw + rw, 1440));
+        }
+    }
+
+    #[test]
+    fn every_snap_position_lands_on_the_window_monitor() {
+        use SnapPosition::*;
+        let positions = [Left, Right, Top, Bottom, TopLeft, TopRight, BottomLeft, BottomRight, Center];
+
+        // A 1080p display left of the primary and lower down, so both coordinates are offset
+        let side_by_side = vec![monitor("primary", 0, 0, 1920, 1080), monitor("left", -1920, 180, 1920, 1080)];
+        let expected = [
+            (-1920, 180, 960, 1080), (-960, 180, 960, 1080), (-1920, 180, 1920, 540), (-1920, 720, 1920, 540),
+            (-1920, 180, 960, 540), (-960, 180, 960, 540), (-1920, 720, 960, 540), (-960, 720, 960, 540),
+            (-1632, 342, 1344, 756),
+        ];
+        // Mostly, but not entirely, on the left display
+        let window = (-1000, 400, 1200, 600);
+        for (position, rect) in positions.iter().zip(expected) {
+            assert_eq!(snap_rect_for_window(position, window, &side_by_side, false, 0), Ok(Some(rect)), "{:?}", position);
+        }
+        let window = (100, 100, 800, 600);
+        assert_eq!(snap_rect_for_window(&Center, window, &side_by_side, false, 0), Ok(Some((288, 162, 1344, 756))));
+
+        // A 4K primary with a taskbar and an ultrawide to its right
+        let mut uhd = monitor("4k", 0, 0, 3840, 2160);
+        uhd.work_height = 2100;
+        let ultrawide = monitor("ultrawide", 3840, 0, 3440, 1440);
+        let mixed = vec![uhd, ultrawide];
+        let expected = [
+            (0, 0, 1920, 2100), (1920, 0, 1920, 2100), (0, 0, 3840, 1050), (0, 1050, 3840, 1050),
+            (0, 0, 1920, 1050), (1920, 0, 1920, 1050), (0, 1050, 1920, 1050), (1920, 1050, 1920, 1050),
+            (576, 315, 2688, 1470),
+        ];
+        for (position, rect) in positions.iter().zip(expected) {
+            assert_eq!(snap_rect_for_window(position, (500, 500, 800, 600), &mixed, false, 0), Ok(Some(rect)), "{:?}", position);
+        }
+        let expected = [
+            (3840, 0, 1720, 1440), (5560, 0, 1720, 1440), (3840, 0, 3440, 720), (3840, 720, 3440, 720),
+            (3840, 0, 1720, 720), (5560, 0, 1720, 720), (3840, 720, 1720, 720), (5560, 720, 1720, 720),
+            (4356, 216, 2408, 1008),
+        ];
+        for (position, rect) in positions.iter().zip(expected) {
+            assert_eq!(snap_rect_for_window(position, (4000, 200, 800, 600), &mixed, false, 0), Ok(Some(rect)), "{:?}", position);
+        }
+
+        // The full monitor on request, maximize left to the OS, and nothing to snap to without monitors
+        assert_eq!(snap_rect_for_window(&Bottom, (500, 500, 800, 600), &mixed, true, 0), Ok(Some((0, 1080, 3840, 1080))));
+        assert_eq!(snap_rect_for_window(&Maximize, (500, 500, 800, 600), &mixed, false, 0), Ok(None));
+        assert!(snap_rect_for_window(&Left, (500, 500, 800, 600), &[], false, 0).is_err());
+    }
+
+    #[test]
+    fn halves_cover_odd_widths_exactly() {
+        let odd = monitor("odd", 0, 0, 1366, 769);
+        let (_, _, left_width, _) = compute_snap_rect(&SnapPosition::Left, &odd, 0).unwrap();
+        let (right_x, _, right_width, _) = compute_snap_rect(&SnapPosition::Right, &odd, 0).unwrap();
+        assert_eq!(right_x, left_width as i32);
+        assert_eq!(left_width + right_width, 1366);
+        assert!(compute_snap_rect(&SnapPosition::Maximize, &odd, 0).is_none());
+    }
+
+    #[test]
+    fn labels_of_type_only_match_that_type() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("shell".to_string(), test_config("shell"));
+        registry.add_window("campaign_1".to_string(), test_config("campaign"));
+        registry.add_window("campaign_2".to_string(), test_config("campaign"));
+
+        assert_eq!(registry.get_labels_of_type("campaign"), vec!["campaign_2", "campaign_1"]);
+        assert!(registry.get_labels_of_type("inbox").is_empty());
+    }
+
+    #[test]
+    fn snap_gap_separates_neighbours_and_screen_edges() {
+        let primary = monitor("primary", 0, 0, 1920, 1080);
+
+        assert_eq!(compute_snap_rect(&SnapPosition::Left, &primary, 10), Some((10, 10, 945, 1060)));
+        assert_eq!(compute_snap_rect(&SnapPosition::Right, &primary, 10), Some((965, 10, 945, 1060)));
+        assert_eq!(compute_snap_rect(&SnapPosition::BottomRight, &primary, 10), Some((965, 545, 945, 525)));
+        // The gap is relative to the monitor, so offset monitors get it too
+        let right = monitor("right", 1920, 0, 1920, 1080);
+        assert_eq!(compute_snap_rect(&SnapPosition::Left, &right, 10), Some((1930, 10, 945, 1060)));
+    }
+
+    #[test]
+    fn saved_layouts_load_from_both_file_formats() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        let windows: Vec<WindowState> = registry.get_windows_by_z_order().into_iter().cloned().collect();
+
+        let legacy = serde_json::to_string(&windows).unwrap();
+        let layout = parse_saved_layout(&legacy).unwrap();
+        assert_eq!(layout.windows.len(), 1);
+        assert_eq!(layout.snap_gap, 0);
+
+        let current = serde_json::to_string(&SavedLayout { windows, snap_gap: 12, focused_label: None, monitors: Vec::new() }).unwrap();
+        let layout = parse_saved_layout(&current).unwrap();
+        assert_eq!(layout.windows[0].label, "map");
+        assert_eq!(layout.snap_gap, 12);
+
+        assert!(parse_saved_layout("{\"not\": \"a layout\"}").is_err());
+    }
+
+    #[test]
+    fn show_desktop_restores_only_what_it_minimized() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("shell".to_string(), test_config("shell"));
+        registry.add_window("inbox".to_string(), test_config("inbox"));
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.add_window("news".to_string(), test_config("news"));
+        registry.update_window_state("inbox", Some(true), None, None);
+        registry.focus_window("map");
+
+        let mut minimized = registry.begin_show_desktop();
+        minimized.sort();
+        assert_eq!(minimized, vec!["map", "news", "shell"]);
+        assert!(registry.get_window("map").unwrap().is_minimized);
+
+        // Pressing it again while everything is down keeps the original snapshot
+        assert!(registry.begin_show_desktop().is_empty());
+
+        registry.remove_window("news");
+        let mut snapshot = registry.end_show_desktop();
+        snapshot.labels.sort();
+        assert_eq!(snapshot.labels, vec!["map", "shell"]);
+        assert_eq!(snapshot.focused.as_deref(), Some("map"));
+        assert!(!registry.get_window("shell").unwrap().is_minimized);
+        assert!(registry.get_window("inbox").unwrap().is_minimized);
+
+        // Nothing left to restore
+        assert!(registry.end_show_desktop().labels.is_empty());
+    }
+
+    #[test]
+    fn magnetic_snap_pulls_onto_nearby_edges() {
+        let monitor_rect = (0, 0, 1920, 1080);
+        let neighbor = (100, 100, 400, 300);
+
+        // Left edge 8px right of the neighbour's right edge: docks against it
+        assert_eq!(magnetic_snap((508, 150, 200, 200), &[neighbor], 12), (500, 150));
+        // Near the monitor's top-left corner
+        assert_eq!(magnetic_snap((10, 5, 200, 200), &[monitor_rect], 12), (0, 0));
+        // Top edges within reach line up as well
+        assert_eq!(magnetic_snap((505, 110, 200, 200), &[neighbor], 12), (500, 100));
+    }
+
+    #[test]
+    fn magnetic_snap_ignores_distant_or_unreachable_edges() {
+        let neighbor = (100, 100, 400, 300);
+        // Too far away horizontally
+        assert_eq!(magnetic_snap((530, 150, 200, 200), &[neighbor], 12), (530, 150));
+        // Horizontally close, but far below the neighbour so their edges can't touch
+        assert_eq!(magnetic_snap((505, 900, 200, 100), &[neighbor], 12), (505, 900));
+        assert_eq!(magnetic_snap((505, 900, 200, 100), &[], 12), (505, 900));
+    }
+
+    #[test]
+    fn registry_only_snaps_when_enabled() {
+        let monitors = vec![monitor("primary", 0, 0, 1920, 1080)];
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.update_window_position("map", 6, 300);
+        registry.update_window_size("map", 800, 600);
+
+        assert_eq!(registry.magnetic_snap_target("map", &monitors), None);
+        registry.magnetic_snap_enabled = true;
+        assert_eq!(registry.magnetic_snap_target("map", &monitors), Some((0, 300)));
+
+        registry.update_window_position("map", 0, 300);
+        assert_eq!(registry.magnetic_snap_target("map", &monitors), None);
+    }
+
+    #[test]
+    fn raise_order_goes_bottom_to_top_and_skips_minimized() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("bottom".to_string(), test_config("map"));
+        registry.add_window("middle".to_string(), test_config("map"));
+        registry.add_window("top".to_string(), test_config("map"));
+        registry.add_window("hidden".to_string(), test_config("map"));
+        registry.set_window_visible("hidden", false);
+        registry.update_window_state("middle", Some(true), None, None);
+
+        let windows = registry.get_windows_by_z_order();
+        assert_eq!(raise_order(&windows, false), vec!["bottom", "top"]);
+        assert_eq!(raise_order(&windows, true), vec!["bottom", "middle", "top"]);
+    }
+
+    #[test]
+    fn stats_summarize_the_registry() {
+        let mut registry = WindowRegistry::new();
+        assert_eq!(registry.stats(1_000).total_windows, 0);
+        assert_eq!(registry.stats(1_000).oldest_window_age_ms, None);
+
+        registry.add_window("map_1".to_string(), test_config("map"));
+        registry.add_window("map_2".to_string(), test_config("map"));
+        registry.add_window("inbox".to_string(), test_config("inbox"));
+        set_times(&mut registry, "map_1", 400, 400);
+        set_times(&mut registry, "map_2", 700, 700);
+        set_times(&mut registry, "inbox", 900, 900);
+
+        let stats = registry.stats(1_000);
+        assert_eq!(stats.total_windows, 3);
+        assert_eq!(stats.windows_by_type.get("map"), Some(&2));
+        assert_eq!(stats.windows_by_type.get("inbox"), Some(&1));
+        assert_eq!(stats.focused_window.as_deref(), Some("inbox"));
+        assert_eq!(stats.highest_z_order, 3);
+        assert_eq!(stats.z_order_counter, 3);
+        assert_eq!(stats.oldest_window_age_ms, Some(600));
+
+        // The counter keeps its place when the topmost window closes
+        registry.remove_window("inbox");
+        let stats = registry.stats(1_000);
+        assert_eq!((stats.highest_z_order, stats.z_order_counter), (2, 3));
+    }
+
+    #[test]
+    fn z_order_cycle_walks_a_frozen_stack() {
+        let mut registry = WindowRegistry::new();
+        for label in ["a", "b", "c"] {
+            registry.add_window(label.to_string(), test_config("map"));
+        }
+
+        // Focusing each step raises it, but the cycle keeps the original stacking
+        let first = registry.cycle_step(CycleMode::ZOrder, true, false, None, 1_000).unwrap();
+        assert_eq!(first, "b");
+        registry.focus_window_at(&first, 1_000);
+        let second = registry.cycle_step(CycleMode::ZOrder, true, false, None, 1_100).unwrap();
+        assert_eq!(second, "a");
+        registry.focus_window_at(&second, 1_100);
+        assert_eq!(registry.cycle_step(CycleMode::ZOrder, true, false, None, 1_200).as_deref(), Some("c"));
+        assert_eq!(registry.cycle_step(CycleMode::ZOrder, false, false, None, 1_300).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn mru_cycle_defers_last_focused_until_the_cycle_ends() {
+        let mut registry = WindowRegistry::new();
+        for label in ["a", "b", "c"] {
+            registry.add_window(label.to_string(), test_config("map"));
+        }
+        set_times(&mut registry, "a", 1, 300);
+        set_times(&mut registry, "b", 2, 200);
+        set_times(&mut registry, "c", 3, 100);
+        registry.focus_window_at("a", 300);
+
+        let first = registry.cycle_step(CycleMode::MostRecentlyUsed, true, false, None, 1_000).unwrap();
+        assert_eq!(first, "b");
+        registry.focus_window_at(&first, 1_000);
+        assert_eq!(registry.get_window("b").unwrap().last_focused_at, 200);
+
+        let second = registry.cycle_step(CycleMode::MostRecentlyUsed, true, false, None, 1_100).unwrap();
+        assert_eq!(second, "c");
+        registry.focus_window_at(&second, 1_100);
+        assert_eq!(labels(registry.get_windows_by_recent_use()), vec!["a", "b", "c"]);
+
+        registry.end_cycle(1_200);
+        assert_eq!(registry.get_window("c").unwrap().last_focused_at, 1_200);
+        assert_eq!(labels(registry.get_windows_by_recent_use()), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn abandoned_cycle_expires() {
+        let mut registry = WindowRegistry::new();
+        for label in ["a", "b"] {
+            registry.add_window(label.to_string(), test_config("map"));
+        }
+        set_times(&mut registry, "a", 1, 200);
+        set_times(&mut registry, "b", 2, 100);
+
+        let target = registry.cycle_step(CycleMode::MostRecentlyUsed, true, false, None, 1_000).unwrap();
+        assert_eq!(target, "a");
+        registry.focus_window_at(&target, 1_000);
+        assert_eq!(registry.get_window("a").unwrap().last_focused_at, 200);
+
+        // The stale cycle is committed before the new focus counts as normal use
+        let later = 1_000 + CYCLE_SESSION_TIMEOUT_MS + 1;
+        registry.focus_window_at("b", later);
+        assert_eq!(registry.get_window("a").unwrap().last_focused_at, later);
+        assert_eq!(registry.get_window("b").unwrap().last_focused_at, later);
+    }
+
+    #[test]
+    fn set_window_type_respects_type_limits() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("speech_1".to_string(), test_config("draft_speech"));
+        registry.add_window("speech_2".to_string(), test_config("draft_speech"));
+        registry.add_window("published".to_string(), test_config("published_speech"));
+        registry.set_type_limit("published_speech", Some(1));
+
+        assert_eq!(
+            registry.set_window_type("speech_1", "published_speech"),
+            Err(WindowError::TypeLimitReached {
+                window_type: "published_speech".to_string(),
+                limit: 1,
+                count: 1,
+                existing: vec!["published".to_string()],
+            })
+        );
+        assert_eq!(registry.get_window("speech_1").unwrap().config.window_type, "draft_speech");
+
+        // Re-typing to the type a window already has doesn't count it against the limit
+        assert_eq!(registry.set_window_type("published", "published_speech"), Ok(()));
+
+        registry.set_type_limit("published_speech", None);
+        assert_eq!(registry.set_window_type("speech_1", "published_speech"), Ok(()));
+        assert_eq!(registry.get_labels_of_type("draft_speech"), vec!["speech_2"]);
+        assert_eq!(
+            registry.set_window_type("missing", "map"),
+            Err(WindowError::NotFound { label: "missing".to_string() })
+        );
+    }
+
+    #[test]
+    fn cycle_can_be_scoped_to_one_window_type() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("dashboard_1".to_string(), test_config("dashboard"));
+        registry.add_window("map_1".to_string(), test_config("map"));
+        registry.add_window("dashboard_2".to_string(), test_config("dashboard"));
+        registry.add_window("news_1".to_string(), test_config("news"));
+
+        // Starting from a window of another type lands on the first match
+        let first = registry.cycle_step(CycleMode::ZOrder, true, false, Some("dashboard"), 1_000).unwrap();
+        assert_eq!(first, "dashboard_2");
+        registry.focus_window_at(&first, 1_000);
+        assert_eq!(registry.cycle_step(CycleMode::ZOrder, true, false, Some("dashboard"), 1_100).as_deref(), Some("dashboard_1"));
+        assert_eq!(registry.cycle_step(CycleMode::ZOrder, true, false, Some("dashboard"), 1_200).as_deref(), Some("dashboard_2"));
+        assert_eq!(registry.cycle_step(CycleMode::ZOrder, true, false, Some("inbox"), 1_300), None);
+    }
+
+    #[test]
+    fn raise_lower_and_place_rewrite_z_order() {
+        let mut registry = WindowRegistry::new();
+        for label in ["main", "toast", "modal"] {
+            registry.add_window(label.to_string(), test_config("map"));
+        }
+
+        registry.lower_window("modal").unwrap();
+        assert_eq!(labels(registry.get_windows_by_z_order()), vec!["toast", "main", "modal"]);
+
+        registry.raise_window("modal").unwrap();
+        registry.place_window("toast", ZPlacement::Above, "main").unwrap();
+        assert_eq!(labels(registry.get_windows_by_z_order()), vec!["modal", "toast", "main"]);
+
+        registry.place_window("main", ZPlacement::Below, "toast").unwrap();
+        assert_eq!(labels(registry.get_windows_by_z_order()), vec!["modal", "toast", "main"]);
+        registry.place_window("main", ZPlacement::Above, "modal").unwrap();
+        assert_eq!(labels(registry.get_windows_by_z_order()), vec!["main", "modal", "toast"]);
+
+        assert_eq!(
+            registry.place_window("toast", ZPlacement::Above, "missing"),
+            Err(WindowError::NotFound { label: "missing".to_string() })
+        );
+    }
+
+    #[test]
+    fn z_order_counter_is_renormalized_before_overflow() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("a".to_string(), test_config("map"));
+        registry.add_window("b".to_string(), test_config("map"));
+        registry.windows.get_mut("a").unwrap().z_order = u32::MAX - 2;
+        registry.windows.get_mut("b").unwrap().z_order = u32::MAX - 1;
+        registry.z_order_counter = u32::MAX - 1;
+
+        registry.focus_window("a");
+        assert_eq!(registry.get_window("b").unwrap().z_order, 2);
+        assert_eq!(registry.get_window("a").unwrap().z_order, 3);
+        assert_eq!(labels(registry.get_windows_by_z_order()), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn shortcut_bindings_match_regardless_of_spelling() {
+        let bindings = vec![ShortcutBinding {
+            accelerator: "ctrl+alt+ArrowLeft".to_string(),
+            action: ShortcutAction::SnapLeft,
+        }];
+        let shortcut = parse_shortcut("Alt+Control+ArrowLeft").unwrap();
+        assert_eq!(binding_for(&bindings, &shortcut).map(|b| b.action), Some(ShortcutAction::SnapLeft));
+        assert!(binding_for(&bindings, &parse_shortcut("ctrl+ArrowLeft").unwrap()).is_none());
+        assert!(parse_shortcut("ctrl+nonsense").is_err());
+    }
+
+    #[test]
+    fn close_confirmation_holds_the_window_until_answered() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("speech".to_string(), test_config("draft_speech"));
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.set_close_confirmation("speech", true).unwrap();
+
+        assert_eq!(registry.request_close("map", 1_000), CloseDecision::Proceed);
+        assert_eq!(registry.request_close("speech", 1_000), CloseDecision::Ask { requested_at: 1_000 });
+        assert_eq!(registry.request_close("speech", 2_000), CloseDecision::AlreadyAsked);
+        assert!(registry.get_window("speech").is_some());
+
+        // Declining disarms the timeout for that request
+        assert!(registry.resolve_close("speech"));
+        assert!(!registry.expire_close("speech", 1_000));
+
+        assert_eq!(registry.request_close("speech", 3_000), CloseDecision::Ask { requested_at: 3_000 });
+        assert!(!registry.expire_close("speech", 1_000));
+        assert!(registry.expire_close("speech", 3_000));
+
+        registry.remove_window("speech");
+        assert!(!registry.requires_close_confirmation("speech"));
+        assert_eq!(
+            registry.set_close_confirmation("speech", true),
+            Err(WindowError::NotFound { label: "speech".to_string() })
+        );
+    }
+
+    #[test]
+    fn remove_window_reports_only_the_first_removal() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("news".to_string(), test_config("news"));
+        assert!(registry.remove_window("news"));
+        assert!(!registry.remove_window("news"));
+    }
+
+    #[test]
+    fn events_are_queued_until_the_window_is_ready() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("briefing".to_string(), test_config("briefing"));
+        registry.event_queue_ttl_ms = 1_000;
+
+        registry.queue_event("briefing", "stale", serde_json::json!(0), 100);
+        registry.queue_event("briefing", "init", serde_json::json!({ "topic": "budget" }), 1_000);
+        registry.queue_event("briefing", "update", serde_json::json!(2), 1_050);
+        assert!(!registry.is_ready("briefing"));
+
+        let delivered = registry.mark_ready("briefing", 1_200);
+        let events: Vec<&str> = delivered.iter().map(|e| e.event.as_str()).collect();
+        assert_eq!(events, vec!["init", "update"]);
+        assert!(registry.is_ready("briefing"));
+        assert!(registry.mark_ready("briefing", 1_300).is_empty());
+
+        // A window that never reports ready loses its queue after the TTL
+        registry.queue_event("crashed", "init", serde_json::json!(null), 2_000);
+        registry.queue_event("briefing_2", "init", serde_json::json!(null), 3_500);
+        assert!(!registry.pending_events.contains_key("crashed"));
+    }
+
+    #[test]
+    fn app_focus_follows_blur_and_refocus() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.add_window("inbox".to_string(), test_config("inbox"));
+
+        // Switching between our own windows never reports the app as unfocused
+        registry.blur_window("inbox");
+        registry.focus_window("map");
+        assert_eq!(registry.take_app_focus_change(), None);
+
+        registry.blur_window("map");
+        assert!(!registry.get_window("map").unwrap().is_focused);
+        assert_eq!(
+            registry.take_app_focus_change(),
+            Some(AppFocusState { app_focused: false, focused_window: None })
+        );
+        assert_eq!(registry.take_app_focus_change(), None);
+
+        registry.focus_window("map");
+        assert_eq!(
+            registry.take_app_focus_change(),
+            Some(AppFocusState { app_focused: true, focused_window: Some("map".to_string()) })
+        );
+    }
+
+    #[test]
+    fn resize_events_keep_state_flags_and_normal_size_in_sync() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        let normal = NativeWindowFlags::default();
+        let maximized = NativeWindowFlags { maximized: true, ..normal };
+        let minimized = NativeWindowFlags { minimized: true, ..normal };
+
+        registry.observe_resize("map", 1024, 768, normal);
+        registry.observe_resize("map", 1920, 1040, maximized);
+        let window = registry.get_window("map").unwrap();
+        assert!(window.is_maximized);
+        assert_eq!((window.current_width, window.current_height), (Some(1024), Some(768)));
+
+        // Windows reports a minimized window as 0x0
+        registry.observe_resize("map", 0, 0, minimized);
+        let window = registry.get_window("map").unwrap();
+        assert!(window.is_minimized && !window.is_maximized);
+        assert_eq!((window.current_width, window.current_height), (Some(1024), Some(768)));
+
+        registry.observe_resize("map", 1100, 800, normal);
+        let window = registry.get_window("map").unwrap();
+        assert!(!window.is_minimized && !window.is_maximized);
+        assert_eq!((window.current_width, window.current_height), (Some(1100), Some(800)));
+    }
+
+    #[test]
+    fn legacy_create_window_arguments_map_onto_a_default_config() {
+        let config = legacy_window_config("Briefing".to_string(), 640.0, 480.0);
+        assert_eq!(config.window_type, LEGACY_WINDOW_TYPE);
+        assert_eq!(config.title, "Briefing");
+        assert_eq!((config.width, config.height), (640.0, 480.0));
+        assert!(config.resizable && config.closable && config.decorations);
+        assert_eq!((config.x, config.y, config.url), (None, None, None));
+
+        let response = serde_json::to_value(LegacyResponse::new("legacy_1".to_string(), "create_app_window")).unwrap();
+        assert_eq!(response["deprecated"], true);
+        assert_eq!(response["replacement"], "create_app_window");
+        assert_eq!(response["value"], "legacy_1");
+    }
+
+    #[test]
+    fn monitor_index_picks_the_monitor_with_the_larger_share() {
+        let monitors = vec![monitor("left", 0, 0, 1920, 1080), monitor("right", 1920, 0, 2560, 1440)];
+
+        assert_eq!(monitor_index_for_rect(100.0, 100.0, 800.0, 600.0, &monitors), (0, Some("left".to_string())));
+        // Straddles the boundary with 600px on the right monitor and 200px on the left
+        assert_eq!(monitor_index_for_rect(1720.0, 100.0, 800.0, 600.0, &monitors), (1, Some("right".to_string())));
+        // Fully off-screen windows report the nearest monitor
+        assert_eq!(monitor_index_for_rect(5000.0, 100.0, 800.0, 600.0, &monitors), (1, Some("right".to_string())));
+        assert_eq!(monitor_index_for_rect(0.0, 0.0, 800.0, 600.0, &[]), (0, None));
+    }
+
+    #[test]
+    fn animation_interpolates_with_easing() {
+        assert_eq!(ease_in_out_cubic(0.0), 0.0);
+        assert_eq!(ease_in_out_cubic(0.5), 0.5);
+        assert_eq!(ease_in_out_cubic(1.0), 1.0);
+        assert_eq!(ease_in_out_cubic(1.5), 1.0);
+        assert!(ease_in_out_cubic(0.25) < 0.25);
+
+        let from = (0, 0, 400, 300);
+        let to = (200, -100, 800, 500);
+        assert_eq!(interpolate_rect(from, to, 0.0), from);
+        assert_eq!(interpolate_rect(from, to, 0.5), (100, -50, 600, 400));
+        assert_eq!(interpolate_rect(from, to, 1.0), to);
+    }
+
+    #[test]
+    fn starting_an_animation_cancels_the_previous_one() {
+        let mut registry = WindowRegistry::new();
+        let first = registry.start_animation("map");
+        let second = registry.start_animation("map");
+        let other = registry.start_animation("inbox");
+        assert!(!registry.is_animation_current("map", first));
+        assert!(registry.is_animation_current("map", second));
+        assert!(registry.is_animation_current("inbox", other));
+
+        // A cancelled animation finishing late doesn't clear its replacement
+        registry.finish_animation("map", first);
+        assert!(registry.is_animation_current("map", second));
+        registry.finish_animation("map", second);
+        assert!(!registry.is_animation_current("map", second));
+    }
+
+    #[test]
+    fn size_limits_validate_clamp_and_persist() {
+        let config = WindowConfig {
+            min_width: Some(400.0),
+            min_height: Some(300.0),
+            max_width: Some(1200.0),
+            ..test_config("dashboard")
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.clamp_size(100.0, 100.0), (400.0, 300.0));
+        assert_eq!(config.clamp_size(5000.0, 5000.0), (1200.0, 5000.0));
+        assert_eq!(config.clamp_size(800.0, 600.0), (800.0, 600.0));
+
+        let inverted = WindowConfig { min_height: Some(900.0), max_height: Some(600.0), ..test_config("dashboard") };
+        let errors = inverted.validate().unwrap_err();
+        assert_eq!(errors.iter().map(|e| e.field.as_str()).collect::<Vec<_>>(), vec!["min_height"]);
+
+        let mut registry = WindowRegistry::new();
+        registry.add_window("dashboard".to_string(), config);
+        let restored = round_trip(&registry);
+        let restored_config = &restored.windows[0].config;
+        assert_eq!(
+            (restored_config.min_width, restored_config.min_height, restored_config.max_width, restored_config.max_height),
+            (Some(400.0), Some(300.0), Some(1200.0), None)
+        );
+    }
+
+    #[test]
+    fn size_constraints_are_stored_on_the_window_config() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("dashboard".to_string(), test_config("dashboard"));
+
+        registry.set_size_limits("dashboard", Some((640, 480)), None).unwrap();
+        let config = &registry.get_window("dashboard").unwrap().config;
+        assert_eq!(config.clamp_size(100.0, 900.0), (640.0, 900.0));
+
+        // A rejected update leaves the previous limits in place
+        assert!(matches!(
+            registry.set_size_limits("dashboard", Some((800, 600)), Some((400, 300))),
+            Err(WindowError::InvalidConfig { .. })
+        ));
+        assert_eq!(registry.get_window("dashboard").unwrap().config.min_width, Some(640.0));
+
+        registry.set_size_limits("dashboard", None, None).unwrap();
+        assert_eq!(registry.get_window("dashboard").unwrap().config.min_width, None);
+        assert!(matches!(registry.set_size_limits("missing", None, None), Err(WindowError::NotFound { .. })));
+    }
+
+    #[test]
+    fn placement_centers_on_the_requested_monitor() {
+        let mut right = monitor("right", 1920, 0, 2560, 1440);
+        right.scale_factor = 2.0;
+        let monitors = vec![monitor("left", 0, 0, 1920, 1080), right];
+
+        assert_eq!(resolve_placement(&WindowPlacement::Absolute, 800.0, 600.0, Some("right"), None, &monitors), None);
+        assert_eq!(
+            resolve_placement(&WindowPlacement::CenterPrimary, 800.0, 600.0, Some("right"), None, &monitors),
+            Some((560.0, 240.0))
+        );
+        // 800x600 logical is 1600x1200 physical on the 2x monitor
+        assert_eq!(
+            resolve_placement(&WindowPlacement::CenterOnFocusedMonitor, 800.0, 600.0, Some("right"), None, &monitors),
+            Some((1200.0, 60.0))
+        );
+        assert_eq!(
+            resolve_placement(&WindowPlacement::CenterOnMonitor("gone".to_string()), 800.0, 600.0, None, None, &monitors),
+            Some((560.0, 240.0))
+        );
+        assert_eq!(resolve_placement(&WindowPlacement::CenterPrimary, 800.0, 600.0, None, None, &[]), None);
+        assert_eq!(
+            resolve_placement(&WindowPlacement::CenterOnCursorMonitor, 800.0, 600.0, Some("left"), Some("right"), &monitors),
+            Some((1200.0, 60.0))
+        );
+        // No cursor reading means the primary monitor
+        assert_eq!(
+            resolve_placement(&WindowPlacement::CenterOnCursorMonitor, 800.0, 600.0, Some("right"), None, &monitors),
+            Some((560.0, 240.0))
+        );
+    }
+
+    #[test]
+    fn cursor_position_picks_the_nearest_monitor_in_a_dead_zone() {
+        let mut right = monitor("right", 1920, 0, 2560, 1440);
+        right.scale_factor = 2.0;
+        let monitors = vec![monitor("left", 0, 0, 1920, 1080), right];
+
+        let cursor = cursor_position_on(2920.0, 400.0, &monitors).unwrap();
+        assert_eq!(cursor.monitor.id, "right");
+        assert_eq!((cursor.physical_x, cursor.physical_y), (2920.0, 400.0));
+        assert_eq!((cursor.x, cursor.y), (2420.0, 200.0));
+
+        // Below the shorter left monitor, but closer to the right one
+        let cursor = cursor_position_on(1900.0, 1300.0, &monitors).unwrap();
+        assert_eq!(cursor.monitor.id, "right");
+        let cursor = cursor_position_on(1000.0, 1100.0, &monitors).unwrap();
+        assert_eq!(cursor.monitor.id, "left");
+
+        assert_eq!(cursor_position_on(0.0, 0.0, &[]), None);
+    }
+
+    #[test]
+    fn set_window_title_updates_the_config() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("inbox".to_string(), test_config("inbox"));
+        registry.set_window_title("inbox", "Inbox (3 unread)").unwrap();
+        assert_eq!(registry.get_window("inbox").unwrap().config.title, "Inbox (3 unread)");
+        assert_eq!(
+            registry.set_window_title("closed", "Inbox"),
+            Err(WindowError::NotFound { label: "closed".to_string() })
+        );
+    }
+
+    // What open_app_window does around a successful build
+    fn next_cascade_spawn(registry: &mut WindowRegistry, window_type: &str, width: u32, height: u32, monitor: &MonitorInfo) -> (i32, i32) {
+        let spawn = registry.cascade_spawn_for(window_type, width, height, monitor);
+        registry.record_cascade_spawn(window_type, spawn.clone());
+        (spawn.x, spawn.y)
+    }
+
+    #[test]
+    fn cascade_spawns_step_wrap_and_reset() {
+        let left = monitor("left", 0, 0, 1920, 1080);
+        let right = monitor("right", 1920, 0, 1920, 1080);
+        let mut registry = WindowRegistry::new();
+
+        assert_eq!(next_cascade_spawn(&mut registry, "email", 800, 600, &left), (32, 32));
+        assert_eq!(next_cascade_spawn(&mut registry, "email", 800, 600, &left), (64, 64));
+        // Other types keep their own chain
+        assert_eq!(next_cascade_spawn(&mut registry, "news", 800, 600, &left), (32, 32));
+
+        // 1080 - 600 leaves room for the corner at y=480 but not y=512
+        for _ in 0..12 {
+            next_cascade_spawn(&mut registry, "email", 800, 600, &left);
+        }
+        assert_eq!(next_cascade_spawn(&mut registry, "email", 800, 600, &left), (480, 480));
+        assert_eq!(next_cascade_spawn(&mut registry, "email", 800, 600, &left), (32, 32));
+
+        // Spawning on another monitor starts a chain there
+        assert_eq!(next_cascade_spawn(&mut registry, "email", 800, 600, &right), (1952, 32));
+        assert_eq!(next_cascade_spawn(&mut registry, "email", 800, 600, &right), (1984, 64));
+
+        // Closing the last window of the type resets its chain
+        registry.add_window("email_1".to_string(), test_config("email"));
+        registry.add_window("email_2".to_string(), test_config("email"));
+        registry.remove_window("email_1");
+        assert_eq!(next_cascade_spawn(&mut registry, "email", 800, 600, &right), (2016, 96));
+        registry.remove_window("email_2");
+        assert_eq!(next_cascade_spawn(&mut registry, "email", 800, 600, &right), (1952, 32));
+    }
+
+    #[test]
+    fn fullscreen_remembers_the_bounds_to_restore() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+
+        registry.enter_fullscreen("map", (100, 50, 1024, 768));
+        assert!(registry.get_window("map").unwrap().is_fullscreen);
+        assert_eq!(registry.exit_fullscreen("map"), Some((100, 50, 1024, 768)));
+        assert!(!registry.get_window("map").unwrap().is_fullscreen);
+        // Fullscreen entered some other way has nothing to restore
+        assert_eq!(registry.exit_fullscreen("map"), None);
+        assert_eq!(registry.exit_fullscreen("missing"), None);
+    }
+
+    #[test]
+    fn fullscreen_round_trips_keep_a_decorated_window_the_same_size() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+
+        // Saved and restored as the inner size, so a frame around it never accumulates
+        let position = tauri::PhysicalPosition { x: 100, y: 50 };
+        let mut inner = tauri::PhysicalSize { width: 1024, height: 768 };
+        for _ in 0..3 {
+            registry.enter_fullscreen("map", rect_of(position, inner));
+            let (_, _, width, height) = registry.exit_fullscreen("map").unwrap();
+            // apply_rect sets the inner size
+            inner = tauri::PhysicalSize { width, height };
+        }
+        assert_eq!((inner.width, inner.height), (1024, 768));
+    }
+
+    #[test]
+    fn clamp_rect_shrinks_windows_larger_than_every_monitor() {
+        let monitors = vec![monitor("laptop", 0, 0, 1920, 1080)];
+        // Saved on a 4K display that's no longer attached
+        assert_eq!(clamp_rect_to_visible(2500.0, 300.0, 3000.0, 1600.0, &monitors), (0, 0, 1920, 1080));
+        assert_eq!(clamp_rect_to_visible(100.0, 100.0, 800.0, 600.0, &monitors), (100, 100, 800, 600));
+
+        // Fits on the larger of two monitors, so only the position changes
+        let monitors = vec![monitor("laptop", 0, 0, 1920, 1080), monitor("external", 1920, 0, 2560, 1440)];
+        assert_eq!(clamp_rect_to_visible(5000.0, 100.0, 2400.0, 1200.0, &monitors), (2080, 100, 2400, 1200));
+        assert_eq!(clamp_rect_to_visible(10.0, 10.0, 800.0, 600.0, &[]), (10, 10, 800, 600));
+    }
+
+    #[test]
+    fn attention_flag_clears_on_focus() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("diplomacy".to_string(), test_config("diplomacy"));
+        registry.add_window("map".to_string(), test_config("map"));
+
+        registry.set_needs_attention("diplomacy", true).unwrap();
+        registry.focus_window("map");
+        assert!(registry.get_window("diplomacy").unwrap().needs_attention);
+
+        registry.focus_window("diplomacy");
+        assert!(!registry.get_window("diplomacy").unwrap().needs_attention);
+        assert!(registry.set_needs_attention("missing", true).is_err());
+    }
+
+    #[test]
+    fn config_validation_names_offending_fields() {
+        type Case = (&'static str, fn(&mut WindowConfig), Option<&'static str>);
+        let cases: Vec<Case> = vec![
+            ("defaults", |_| {}, None),
+            ("zero width", |c| c.width = 0.0, Some("width")),
+            ("negative height", |c| c.height = -10.0, Some("height")),
+            ("NaN width", |c| c.width = f64::NAN, Some("width")),
+            ("oversized height", |c| c.height = 100_000.0, Some("height")),
+            ("NaN x", |c| c.x = Some(f64::NAN), Some("x")),
+            ("infinite y", |c| c.y = Some(f64::INFINITY), Some("y")),
+            ("negative position", |c| c.x = Some(-1920.0), None),
+            ("negative min width", |c| c.min_width = Some(-1.0), Some("min_width")),
+            ("inverted height limits", |c| {
+                c.min_height = Some(900.0);
+                c.max_height = Some(600.0);
+            }, Some("min_height")),
+            ("app-relative url", |c| c.url = Some("windows/map.html".to_string()), None),
+            ("https url", |c| c.url = Some("https://example.org/poll".to_string()), None),
+            ("file url", |c| c.url = Some("file:///etc/passwd".to_string()), Some("url")),
+            ("javascript url", |c| c.url = Some("javascript:alert(1)".to_string()), Some("url")),
+            ("protocol-relative url", |c| c.url = Some("//example.org".to_string()), Some("url")),
+            ("traversal url", |c| c.url = Some("../../secrets.html".to_string()), Some("url")),
+            ("empty url", |c| c.url = Some(" ".to_string()), Some("url")),
+            ("transparent with decorations", |c| c.transparent = true, Some("transparent")),
+            ("transparent without decorations", |c| {
+                c.transparent = true;
+                c.decorations = false;
+            }, None),
+        ];
+
+        for (name, mutate, expected) in cases {
+            let mut config = test_config("news");
+            mutate(&mut config);
+            let fields: Vec<String> = config.validate().err().unwrap_or_default()
+                .into_iter().map(|e| e.field).collect();
+            assert_eq!(fields, expected.into_iter().map(String::from).collect::<Vec<_>>(), "{}", name);
+        }
+
+        let config = WindowConfig { width: 0.0, x: Some(f64::NAN), ..test_config("news") };
+        let error = WindowError::InvalidConfig { errors: config.validate().unwrap_err() };
+        assert_eq!(error.to_string(), "Invalid window config: width: must be a positive number; x: must be a finite number");
+    }
+
+    #[test]
+    fn window_templates_merge_partial_configs() {
+        let templates = parse_window_templates(r#"{
+            "polling": { "width": 1024, "height": 720, "resizable": false },
+            "news": { "url": "news.html", "placement": "Cascade" }
+        }"#).unwrap();
+
+        let polling = template_for(&templates, "polling");
+        assert_eq!((polling.width, polling.height, polling.resizable), (1024.0, 720.0, false));
+        assert_eq!(polling.title, "polling");
+        assert!(polling.closable);
+
+        // Fields the caller passes win over the template; the rest come from it
+        let config = PartialWindowConfig {
+            title: Patch::Set("Latest polls".to_string()),
+            height: Patch::Set(500.0),
+            ..PartialWindowConfig::default()
+        }.merge_over(&polling);
+        assert_eq!((config.title.as_str(), config.width, config.height), ("Latest polls", 1024.0, 500.0));
+        assert!(!config.resizable);
+
+        let news = template_for(&templates, "news");
+        assert_eq!((news.url.as_deref(), news.placement), (Some("news.html"), WindowPlacement::Cascade));
+
+        let unknown = template_for(&templates, "email");
+        assert_eq!((unknown.window_type.as_str(), unknown.width), ("email", 800.0));
+
+        assert!(parse_window_templates(r#"{ "map": { "width": -5 } }"#).unwrap_err().contains("'map'"));
+        assert!(parse_window_templates("not json").is_err());
+    }
+
+    #[test]
+    fn type_limit_blocks_creation_until_cleared() {
+        let mut registry = WindowRegistry::new();
+        registry.set_type_limit("dashboard", Some(2));
+        assert!(registry.check_type_limit("dashboard", None).is_ok());
+
+        registry.add_window("dashboard_2".to_string(), test_config("dashboard"));
+        registry.add_window("dashboard_1".to_string(), test_config("dashboard"));
+        set_times(&mut registry, "dashboard_1", 100, 100);
+        set_times(&mut registry, "dashboard_2", 200, 200);
+
+        match registry.check_type_limit("dashboard", None) {
+            Err(WindowError::TypeLimitReached { limit, count, .. }) => assert_eq!((limit, count), (2, 2)),
+            other => panic!("expected the limit to be hit, got {:?}", other),
+        }
+        assert!(registry.check_type_limit("news", None).is_ok());
+        assert_eq!(registry.oldest_of_type("dashboard").as_deref(), Some("dashboard_1"));
+
+        registry.remove_window("dashboard_2");
+        assert!(registry.check_type_limit("dashboard", None).is_ok());
+
+        registry.add_window("dashboard_3".to_string(), test_config("dashboard"));
+        assert!(registry.check_type_limit("dashboard", None).is_err());
+        registry.set_type_limit("dashboard", None);
+        assert!(registry.check_type_limit("dashboard", None).is_ok());
+    }
+
+    #[test]
+    fn unique_label_skips_taken_timestamps() {
+        let mut registry = WindowRegistry::new();
+        assert_eq!(registry.unique_label("map", 1000), "map_1000");
+
+        registry.add_window("map_1000".to_string(), test_config("map"));
+        registry.add_window("map_1001".to_string(), test_config("map"));
+        assert_eq!(registry.unique_label("map", 1000), "map_1002");
+        assert_eq!(registry.unique_label("news", 1000), "news_1000");
+    }
+
+    #[test]
+    fn check_label_rejects_bad_charset_and_duplicates() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("news/breaking".to_string(), test_config("news"));
+
+        assert_eq!(registry.check_label("deeplink:poll-7_a"), Ok(()));
+        assert_eq!(
+            registry.check_label("news/breaking"),
+            Err(WindowError::LabelTaken { label: "news/breaking".to_string() })
+        );
+        for label in ["", "has space", "émoji", "dot.ted"] {
+            assert_eq!(registry.check_label(label), Err(WindowError::InvalidLabel { label: label.to_string() }));
+        }
+    }
+
+    #[test]
+    fn window_theme_is_validated_and_stored_on_the_config() {
+        assert_eq!(parse_theme("dark"), Ok(Some(tauri::Theme::Dark)));
+        assert_eq!(parse_theme("system"), Ok(None));
+        assert!(parse_theme("sepia").is_err());
+
+        let sepia = WindowConfig { theme: Some("sepia".to_string()), ..test_config("news") };
+        assert_eq!(sepia.validate().unwrap_err()[0].field, "theme");
+
+        let mut registry = WindowRegistry::new();
+        registry.add_window("late_night_news".to_string(), test_config("news"));
+        registry.set_window_theme("late_night_news", "dark").unwrap();
+        assert_eq!(registry.get_window("late_night_news").unwrap().config.theme.as_deref(), Some("dark"));
+        assert!(registry.set_window_theme("missing", "dark").is_err());
+    }
+
+    #[test]
+    fn offscreen_windows_lists_windows_left_on_a_removed_display() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.add_window("news".to_string(), test_config("news"));
+        registry.add_window("parked".to_string(), WindowConfig { allow_offscreen: true, ..test_config("parked") });
+        registry.add_window("unplaced".to_string(), test_config("unplaced"));
+        for (label, x) in [("map", 100), ("news", 2100), ("parked", 2100)] {
+            registry.update_window_position(label, x, 100);
+            registry.update_window_size(label, 800, 600);
+        }
+
+        let docked = vec![monitor("primary", 0, 0, 1920, 1080), monitor("external", 1920, 0, 1920, 1080)];
+        assert!(registry.offscreen_windows(&docked).is_empty());
+
+        let undocked = vec![monitor("primary", 0, 0, 1920, 1080)];
+        let moves = registry.offscreen_windows(&undocked);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].0, "news");
+        let (x, y, width, height) = moves[0].1;
+        assert!(x + width as i32 <= 1920 && (x, y) != (2100, 100));
+        assert_eq!((width, height), (800, 600));
+    }
+
+    #[test]
+    fn utility_windows_are_tracked_but_left_out_of_switching() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.add_window("news".to_string(), test_config("news"));
+        let toast = WindowConfig { skip_taskbar: true, switcher_visible: false, ..test_config("toast") };
+        registry.add_window("toast".to_string(), toast);
+        set_times(&mut registry, "map", 1, 100);
+        set_times(&mut registry, "news", 2, 200);
+        set_times(&mut registry, "toast", 3, 300);
+
+        assert_eq!(labels(registry.get_switcher_windows()), vec!["news", "map"]);
+        assert_eq!(registry.cycle_step(CycleMode::MostRecentlyUsed, true, false, None, 1000).as_deref(), Some("news"));
+        assert_eq!(registry.cycle_step(CycleMode::MostRecentlyUsed, true, false, None, 1010).as_deref(), Some("map"));
+        assert_eq!(registry.cycle_step(CycleMode::MostRecentlyUsed, true, false, None, 1020).as_deref(), Some("news"));
+
+        // Still managed like any other window
+        registry.focus_window("toast");
+        assert_eq!(registry.get_focused_window().map(|w| w.label.as_str()), Some("toast"));
+        registry.set_skip_taskbar("toast", false).unwrap();
+        assert!(!registry.get_window("toast").unwrap().config.skip_taskbar);
+    }
+
+    #[test]
+    fn toggled_decorations_survive_a_layout_round_trip() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("decision".to_string(), test_config("decision"));
+        registry.set_decorations("decision", false).unwrap();
+
+        let restored = round_trip(&registry);
+        assert!(!restored.windows[0].config.decorations);
+
+        let overlay = WindowConfig { transparent: true, decorations: false, ..test_config("overlay") };
+        registry.add_window("overlay".to_string(), overlay);
+        assert!(registry.set_decorations("overlay", true).is_err());
+        assert!(!registry.get_window("overlay").unwrap().config.decorations);
+    }
+
+    #[test]
+    fn hidden_windows_wait_for_a_reveal() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.add_window("news".to_string(), WindowConfig { visible: false, ..test_config("news") });
+        let polling = WindowConfig { create_and_reveal_when_ready: true, ..test_config("polling") };
+        registry.add_window("polling".to_string(), polling);
+
+        // Hidden windows don't take focus until they're shown
+        assert_eq!(registry.get_focused_window().map(|w| w.label.as_str()), Some("map"));
+        assert!(!registry.get_window("news").unwrap().is_visible);
+        assert!(registry.is_reveal_pending("news") && registry.is_reveal_pending("polling"));
+        assert!(!registry.is_reveal_pending("map"));
+
+        // Only the opted-in window is revealed by the readiness handshake
+        assert!(!registry.reveals_when_ready("news"));
+        assert!(registry.reveals_when_ready("polling"));
+
+        registry.set_window_visible("news", true);
+        assert!(!registry.is_reveal_pending("news"));
+        assert_eq!(registry.get_focused_window().map(|w| w.label.as_str()), Some("news"));
+
+        // Hiding it on purpose means the watchdog leaves it alone
+        registry.set_window_visible("polling", false);
+        assert!(!registry.is_reveal_pending("polling"));
+    }
+
+    #[test]
+    fn window_at_point_picks_the_topmost_shown_window() {
+        let mut registry = WindowRegistry::new();
+        for label in ["map", "news", "polling", "email"] {
+            registry.add_window(label.to_string(), test_config(label));
+        }
+        registry.update_window_state("polling", Some(true), None, None);
+        registry.set_window_visible("email", false);
+        registry.raise_window("news").unwrap();
+
+        let bounds: HashMap<String, (i32, i32, u32, u32)> = [
+            ("map", (0, 0, 800, 600)),
+            ("news", (400, 300, 800, 600)),
+            ("polling", (0, 0, 1920, 1080)),
+            ("email", (0, 0, 1920, 1080)),
+        ].into_iter().map(|(label, rect)| (label.to_string(), rect)).collect();
+
+        assert_eq!(registry.window_at_point(500, 400, &bounds).as_deref(), Some("news"));
+        assert_eq!(registry.window_at_point(100, 100, &bounds).as_deref(), Some("map"));
+        assert_eq!(registry.window_at_point(1200, 300, &bounds), None);
+        assert_eq!(registry.window_at_point(1500, 1000, &bounds), None);
+
+        registry.raise_window("map").unwrap();
+        assert_eq!(registry.window_at_point(500, 400, &bounds).as_deref(), Some("map"));
+    }
+
+    #[test]
+    fn modal_blocks_its_parent_until_either_closes() {
+        let modal_config = || WindowConfig {
+            modal: true,
+            parent_label: Some("cabinet".to_string()),
+            ..test_config("confirm")
+        };
+        assert!(WindowConfig { parent_label: None, ..modal_config() }.validate().is_err());
+
+        let mut registry = WindowRegistry::new();
+        registry.add_window("cabinet".to_string(), test_config("cabinet"));
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.add_window("confirm".to_string(), modal_config());
+        registry.block_parent("cabinet");
+
+        assert_eq!(registry.blocking_modal("cabinet").as_deref(), Some("confirm"));
+        assert!(registry.take_released_parents().is_empty());
+        // Cycling skips the blocked parent
+        for step in 0..3 {
+            let next = registry.cycle_step(CycleMode::ZOrder, true, false, None, 1000 + step);
+            assert_ne!(next.as_deref(), Some("cabinet"));
+        }
+        registry.end_cycle(2000);
+
+        // Closing the modal releases the parent
+        registry.remove_window("confirm");
+        assert_eq!(registry.take_released_parents(), vec!["cabinet".to_string()]);
+        assert!(registry.take_released_parents().is_empty());
+
+        // Closing the parent first leaves the modal as an ordinary window
+        registry.add_window("confirm".to_string(), modal_config());
+        registry.block_parent("cabinet");
+        registry.remove_window("cabinet");
+        assert!(registry.take_released_parents().is_empty());
+        assert_eq!(registry.get_window("confirm").unwrap().config.parent_label, None);
+        assert_eq!(registry.blocking_modal("cabinet"), None);
+        registry.remove_window("confirm");
+        assert!(registry.take_released_parents().is_empty());
+    }
+
+    #[test]
+    fn window_urls_are_checked_against_the_host_allowlist() {
+        let allowed = vec!["analytics.politicail.app".to_string(), "*.polls.example".to_string()];
+
+        assert_eq!(
+            resolve_window_url("windows/map.html", &allowed),
+            Ok(tauri::WebviewUrl::App("windows/map.html".into()))
+        );
+        for url in ["https://analytics.politicail.app/dashboard", "http://ANALYTICS.politicail.app", "https://eu.polls.example/live"] {
+            assert!(matches!(resolve_window_url(url, &allowed), Ok(tauri::WebviewUrl::External(_))), "{}", url);
+        }
+        for url in ["https://evil.example/", "https://polls.example/", "https://analytics.politicail.app.evil.example/", "file:///etc/passwd", "../outside.html"] {
+            assert_eq!(resolve_window_url(url, &allowed), Err(WindowError::UrlNotAllowed { url: url.to_string() }), "{}", url);
+        }
+        for url in ["https://", "http://exa mple.com/"] {
+            assert!(matches!(resolve_window_url(url, &allowed), Err(WindowError::InvalidUrl { .. })), "{}", url);
+        }
+        assert!(resolve_window_url("https://analytics.politicail.app/", &[]).is_err());
+    }
+
+    #[test]
+    fn plan_window_state_warns_about_each_correction() {
+        let monitors = vec![monitor("primary", 0, 0, 1920, 1080)];
+        let config = WindowConfig { min_width: Some(400.0), min_height: Some(300.0), ..test_config("map") };
+        let saved = |x: f64, width: f64, monitor: u32| WindowPositionSize {
+            x,
+            y: 100.0,
+            width,
+            height: 600.0,
+            is_maximized: false,
+            is_minimized: false,
+            is_fullscreen: false,
+            monitor,
+            monitor_name: None,
+            monitor_id: None,
+        };
+
+        let plan = plan_window_state(&saved(100.0, 800.0, 0), &config, &monitors, false).unwrap();
+        assert_eq!(plan, WindowStatePlan { rect: (100, 100, 800, 600), warnings: vec![] });
+
+        let plan = plan_window_state(&saved(2500.0, 200.0, 1), &config, &monitors, false).unwrap();
+        assert_eq!(plan.warnings.len(), 3, "{:?}", plan.warnings);
+        assert!(plan.warnings[0].contains("Monitor #1"));
+        assert!(plan.warnings[1].contains("below the window's minimum"));
+        assert!(plan.warnings[2].contains("off-screen"));
+        assert_eq!((plan.rect.2, plan.rect.3), (400, 600));
+        assert!(plan.rect.0 < 1920);
+
+        // Deliberate off-screen placement only leaves the missing-monitor warning
+        let plan = plan_window_state(&saved(2500.0, 800.0, 1), &config, &monitors, true).unwrap();
+        assert_eq!((plan.rect, plan.warnings.len()), ((2500, 100, 800, 600), 1));
+
+        assert!(plan_window_state(&saved(f64::NAN, 800.0, 0), &config, &monitors, false).is_err());
+    }
+
+    #[test]
+    fn window_zoom_is_clamped_and_saved_with_the_layout() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("legislation".to_string(), WindowConfig { zoom: Some(5.0), ..test_config("legislation") });
+        registry.add_window("map".to_string(), test_config("map"));
+        assert_eq!(registry.get_window("legislation").unwrap().zoom, MAX_ZOOM);
+        assert_eq!(registry.get_window("map").unwrap().zoom, 1.0);
+
+        assert_eq!(registry.set_zoom("legislation", 1.25), Ok(1.25));
+        assert_eq!(registry.set_zoom("map", 0.1), Ok(MIN_ZOOM));
+        assert!(registry.set_zoom("missing", 1.0).is_err());
+
+        let restored = round_trip(&registry);
+        let zoom_of = |label: &str| restored.windows.iter().find(|w| w.label == label).unwrap().zoom;
+        assert_eq!((zoom_of("legislation"), zoom_of("map")), (1.25, MIN_ZOOM));
+
+        // Layouts saved before zoom existed load at 100%
+        let mut old = serde_json::to_value(&restored.windows[0]).unwrap();
+        old.as_object_mut().unwrap().remove("zoom");
+        assert_eq!(serde_json::from_value::<WindowState>(old).unwrap().zoom, 1.0);
+    }
+
+    #[test]
+    fn bgrx_rows_are_packed_into_opaque_rgba() {
+        // 2x2 image with a 4-byte pad at the end of each row
+        let pixels = [
+            1, 2, 3, 0, 4, 5, 6, 0, 9, 9, 9, 9,
+            7, 8, 9, 0, 10, 11, 12, 0, 9, 9, 9, 9,
+        ];
+        let rgba = bgrx_to_rgba(&pixels, 2, 2, 12).unwrap();
+        assert_eq!(rgba, vec![3, 2, 1, 255, 6, 5, 4, 255, 9, 8, 7, 255, 12, 11, 10, 255]);
+
+        assert!(bgrx_to_rgba(&pixels[..19], 2, 2, 12).is_err());
+    }
+
+    #[test]
+    fn encoded_capture_is_a_png() {
+        let png_bytes = encode_png(1, 1, &[255, 0, 0, 255]).unwrap();
+        assert_eq!(&png_bytes[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn background_color_is_parsed_and_validated() {
+        assert_eq!(parse_color("#1e1e1e"), Ok(tauri::window::Color(0x1e, 0x1e, 0x1e, 0xff)));
+        assert_eq!(parse_color("#10203040"), Ok(tauri::window::Color(0x10, 0x20, 0x30, 0x40)));
+        for invalid in ["1e1e1e", "#1e1e", "#1e1e1e1", "#gggggg", "#1e1e1é"] {
+            assert!(parse_color(invalid).is_err(), "{}", invalid);
+        }
+
+        let config = WindowConfig { background_color: Some("navy".to_string()), ..test_config("news") };
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "background_color");
+
+        let dark = WindowConfig { background_color: Some("#101010".to_string()), ..test_config("news") };
+        assert!(dark.validate().is_ok());
+        assert_eq!(dark.starts_hidden(), !WEBVIEW_BACKGROUND_SUPPORTED);
+    }
+
+    #[test]
+    fn background_color_survives_a_save_round_trip() {
+        let config = WindowConfig { background_color: Some("#101010ff".to_string()), ..test_config("news") };
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: WindowConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.background_color.as_deref(), Some("#101010ff"));
+    }
+
+    #[test]
+    fn opacity_is_clamped_and_saved_with_the_layout() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("chart".to_string(), test_config("chart"));
+        assert_eq!(registry.set_opacity("chart", 0.6), Ok(0.6));
+        assert_eq!(registry.set_opacity("chart", 0.0), Ok(MIN_OPACITY));
+        assert!(registry.set_opacity("missing", 0.5).is_err());
+
+        let restored = round_trip(&registry);
+        assert_eq!(restored.windows[0].config.opacity, MIN_OPACITY);
+
+        let config = WindowConfig { opacity: f64::NAN, ..test_config("chart") };
+        assert_eq!(config.validate().unwrap_err()[0].field, "opacity");
+    }
+
+    #[test]
+    fn layouts_saved_before_opacity_and_decorations_load_opaque_and_decorated() {
+        let old_layout = r#"{
+            "windows": [{
+                "label": "map_1700000000000",
+                "config": {
+                    "window_type": "map",
+                    "title": "Map",
+                    "width": 1024.0,
+                    "height": 768.0,
+                    "x": 40.0,
+                    "y": 40.0,
+                    "resizable": true,
+                    "minimizable": true,
+                    "maximizable": true,
+                    "closable": true,
+                    "always_on_top": false,
+                    "transparent": false,
+                    "focus": true,
+                    "fullscreen": false,
+                    "url": null
+                },
+                "z_order": 1,
+                "is_focused": true,
+                "is_minimized": false,
+                "is_maximized": false,
+                "monitor_id": null,
+                "created_at": 1700000000000,
+                "last_focused_at": 1700000000000
+            }],
+            "snap_gap": 0
+        }"#;
+        let restored = parse_saved_layout(old_layout).unwrap();
+        let config = &restored.windows[0].config;
+        assert_eq!(config.opacity, 1.0);
+        assert!(config.decorations);
+    }
+
+    #[test]
+    fn saved_windows_missing_fields_still_load() {
+        let layout = r#"{
+            "windows": [
+                { "label": "map", "config": { "window_type": "map", "title": "Map" } },
+                { "label": "news", "config": { "window_type": "news", "width": 640.0 }, "is_minimized": true },
+                { "label": "broken", "config": { "window_type": "news", "width": "wide" } },
+                { "config": { "window_type": "polling" } }
+            ]
+        }"#;
+        let restored = parse_saved_layout(layout).unwrap();
+        assert_eq!(labels(restored.windows.iter().collect()), vec!["map", "news"]);
+
+        let map = &restored.windows[0];
+        assert_eq!((map.config.width, map.config.height), (800.0, 600.0));
+        assert!(map.config.resizable && map.config.closable && map.config.decorations);
+        assert!(map.is_visible && !map.is_minimized);
+        assert_eq!(map.z_order, 0);
+        assert!(restored.windows[1].is_minimized);
+
+        let entries: Vec<serde_json::Value> = serde_json::from_str::<serde_json::Value>(layout).unwrap()["windows"]
+            .as_array().unwrap().clone();
+        let (_, dropped) = decode_saved_windows(entries);
+        assert_eq!(dropped.len(), 2);
+        assert!(dropped[0].starts_with("'broken'"));
+        assert!(dropped[1].starts_with("#3"));
+    }
+
+    #[test]
+    fn windows_open_relative_to_the_requested_monitor() {
+        let primary = monitor("primary", 0, 0, 1920, 1080);
+        let retina = MonitorInfo { scale_factor: 2.0, ..monitor("retina", 1920, 0, 3840, 2160) };
+        let monitors = vec![primary, retina];
+
+        // Offsets are in the monitor's own logical pixels
+        assert_eq!(
+            monitor_relative_position("retina", Some(100.0), Some(50.0), 800.0, 600.0, &monitors),
+            Some((1060.0, 50.0))
+        );
+        assert_eq!(
+            monitor_relative_position("primary", Some(100.0), Some(50.0), 800.0, 600.0, &monitors),
+            Some((100.0, 50.0))
+        );
+        // 800x600 logical is 1600x1200 physical on the 2x monitor
+        assert_eq!(
+            monitor_relative_position("retina", None, None, 800.0, 600.0, &monitors),
+            Some((1520.0, 240.0))
+        );
+
+        // A disconnected monitor falls back to the primary one, with a warning
+        assert_eq!(
+            monitor_relative_position("projector", None, None, 800.0, 600.0, &monitors),
+            Some((560.0, 240.0))
+        );
+        assert!(missing_monitor_warning("projector", &monitors).is_some());
+        assert!(missing_monitor_warning("retina", &monitors).is_none());
+        assert_eq!(monitor_relative_position("retina", None, None, 800.0, 600.0, &[]), None);
+    }
+
+    #[test]
+    fn groups_are_listed_and_raised_together() {
+        let mut registry = WindowRegistry::new();
+        for label in ["treaty", "map", "embassy", "news"] {
+            registry.add_window(label.to_string(), test_config(label));
+        }
+        registry.assign_to_group("treaty", Some("diplomacy".to_string())).unwrap();
+        registry.assign_to_group("embassy", Some("diplomacy".to_string())).unwrap();
+        assert!(registry.assign_to_group("missing", Some("diplomacy".to_string())).is_err());
+        assert_eq!(labels(registry.get_group_windows("diplomacy")), vec!["embassy", "treaty"]);
+
+        // Raised above map and news without swapping places with each other
+        assert_eq!(registry.raise_group("diplomacy"), vec!["treaty", "embassy"]);
+        assert_eq!(labels(registry.get_windows_by_z_order()), vec!["embassy", "treaty", "news", "map"]);
+        assert!(registry.raise_group("trade").is_empty());
+
+        registry.assign_to_group("treaty", None).unwrap();
+        assert_eq!(labels(registry.get_group_windows("diplomacy")), vec!["embassy"]);
+
+        let restored = round_trip(&registry);
+        let embassy = restored.windows.iter().find(|w| w.label == "embassy").unwrap();
+        assert_eq!(embassy.group_id.as_deref(), Some("diplomacy"));
+    }
+
+    #[test]
+    fn partial_config_merges_every_field() {
+        // Every field differs from the default, so a field the merge drops shows up here
+        let full = WindowConfig {
+            window_type: "treaty".to_string(),
+            title: "Treaty".to_string(),
+            width: 640.0,
+            height: 480.0,
+            x: Some(10.0),
+            y: Some(20.0),
+            resizable: false,
+            minimizable: false,
+            maximizable: false,
+            closable: false,
+            always_on_top: true,
+            decorations: false,
+            transparent: true,
+            focus: false,
+            fullscreen: true,
+            url: Some("treaty.html".to_string()),
+            min_width: Some(300.0),
+            min_height: Some(200.0),
+            max_width: Some(1000.0),
+            max_height: Some(900.0),
+            placement: WindowPlacement::Cascade,
+            allow_offscreen: true,
+            on_type_limit: TypeLimitBehavior::FocusOldest,
+            theme: Some("dark".to_string()),
+            skip_taskbar: true,
+            switcher_visible: false,
+            visible: false,
+            create_and_reveal_when_ready: true,
+            modal: true,
+            parent_label: Some("map".to_string()),
+            zoom: Some(1.5),
+            background_color: Some("#101010".to_string()),
+            opacity: 0.5,
+            monitor_id: Some("right".to_string()),
+            managed: false,
+            use_full_area: true,
+            size_mode: SizeMode::Percent,
+        };
+        let full_json = serde_json::to_value(&full).unwrap();
+        let default_json = serde_json::to_value(WindowConfig::default()).unwrap();
+        for (field, value) in full_json.as_object().unwrap() {
+            assert_ne!(&default_json[field], value, "{} should differ from the default", field);
+        }
+
+        let partial: PartialWindowConfig = serde_json::from_value(full_json.clone()).unwrap();
+        let merged = partial.merge_over(&WindowConfig::default());
+        assert_eq!(serde_json::to_value(&merged).unwrap(), full_json);
+
+        // Nothing given inherits everything
+        let inherited = PartialWindowConfig::default().merge_over(&full);
+        assert_eq!(serde_json::to_value(&inherited).unwrap(), full_json);
+    }
+
+    #[test]
+    fn explicit_null_clears_an_inherited_field() {
+        let template = WindowConfig {
+            x: Some(10.0),
+            theme: Some("dark".to_string()),
+            always_on_top: true,
+            width: 1024.0,
+            ..test_config("polling")
+        };
+        let partial: PartialWindowConfig = serde_json::from_str(r#"{
+            "x": null,
+            "theme": null,
+            "always_on_top": null,
+            "width": null
+        }"#).unwrap();
+        assert_eq!(partial.x, Patch::Clear);
+        assert_eq!(partial.y, Patch::Inherit);
+
+        let merged = partial.merge_over(&template);
+        assert_eq!((merged.x, merged.theme), (None, None));
+        assert!(!merged.always_on_top);
+        assert_eq!(merged.width, WindowConfig::default().width);
+        // Keys that weren't given still come from the template
+        assert_eq!(merged.window_type, "polling");
+        assert_eq!(merged.y, template.y);
+    }
+
+    #[test]
+    fn unmanaged_windows_stay_out_of_tracking_until_adopted() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.add_unmanaged("splash".to_string(), WindowConfig { managed: false, ..test_config("splash") });
+
+        assert_eq!(labels(registry.get_windows_by_z_order()), vec!["map"]);
+        assert_eq!(registry.get_focused_window().map(|w| w.label.as_str()), Some("map"));
+        assert_eq!(labels(registry.unmanaged_windows()), vec!["splash"]);
+        assert!(matches!(registry.check_label("splash"), Err(WindowError::LabelTaken { .. })));
+
+        // A sync doesn't mistake it for a window we've never seen
+        let report = registry.sync_with_live_windows(&["map".to_string(), "splash".to_string()]);
+        assert!(report.added.is_empty());
+        assert!(registry.is_unmanaged("splash"));
+
+        registry.adopt_window("splash").unwrap();
+        assert!(registry.unmanaged_windows().is_empty());
+        assert!(registry.get_window("splash").unwrap().config.managed);
+        assert!(registry.adopt_window("splash").is_err());
+
+        registry.add_unmanaged("ghost".to_string(), WindowConfig { managed: false, ..test_config("drag") });
+        assert!(!registry.remove_window("ghost"));
+        assert!(!registry.is_unmanaged("ghost"));
+    }
+
+    #[test]
+    fn raising_a_group_keeps_its_internal_order() {
+        let mut registry = WindowRegistry::new();
+        for label in ["cabinet", "map", "budget", "news", "polls"] {
+            registry.add_window(label.to_string(), test_config(label));
+        }
+        for label in ["cabinet", "budget", "polls"] {
+            registry.assign_to_group(label, Some("economy".to_string())).unwrap();
+        }
+        set_times(&mut registry, "budget", 0, 500);
+        set_times(&mut registry, "cabinet", 0, 100);
+        set_times(&mut registry, "polls", 0, 300);
+        registry.lower_window("polls").unwrap();
+        assert_eq!(labels(registry.get_windows_by_z_order()), vec!["news", "budget", "map", "cabinet", "polls"]);
+
+        assert_eq!(registry.raise_group("economy"), vec!["polls", "cabinet", "budget"]);
+        assert_eq!(labels(registry.get_windows_by_z_order()), vec!["budget", "cabinet", "polls", "news", "map"]);
+        let z_orders: Vec<u32> = registry.get_group_windows("economy").iter().map(|w| w.z_order).collect();
+        assert!(z_orders.windows(2).all(|pair| pair[0] > pair[1]));
+
+        // Focus returns to the member used last, which here is already on top of the block
+        assert_eq!(registry.group_focus_target("economy").as_deref(), Some("budget"));
+        registry.focus_window("budget");
+        assert_eq!(labels(registry.get_windows_by_z_order())[..3], ["budget", "cabinet", "polls"]);
+        assert_eq!(registry.group_focus_target("diplomacy"), None);
+    }
+
+    #[test]
+    fn hidden_windows_are_skipped_by_cycling_and_tiling() {
+        let mut registry = WindowRegistry::new();
+        for label in ["a", "b", "c"] {
+            registry.add_window(label.to_string(), test_config("map"));
+        }
+        registry.set_window_visible("b", false);
+
+        assert_eq!(registry.cycle_step(CycleMode::ZOrder, true, false, None, 1_000).as_deref(), Some("a"));
+        registry.end_cycle(1_000);
+        assert_eq!(registry.cycle_step(CycleMode::ZOrder, true, true, None, 2_000).as_deref(), Some("b"));
+        assert_eq!(labels(registry.get_visible_windows_by_z_order()), vec!["c", "a"]);
+    }
+
+    #[test]
+    fn constraints_and_resizability_change_at_runtime() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("briefing".to_string(), test_config("briefing"));
+
+        registry.set_constraints("briefing", Some(640.0), None, None, Some(900.0)).unwrap();
+        let config = &registry.get_window("briefing").unwrap().config;
+        assert_eq!((config.min_width, config.min_height, config.max_width, config.max_height), (Some(640.0), None, None, Some(900.0)));
+        assert_eq!(config.clamp_size(500.0, 1200.0), (640.0, 900.0));
+
+        match registry.set_constraints("briefing", Some(800.0), None, Some(600.0), None) {
+            Err(WindowError::InvalidConfig { errors }) => assert_eq!(errors[0].field, "min_width"),
+            other => panic!("expected an invalid config, got {:?}", other),
+        }
+        assert!(registry.set_constraints("briefing", Some(-1.0), None, None, None).is_err());
+        assert_eq!(registry.get_window("briefing").unwrap().config.min_width, Some(640.0));
+
+        // Clearing every bound frees the window again
+        registry.set_constraints("briefing", None, None, None, None).unwrap();
+        assert_eq!(registry.get_window("briefing").unwrap().config.clamp_size(100.0, 5000.0), (100.0, 5000.0));
+
+        registry.set_resizable("briefing", false).unwrap();
+        assert!(!registry.get_window("briefing").unwrap().config.resizable);
+        assert!(registry.set_resizable("missing", true).is_err());
+    }
+
+    #[test]
+    fn skip_taskbar_survives_a_layout_round_trip() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("minimap".to_string(), test_config("minimap"));
+        registry.set_skip_taskbar("minimap", true).unwrap();
+
+        let restored = round_trip(&registry);
+        // load_window_state hands this config to the builder, which applies the flag
+        assert!(restored.windows[0].config.skip_taskbar);
+    }
+
+    #[test]
+    fn monitor_ids_are_stable_and_a_primary_is_always_chosen() {
+        let id = monitor_id("Generic PnP Monitor", 1920, 0, 1920, 1080);
+        assert_eq!(id, monitor_id("Generic PnP Monitor", 1920, 0, 1920, 1080));
+        assert!(id.starts_with("mon-") && id.len() == 20, "{}", id);
+        // Identical models side by side, or one whose resolution changed, are different displays
+        assert_ne!(id, monitor_id("Generic PnP Monitor", 0, 0, 1920, 1080));
+        assert_ne!(id, monitor_id("Generic PnP Monitor", 1920, 0, 2560, 1440));
+
+        let mut monitors = vec![
+            MonitorInfo { is_primary: false, ..monitor("left", -1920, 0, 1920, 1080) },
+            MonitorInfo { is_primary: false, ..monitor("right", 0, 0, 2560, 1440) },
+        ];
+        ensure_primary(&mut monitors);
+        assert!(monitors[0].is_primary && !monitors[1].is_primary);
+
+        monitors[0].is_primary = false;
+        monitors[1].is_primary = true;
+        ensure_primary(&mut monitors);
+        assert!(!monitors[0].is_primary && monitors[1].is_primary);
+    }
+
+    #[test]
+    fn click_through_survives_a_layout_round_trip() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("hud".to_string(), WindowConfig { always_on_top: true, ..test_config("overlay") });
+        registry.set_click_through("hud", true).unwrap();
+        assert!(registry.get_window("hud").unwrap().click_through);
+        assert!(registry.set_click_through("missing", true).is_err());
+
+        let restored = round_trip(&registry);
+        assert!(restored.windows[0].click_through);
+
+        // Layouts saved before the flag existed restore as ordinary windows
+        let mut old = serde_json::to_value(&restored.windows[0]).unwrap();
+        old.as_object_mut().unwrap().remove("click_through");
+        let (windows, dropped) = decode_saved_windows(vec![old]);
+        assert!(dropped.is_empty() && !windows[0].click_through);
+    }
+
+    #[test]
+    fn monitor_changes_are_diffed_and_displaced_windows_flagged() {
+        let laptop = monitor("laptop", 0, 0, 1920, 1080);
+        let dock = monitor("dock", 1920, 0, 2560, 1440);
+        let docked = vec![laptop.clone(), dock.clone()];
+
+        let undocked = MonitorChanges::between(&docked, std::slice::from_ref(&laptop));
+        assert_eq!(undocked.removed, vec![dock.clone()]);
+        assert!(undocked.added.is_empty() && undocked.changed.is_empty());
+        assert!(MonitorChanges::between(&docked, &docked).is_empty());
+
+        let rescaled = MonitorInfo { scale_factor: 2.0, ..laptop.clone() };
+        let changes = MonitorChanges::between(&docked, &[rescaled.clone(), dock.clone()]);
+        assert_eq!(changes.changed, vec![rescaled]);
+        assert!(changes.added.is_empty() && changes.removed.is_empty());
+
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.add_window("news".to_string(), test_config("news"));
+        registry.update_window_position("map", 2200, 100);
+        registry.update_window_size("map", 800, 600);
+        registry.update_window_position("news", 100, 100);
+        registry.update_window_size("news", 800, 600);
+        for label in ["map", "news"] {
+            registry.refresh_monitor_assignment(label, &docked);
+        }
+
+        assert_eq!(registry.apply_monitor_changes(&undocked), vec!["map".to_string()]);
+        let map = registry.get_window("map").unwrap();
+        assert_eq!((map.lost_monitor_id.as_deref(), map.monitor_id.as_deref()), (Some("dock"), Some("laptop")));
+        assert_eq!(registry.get_window("news").unwrap().lost_monitor_id, None);
+
+        // Docking again clears the flag
+        registry.apply_monitor_changes(&MonitorChanges::between(&[laptop], &docked));
+        assert_eq!(registry.get_window("map").unwrap().lost_monitor_id, None);
+    }
+
+    #[test]
+    fn a_failed_window_open_leaves_the_registry_untouched() {
+        let left = monitor("left", 0, 0, 1920, 1080);
+        let mut registry = WindowRegistry::new();
+        registry.add_window("email_1".to_string(), test_config("email"));
+        next_cascade_spawn(&mut registry, "email", 800, 600, &left);
+
+        // Rejected before anything is claimed
+        assert!(matches!(
+            resolve_window_url("https://[::1/inbox", &["localhost".to_string()]),
+            Err(WindowError::InvalidUrl { .. })
+        ));
+
+        // Planning a cascaded spawn whose build then fails records nothing
+        let planned = registry.cascade_spawn_for("email", 800, 600, &left);
+        assert_eq!((planned.x, planned.y), (64, 64));
+        assert_eq!(registry.cascade_spawn_for("email", 800, 600, &left), planned);
+        assert_eq!(labels(registry.get_windows_by_z_order()), vec!["email_1"]);
+        assert_eq!(registry.unique_label("email", 2), "email_2");
+
+        let error = WindowError::BuildFailed {
+            label: "email_2".to_string(),
+            window_type: "email".to_string(),
+            url: "inbox.html".to_string(),
+            message: "webview creation failed".to_string(),
+        };
+        assert_eq!(error.to_string(), "Couldn't open 'email' window 'email_2' at 'inbox.html': webview creation failed");
+    }
+
+    #[test]
+    fn snapping_and_centering_stay_inside_the_work_area() {
+        // A 40px taskbar along the bottom of the primary monitor
+        let primary = MonitorInfo { work_height: 1040, ..monitor("primary", 0, 0, 1920, 1080) };
+
+        let work = primary.usable(false);
+        assert_eq!((work.x, work.y, work.width, work.height), (0, 0, 1920, 1040));
+        assert_eq!(work.id, "primary");
+        assert_eq!(primary.usable(true), primary);
+
+        assert_eq!(compute_snap_rect(&SnapPosition::Bottom, &work, 0), Some((0, 520, 1920, 520)));
+        assert_eq!(compute_snap_rect(&SnapPosition::Bottom, &primary.usable(true), 0), Some((0, 540, 1920, 540)));
+        assert_eq!(centered_position(800.0, 600.0, &work), (560, 220));
+
+        // A title bar hidden behind the taskbar is pulled back above it
+        let monitors = usable_monitors(std::slice::from_ref(&primary), false);
+        let (_, y, _, _) = clamp_rect_to_visible(100.0, 1045.0, 800.0, 600.0, &monitors);
+        assert!(y as f64 + TITLE_BAR_HEIGHT <= 1040.0, "title bar at {} is under the taskbar", y);
+        assert_eq!(clamp_rect_to_visible(100.0, 1000.0, 800.0, 600.0, std::slice::from_ref(&primary)).1, 1000);
+    }
+
+    #[test]
+    fn windows_move_between_monitors_proportionally() {
+        let laptop = MonitorInfo { work_height: 1040, ..monitor("laptop", 0, 0, 1920, 1080) };
+        let retina = MonitorInfo { scale_factor: 2.0, ..monitor("retina", 1920, 0, 3840, 2160) };
+
+        // A quarter of the way across and down the laptop's work area
+        assert_eq!(map_to_monitor(480.0, 260.0, 800.0, 600.0, &laptop, &retina), (2880, 540));
+        // Hard against the right edge stays inside the target
+        assert_eq!(map_to_monitor(1800.0, 0.0, 1600.0, 1200.0, &laptop, &retina), (4160, 0));
+
+        // Logical size survives the scale change, but never outgrows the work area
+        assert_eq!(size_on_monitor(800.0, 600.0, &laptop, &retina), (1600, 1200));
+        assert_eq!(size_on_monitor(1600.0, 1200.0, &retina, &laptop), (800, 600));
+        assert_eq!(size_on_monitor(3000.0, 2000.0, &retina, &laptop), (1500, 1000));
+    }
+
+    #[test]
+    fn adjacent_monitors_follow_the_layout() {
+        let monitors = vec![
+            monitor("left", -1920, 0, 1920, 1080),
+            monitor("primary", 0, 0, 1920, 1080),
+            monitor("right", 1920, 0, 1920, 1080),
+            monitor("above", 0, -1080, 1920, 1080),
+        ];
+        let find = |id: &str| monitors.iter().find(|m| m.id == id).unwrap();
+        let adjacent = |id: &str, direction| adjacent_monitor(find(id), &monitors, direction).map(|m| m.id.as_str());
+
+        assert_eq!(adjacent("primary", MonitorDirection::Left), Some("left"));
+        assert_eq!(adjacent("primary", MonitorDirection::Right), Some("right"));
+        assert_eq!(adjacent("primary", MonitorDirection::Up), Some("above"));
+        assert_eq!(adjacent("primary", MonitorDirection::Down), None);
+        assert_eq!(adjacent("left", MonitorDirection::Up), Some("above"));
+
+        // Left to right, then top to bottom, wrapping
+        assert_eq!(adjacent("left", MonitorDirection::Next), Some("above"));
+        assert_eq!(adjacent("above", MonitorDirection::Next), Some("primary"));
+        assert_eq!(adjacent("right", MonitorDirection::Next), Some("left"));
+        assert_eq!(adjacent("left", MonitorDirection::Previous), Some("right"));
+
+        let single = vec![monitor("primary", 0, 0, 1920, 1080)];
+        assert!(adjacent_monitor(&single[0], &single, MonitorDirection::Next).is_none());
+    }
+
+    #[test]
+    fn logical_coordinates_round_trip_at_every_scale() {
+        for scale in [1.0, 1.25, 2.0] {
+            let primary = monitor("primary", 0, 0, 1920, 1080);
+            let side = MonitorInfo { scale_factor: scale, ..monitor("side", 1920, 0, 2400, 1350) };
+            let monitors = vec![primary, side.clone()];
+
+            // 100 logical px into the side monitor, 800x600 logical
+            let physical = logical_rect_to_physical((2020.0, 100.0, 800.0, 600.0), None, &monitors);
+            assert_eq!(physical, (
+                1920 + (100.0 * scale).round() as i32,
+                (100.0 * scale).round() as i32,
+                (800.0 * scale).round() as u32,
+                (600.0 * scale).round() as u32,
+            ), "scale {}", scale);
+            assert_eq!(physical_rect_to_logical(physical, &monitors), (2020.0, 100.0, 800.0, 600.0), "scale {}", scale);
+
+            // Save/load cycles don't drift, even from positions that aren't whole logical pixels
+            let mut rect = (2333, 417, 1001, 777);
+            for _ in 0..5 {
+                let logical = physical_rect_to_logical(rect, &monitors);
+                let again = logical_rect_to_physical(logical, Some("side"), &monitors);
+                assert_eq!(again, rect, "scale {}", scale);
+                rect = again;
             }
+
+            // The registry keeps both forms in step
+            let mut registry = WindowRegistry::new();
+            registry.add_window("map".to_string(), test_config("map"));
+            registry.update_window_position("map", physical.0, physical.1);
+            registry.update_window_size("map", physical.2, physical.3);
+            registry.refresh_monitor_assignment("map", &monitors);
+            let map = registry.get_window("map").unwrap();
+            assert_eq!((map.scale_factor, map.logical_bounds), (scale, Some((2020.0, 100.0, 800.0, 600.0))));
         }
     }
 
-    Ok(restored_labels)
-}
+    #[test]
+    fn restored_windows_keep_their_logical_size_on_a_new_scale() {
+        let saved_on = monitor("primary", 0, 0, 1920, 1080);
+        let restored_on = MonitorInfo { scale_factor: 1.5, ..monitor("primary", 0, 0, 2880, 1620) };
+        let state = WindowPositionSize {
+            x: 100.0,
+            y: 100.0,
+            width: 800.0,
+            height: 600.0,
+            is_maximized: false,
+            is_minimized: false,
+            is_fullscreen: false,
+            monitor: 0,
+            monitor_name: Some("primary".to_string()),
+            monitor_id: None,
+        };
 
-fn main() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_shell::init())
-        .manage(WindowRegistryState::new(WindowRegistry::new()))
-        .invoke_handler(tauri::generate_handler![
-            greet,
-            create_window,
-            close_window,
-            resize_window,
-            create_app_window,
-            close_app_window,
-            focus_app_window,
-            minimize_window,
-            maximize_window,
-            unmaximize_window,
-            resize_app_window,
-            move_window,
-            get_window_list,
-            get_focused_window,
-            get_monitors,
-            get_monitor_info,
-            get_all_window_states,
-            set_window_state,
-            cycle_windows,
-            snap_window,
-            save_window_state,
-            load_window_state
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
\ No newline at end of file
+        let config = test_config("map");
+        let plan = plan_window_state(&state, &config, std::slice::from_ref(&saved_on), false).unwrap();
+        assert_eq!(plan.rect, (100, 100, 800, 600));
+        let plan = plan_window_state(&state, &config, std::slice::from_ref(&restored_on), false).unwrap();
+        assert_eq!(plan, WindowStatePlan { rect: (150, 150, 1200, 900), warnings: vec![] });
+    }
+
+    #[test]
+    fn restores_reuse_matching_windows_once_each() {
+        let mut registry = WindowRegistry::new();
+        let inbox = |window_type: &str| WindowConfig { url: Some("inbox.html".to_string()), ..test_config(window_type) };
+        registry.add_window("email_1".to_string(), inbox("email"));
+        registry.add_window("email_2".to_string(), inbox("email"));
+        registry.add_window("news_1".to_string(), test_config("news"));
+        set_times(&mut registry, "email_1", 1, 1);
+        set_times(&mut registry, "email_2", 2, 2);
+
+        assert_eq!(registry.reusable_window("email", Some("inbox.html"), &[]).as_deref(), Some("email_1"));
+        let claimed = vec!["email_1".to_string()];
+        assert_eq!(registry.reusable_window("email", Some("inbox.html"), &claimed).as_deref(), Some("email_2"));
+        let claimed = vec!["email_1".to_string(), "email_2".to_string()];
+        assert_eq!(registry.reusable_window("email", Some("inbox.html"), &claimed), None);
+
+        // Type and url must both match; no url only matches no url
+        assert_eq!(registry.reusable_window("email", Some("drafts.html"), &[]), None);
+        assert_eq!(registry.reusable_window("news", None, &[]).as_deref(), Some("news_1"));
+        assert_eq!(registry.reusable_window("news", Some("inbox.html"), &[]), None);
+    }
+
+    #[test]
+    fn focus_is_restored_to_the_saved_window() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.add_window("news".to_string(), test_config("news"));
+        registry.focus_window("map");
+        // Pinned above "map" so the fallback has a clear topmost window
+        registry.windows.get_mut("news").unwrap().z_order = 100;
+
+        let loaded = round_trip(&registry);
+        assert_eq!(loaded.focused_label.as_deref(), Some("map"));
+
+        // "map" came back under a fresh label because its old one was taken
+        let restored: Vec<(String, String, u32)> = loaded.windows.iter()
+            .map(|w| (w.label.clone(), if w.label == "map" { "map_2".to_string() } else { w.label.clone() }, w.z_order))
+            .collect();
+        assert_eq!(restore_focus_target(loaded.focused_label.as_deref(), &restored).as_deref(), Some("map_2"));
+
+        // Without it, the window that was on top gets focus
+        let without_map: Vec<(String, String, u32)> = restored.iter().filter(|(saved, _, _)| saved != "map").cloned().collect();
+        assert_eq!(restore_focus_target(Some("map"), &without_map).as_deref(), Some("news"));
+        assert_eq!(restore_focus_target(None, &restored).as_deref(), Some("news"));
+        assert_eq!(restore_focus_target(Some("map"), &[]), None);
+    }
+
+    #[test]
+    fn saved_monitors_are_matched_by_id_then_name_then_place() {
+        let left = MonitorInfo { name: "Generic PnP Monitor".to_string(), ..monitor("left", 0, 0, 1920, 1080) };
+        let right = MonitorInfo { name: "Generic PnP Monitor".to_string(), ..monitor("right", 1920, 0, 1920, 1080) };
+        let laptop = MonitorInfo { name: "Built-in Retina Display".to_string(), ..monitor("laptop", 0, 0, 1920, 1080) };
+        let saved = vec![left.clone(), right.clone()];
+        let resolve = |id: &str, now: &[MonitorInfo]| resolve_saved_monitor(id, &saved, now).map(|m| m.id.clone());
+
+        assert_eq!(resolve("right", &saved).as_deref(), Some("right"));
+
+        // The right-hand display came back with a new id: the second from the left stands in
+        let moved = MonitorInfo { id: "right-again".to_string(), ..right.clone() };
+        assert_eq!(resolve("right", &[left.clone(), moved]).as_deref(), Some("right-again"));
+
+        // Old layouts stored a name, sometimes with the position tacked on; only a unique name counts
+        let now = vec![laptop.clone(), MonitorInfo { id: "dell".to_string(), name: "DELL U2720Q".to_string(), ..right.clone() }];
+        assert_eq!(resolve_saved_monitor("DELL U2720Q@1920,0", &[], &now).map(|m| m.id.as_str()), Some("dell"));
+        assert_eq!(resolve_saved_monitor("Generic PnP Monitor", &[], &saved).map(|m| m.id.as_str()), Some("left"));
+
+        // Undocked: nothing in second place, so the primary
+        assert_eq!(resolve("right", std::slice::from_ref(&laptop)).as_deref(), Some("laptop"));
+
+        // Relocating keeps the window's spot relative to the display and its logical size
+        let retina = MonitorInfo { id: "retina".to_string(), scale_factor: 2.0, ..monitor("retina", 1920, 0, 3840, 2160) };
+        let mut config = WindowConfig { x: Some(2400.0), y: Some(270.0), width: 800.0, height: 600.0, ..test_config("map") };
+        assert_eq!(relocate_saved_window(&mut config, "right", &saved, &[left.clone(), retina]).as_deref(), Some("retina"));
+        assert_eq!((config.x, config.y, config.width, config.height), (Some(2400.0), Some(270.0), 800.0, 600.0));
+
+        let mut config = WindowConfig { x: Some(2400.0), y: Some(270.0), width: 800.0, height: 600.0, ..test_config("map") };
+        assert_eq!(relocate_saved_window(&mut config, "right", &saved, std::slice::from_ref(&laptop)).as_deref(), Some("laptop"));
+        assert_eq!((config.x, config.y), (Some(480.0), Some(270.0)));
+    }
+
+    #[test]
+    fn minimizing_the_focused_window_hands_focus_to_the_top_window() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("shell".to_string(), test_config("shell"));
+        registry.add_window("inbox".to_string(), test_config("inbox"));
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.set_window_visible("inbox", false);
+        registry.focus_window("map");
+
+        // inbox is hidden, so shell is the next window that can hold focus
+        registry.update_window_state("map", Some(true), None, None);
+        assert_eq!(registry.get_focused_window().map(|w| w.label.as_str()), Some("shell"));
+        assert!(registry.get_window("shell").unwrap().is_focused);
+        assert!(!registry.get_window("map").unwrap().is_focused);
+
+        // Minimizing a window that isn't focused leaves focus alone
+        registry.update_window_state("map", Some(false), None, None);
+        registry.update_window_state("map", Some(true), None, None);
+        assert_eq!(registry.get_focused_window().map(|w| w.label.as_str()), Some("shell"));
+
+        // The titlebar button reaches us as native flags
+        registry.apply_native_flags("shell", NativeWindowFlags { minimized: true, maximized: false, fullscreen: false });
+        assert!(registry.get_focused_window().is_none());
+        assert_eq!(registry.stats(0).focused_window, None);
+    }
+
+    #[test]
+    fn focused_window_falls_back_when_it_is_no_longer_shown() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("shell".to_string(), test_config("shell"));
+        registry.add_window("map".to_string(), test_config("map"));
+
+        registry.begin_show_desktop();
+        assert!(registry.get_focused_window().is_none());
+
+        registry.end_show_desktop();
+        registry.windows.get_mut("map").unwrap().is_visible = false;
+        assert_eq!(registry.get_focused_window().map(|w| w.label.as_str()), Some("shell"));
+    }
+
+    #[test]
+    fn windows_on_a_disconnected_monitor_are_rehomed_and_restored() {
+        let mut laptop = monitor("laptop", 0, 0, 1920, 1080);
+        laptop.work_height = 1040;
+        let dock = monitor("dock", 1920, 0, 2560, 1440);
+        let docked = vec![laptop.clone(), dock.clone()];
+
+        let mut registry = WindowRegistry::new();
+        for (label, x) in [("map", 2000), ("news", 2400), ("shell", 100)] {
+            registry.add_window(label.to_string(), test_config(label));
+            registry.update_window_position(label, x, 100);
+            registry.update_window_size(label, 800, 1200);
+            registry.refresh_monitor_assignment(label, &docked);
+        }
+
+        let undocked = MonitorChanges::between(&docked, std::slice::from_ref(&laptop));
+        let displaced = registry.apply_monitor_changes(&undocked);
+        let moves = registry.rehome_displaced(&displaced, &undocked);
+        // Cascaded from the work area's corner, bottom of the stack first, and shrunk to fit it
+        assert_eq!(moves, vec![
+            ("map".to_string(), (0, 0, 800, 1010)),
+            ("news".to_string(), (30, 30, 800, 1010)),
+        ]);
+        let map = registry.get_window("map").unwrap();
+        assert_eq!(map.displaced_from, Some(DisplacedFrom { monitor_id: "dock".to_string(), bounds: (2000, 100, 800, 1200) }));
+        assert_eq!((map.current_x, map.monitor_id.as_deref()), (Some(0), Some("laptop")));
+        assert_eq!(registry.get_window("shell").unwrap().displaced_from, None);
+
+        // Nothing to restore until the dock is back
+        assert!(registry.restore_displaced(std::slice::from_ref(&laptop)).is_empty());
+        registry.apply_monitor_changes(&MonitorChanges::between(std::slice::from_ref(&laptop), &docked));
+        assert_eq!(registry.restorable_windows(&docked), vec!["map".to_string(), "news".to_string()]);
+
+        let moves = registry.restore_displaced(&docked);
+        assert_eq!(moves.len(), 2);
+        let map = registry.get_window("map").unwrap();
+        assert_eq!((map.current_x, map.current_height, map.monitor_id.as_deref()), (Some(2000), Some(1200), Some("dock")));
+        assert_eq!(map.displaced_from, None);
+        assert!(registry.restorable_windows(&docked).is_empty());
+    }
+
+    #[test]
+    fn metadata_is_kept_per_window_and_survives_a_layout_round_trip() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("dashboard".to_string(), test_config("dashboard"));
+        registry.set_metadata("dashboard", "entity", serde_json::json!({ "party": "greens", "seats": 12 })).unwrap();
+        registry.set_metadata("dashboard", "pinned", serde_json::json!(true)).unwrap();
+        assert_eq!(
+            registry.get_metadata("dashboard", "entity").unwrap(),
+            Some(serde_json::json!({ "party": "greens", "seats": 12 }))
+        );
+        assert_eq!(registry.get_metadata("dashboard", "missing").unwrap(), None);
+        assert!(registry.set_metadata("missing", "entity", serde_json::json!(1)).is_err());
+        assert!(registry.get_metadata("missing", "entity").is_err());
+
+        // null clears a key
+        registry.set_metadata("dashboard", "pinned", serde_json::Value::Null).unwrap();
+        assert_eq!(registry.get_metadata("dashboard", "pinned").unwrap(), None);
+
+        let restored = round_trip(&registry);
+        assert_eq!(restored.windows[0].metadata, registry.get_window("dashboard").unwrap().metadata);
+
+        let mut old = serde_json::to_value(&restored.windows[0]).unwrap();
+        old.as_object_mut().unwrap().remove("metadata");
+        let (windows, dropped) = decode_saved_windows(vec![old]);
+        assert!(dropped.is_empty() && windows[0].metadata.is_empty());
+    }
+
+    #[test]
+    fn monitor_fingerprint_identifies_the_setup_in_any_order() {
+        let laptop = monitor("laptop", 0, 0, 1920, 1080);
+        let left = monitor("left", -2560, 0, 2560, 1440);
+        let right = monitor("right", 1920, 0, 2560, 1440);
+
+        let docked = monitor_fingerprint(&[laptop.clone(), left.clone(), right.clone()]);
+        assert_eq!(docked, monitor_fingerprint(&[right.clone(), laptop.clone(), left.clone()]));
+        assert!(docked.starts_with("cfg-") && validate_profile_name(&docked).is_ok());
+        assert_ne!(docked, monitor_fingerprint(std::slice::from_ref(&laptop)));
+
+        // Same displays, rearranged
+        let swapped = MonitorInfo { x: -2560, ..right.clone() };
+        assert_ne!(docked, monitor_fingerprint(&[laptop.clone(), MonitorInfo { x: 1920, ..left }, swapped]));
+        // A scale change alone doesn't make it a different setup
+        let rescaled = MonitorInfo { scale_factor: 2.0, ..laptop.clone() };
+        assert_eq!(monitor_fingerprint(&[laptop]), monitor_fingerprint(&[rescaled]));
+    }
+
+    #[test]
+    fn scale_changes_rederive_logical_bounds_and_are_reported_once() {
+        let mut hidpi = monitor("4k", 0, 0, 3840, 2160);
+        hidpi.scale_factor = 2.0;
+        let monitors = vec![hidpi, monitor("1080p", 3840, 0, 1920, 1080)];
+
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.update_window_position("map", 3000, 200);
+        registry.update_window_size("map", 1600, 1200);
+        registry.refresh_monitor_assignment("map", &monitors);
+        assert_eq!(registry.get_window("map").unwrap().scale_factor, 2.0);
+
+        // Dragged mostly onto the 1080p display; the OS has already resized it to 800x600
+        registry.update_window_position("map", 3700, 200);
+        let change = registry.observe_scale_change("map", 800, 600, 1.0, &monitors).unwrap();
+        assert_eq!(change, WindowScaleChange {
+            label: "map".to_string(),
+            old_scale_factor: 2.0,
+            new_scale_factor: 1.0,
+        });
+        let map = registry.get_window("map").unwrap();
+        assert_eq!((map.scale_factor, map.monitor_id.as_deref()), (1.0, Some("1080p")));
+        assert_eq!(map.logical_bounds, Some((3700.0, 200.0, 800.0, 600.0)));
+
+        // Reported by the OS while mostly still on the 4k display: its factor is trusted
+        registry.update_window_position("map", 3200, 200);
+        registry.observe_scale_change("map", 800, 600, 1.0, &monitors);
+        let map = registry.get_window("map").unwrap();
+        assert_eq!((map.scale_factor, map.logical_bounds), (1.0, Some((3200.0, 200.0, 800.0, 600.0))));
+
+        assert_eq!(registry.observe_scale_change("map", 800, 600, 1.0, &monitors), None);
+        assert_eq!(registry.observe_scale_change("missing", 800, 600, 1.0, &monitors), None);
+    }
+
+    #[test]
+    fn window_presence_flags_registry_and_tauri_disagreeing() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.add_unmanaged("devtools".to_string(), test_config("devtools"));
+
+        assert_eq!(registry.presence("map", true), WindowPresence::Present);
+        assert_eq!(registry.presence("devtools", true), WindowPresence::Present);
+        assert_eq!(registry.presence("missing", false), WindowPresence::Absent);
+        // Closed natively without the registry hearing about it
+        assert_eq!(registry.presence("map", false), WindowPresence::RegistryOnly);
+        assert_eq!(registry.presence("stray", true), WindowPresence::LiveOnly);
+
+        registry.remove_window("map");
+        assert_eq!(registry.presence("map", false), WindowPresence::Absent);
+    }
+
+    #[test]
+    fn spanning_windows_are_flagged_and_belong_to_the_majority_monitor() {
+        let monitors = vec![monitor("left", 0, 0, 1920, 1080), monitor("right", 1920, 0, 1920, 1080)];
+
+        // 25% / 75%
+        let coverage = monitor_coverage(1720.0, 100.0, 800.0, 600.0, &monitors);
+        assert_eq!(coverage.iter().map(|c| (c.monitor_id.as_str(), c.percent)).collect::<Vec<_>>(),
+            vec![("left", 25.0), ("right", 75.0)]);
+        assert_eq!(monitor_for_rect(1720.0, 100.0, 800.0, 600.0, &monitors).unwrap().id, "right");
+
+        // An exact 50/50 split goes to the monitor listed first, whichever way round they're enumerated
+        let coverage = monitor_coverage(1520.0, 100.0, 800.0, 600.0, &monitors);
+        assert_eq!((coverage[0].percent, coverage[1].percent), (50.0, 50.0));
+        assert_eq!(monitor_for_rect(1520.0, 100.0, 800.0, 600.0, &monitors).unwrap().id, "left");
+        let reversed = vec![monitors[1].clone(), monitors[0].clone()];
+        assert_eq!(monitor_for_rect(1520.0, 100.0, 800.0, 600.0, &reversed).unwrap().id, "right");
+
+        // Partly off every screen
+        let coverage = monitor_coverage(-400.0, 100.0, 800.0, 600.0, &monitors);
+        assert_eq!((coverage[0].percent, coverage[1].percent), (50.0, 0.0));
+
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        registry.update_window_position("map", 1720, 100);
+        registry.update_window_size("map", 800, 600);
+        registry.refresh_monitor_assignment("map", &monitors);
+        let map = registry.get_window("map").unwrap();
+        assert!(map.spanning);
+        assert_eq!(map.monitor_id.as_deref(), Some("right"));
+
+        // Snapping uses the majority monitor only
+        let target = monitor_for_rect(1720.0, 100.0, 800.0, 600.0, &monitors).unwrap();
+        assert_eq!(compute_snap_rect(&SnapPosition::Left, target, 0), Some((1920, 0, 960, 1080)));
+
+        registry.update_window_position("map", 2000, 100);
+        registry.refresh_monitor_assignment("map", &monitors);
+        assert!(!registry.get_window("map").unwrap().spanning);
+    }
+
+    #[test]
+    fn monitor_cache_swaps_snapshots_without_disturbing_readers() {
+        let cache = MonitorCacheState::new(None);
+        let laptop = monitor("laptop", 0, 0, 1920, 1080);
+        let dock = monitor("dock", 1920, 0, 2560, 1440);
+
+        assert!(store_monitor_snapshot(&cache, vec![laptop.clone()], 1_000).is_none());
+        let reader = cache.lock().unwrap().clone().unwrap();
+        assert_eq!(reader.age_ms(1_250), 250);
+
+        let changes = store_monitor_snapshot(&cache, vec![laptop.clone(), dock.clone()], 2_000).unwrap();
+        assert_eq!(changes.added, vec![dock]);
+        // The earlier reader still sees the list it was handed
+        assert_eq!(*reader.monitors, vec![laptop.clone()]);
+        let current = cache.lock().unwrap().clone().unwrap();
+        assert_eq!((current.monitors.len(), current.age_ms(2_000)), (2, 0));
+
+        // Refreshing an unchanged setup only renews the timestamp
+        let same = store_monitor_snapshot(&cache, current.monitors.to_vec(), 3_000).unwrap();
+        assert!(same.is_empty());
+        assert_eq!(cache.lock().unwrap().as_ref().unwrap().taken_at, 3_000);
+    }
+
+    #[test]
+    fn percent_size_resolves_against_the_target_monitor() {
+        let mut scaled = monitor("4k", 1920, 0, 3840, 2160);
+        scaled.scale_factor = 2.0;
+        scaled.work_height = 2080;
+        let monitors = vec![monitor("main", 0, 0, 1920, 1080), scaled];
+        let config = WindowConfig {
+            width: 50.0,
+            height: 50.0,
+            size_mode: SizeMode::Percent,
+            placement: WindowPlacement::CenterOnMonitor("4k".to_string()),
+            ..test_config("briefing")
+        };
+
+        let target = size_target_monitor(&config, None, None, &monitors);
+        assert_eq!(target.map(|m| m.id.as_str()), Some("4k"));
+        // Half of the 1920x1040 logical work area
+        assert_eq!(config.resolve_size(target), (960.0, 520.0));
+        let full = WindowConfig { use_full_area: true, ..config.clone() };
+        assert_eq!(full.resolve_size(target), (960.0, 540.0));
+
+        // Absolute placement with no monitor_id sizes against the primary
+        let primary = WindowConfig { placement: WindowPlacement::Absolute, ..config.clone() };
+        let target = size_target_monitor(&primary, Some("4k"), None, &monitors);
+        assert_eq!(primary.resolve_size(target), (960.0, 540.0));
+        let focused = WindowConfig { placement: WindowPlacement::CenterOnFocusedMonitor, ..config.clone() };
+        let target = size_target_monitor(&focused, Some("4k"), None, &monitors);
+        assert_eq!(target.map(|m| m.id.as_str()), Some("4k"));
+
+        // No monitors at all falls back to the default size
+        assert_eq!(config.resolve_size(None), (800.0, 600.0));
+        // Pixels are left alone
+        let pixels = WindowConfig { size_mode: SizeMode::Pixels, ..config };
+        assert_eq!(pixels.resolve_size(target), (50.0, 50.0));
+    }
+
+    #[test]
+    fn percent_sizes_are_validated_as_percentages() {
+        let config = WindowConfig {
+            width: 2000.0,
+            height: 100.0,
+            size_mode: SizeMode::Percent,
+            ..test_config("briefing")
+        };
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "width");
+        assert!(WindowConfig { width: 100.0, ..config }.validate().is_ok());
+    }
+
+    #[test]
+    fn avoid_overlap_steps_off_occupied_spots() {
+        let primary = monitor("primary", 0, 0, 1920, 1080);
+        // Nothing open: centered
+        assert_eq!(avoid_overlap_position(800, 600, &[], &primary, 32), (560, 240));
+
+        // Windows elsewhere don't get in the way
+        let elsewhere = [(0, 0, 800, 600), (1100, 400, 800, 600)];
+        assert_eq!(avoid_overlap_position(800, 600, &elsewhere, &primary, 32), (560, 240));
+
+        // The center and first step are taken, so the second step is used
+        let stacked = [(560, 240, 800, 600), (592, 272, 800, 600)];
+        assert_eq!(avoid_overlap_position(800, 600, &stacked, &primary, 32), (624, 304));
+
+        // Down-right runs out of room, so it steps up-left instead
+        let tall = monitor("tall", -1280, 0, 1280, 1024);
+        let taken = [(-1140, 62, 1000, 900), (-1108, 94, 1000, 900)];
+        assert_eq!(avoid_overlap_position(1000, 900, &taken, &tall, 32), (-1172, 30));
+
+        // A window filling the monitor can only go in one place
+        let full = [(0, 0, 1920, 1080)];
+        assert_eq!(avoid_overlap_position(1920, 1080, &full, &primary, 32), (0, 0));
+    }
+
+    #[test]
+    fn avoid_overlap_only_considers_the_same_type() {
+        let primary = monitor("primary", 0, 0, 1920, 1080);
+        let mut registry = WindowRegistry::new();
+        for (label, window_type) in [("news_1", "news"), ("map_1", "map")] {
+            registry.add_window(label.to_string(), test_config(window_type));
+            let window = registry.windows.get_mut(label).unwrap();
+            window.current_x = Some(560);
+            window.current_y = Some(240);
+            window.current_width = Some(800);
+            window.current_height = Some(600);
+        }
+
+        assert_eq!(registry.spawn_avoiding_overlap("news", 800, 600, &primary), (592, 272));
+        assert_eq!(registry.spawn_avoiding_overlap("briefing", 800, 600, &primary), (560, 240));
+    }
+
+    #[test]
+    fn default_placement_applies_only_to_unpositioned_configs() {
+        let default = WindowPlacement::SmartAvoidOverlap;
+        let config = test_config("news");
+        assert_eq!(config.placement_or(&default), WindowPlacement::SmartAvoidOverlap);
+
+        let positioned = WindowConfig { x: Some(10.0), y: Some(20.0), ..config.clone() };
+        assert_eq!(positioned.placement_or(&default), WindowPlacement::Absolute);
+        let on_monitor = WindowConfig { monitor_id: Some("right".to_string()), ..config.clone() };
+        assert_eq!(on_monitor.placement_or(&default), WindowPlacement::Absolute);
+        let centered = WindowConfig { placement: WindowPlacement::CenterPrimary, ..config };
+        assert_eq!(centered.placement_or(&default), WindowPlacement::CenterPrimary);
+    }
+
+    // A Percent config as create_app_window stores it: resolved once, then plain pixels
+    fn opened_percent_config() -> WindowConfig {
+        let mut config = WindowConfig {
+            width: 50.0,
+            height: 50.0,
+            size_mode: SizeMode::Percent,
+            ..test_config("briefing")
+        };
+        let (width, height) = config.resolve_size(Some(&monitor("main", 0, 0, 1920, 1080)));
+        config.set_pixel_size(width, height);
+        config
+    }
+
+    #[test]
+    fn percent_windows_accept_pixel_window_states() {
+        let monitors = vec![monitor("main", 0, 0, 1920, 1080)];
+        let opened = opened_percent_config();
+        assert_eq!((opened.width, opened.height, opened.size_mode), (960.0, 540.0, SizeMode::Pixels));
+
+        let state = WindowPositionSize {
+            x: 100.0,
+            y: 100.0,
+            width: 1200.0,
+            height: 800.0,
+            is_maximized: false,
+            is_minimized: false,
+            is_fullscreen: false,
+            monitor: 0,
+            monitor_name: None,
+            monitor_id: None,
+        };
+        assert!(plan_window_state(&state, &opened, &monitors, false).is_ok());
+        // Even a config still marked Percent (e.g. from an older layout) takes pixel sizes
+        let legacy = WindowConfig { size_mode: SizeMode::Percent, ..opened };
+        assert!(plan_window_state(&state, &legacy, &monitors, false).is_ok());
+    }
+
+    #[test]
+    fn duplicating_a_percent_window_copies_its_pixel_size() {
+        let source = WindowConfig { size_mode: SizeMode::Percent, ..opened_percent_config() };
+        let copy = duplicate_config(source, Some((100.0, 50.0)), Some((1200.0, 700.0)));
+        assert_eq!((copy.x, copy.y), (Some(130.0), Some(80.0)));
+        assert_eq!((copy.width, copy.height, copy.size_mode), (1200.0, 700.0, SizeMode::Pixels));
+        assert!(copy.validate().is_ok());
+    }
+
+    #[test]
+    fn percent_windows_survive_a_save_and_load() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("briefing".to_string(), opened_percent_config());
+        let window = registry.windows.get_mut("briefing").unwrap();
+        window.logical_bounds = Some((40.0, 30.0, 1500.0, 900.0));
+        // A layout saved before the size was fixed in pixels
+        registry.add_window("legacy".to_string(), WindowConfig { size_mode: SizeMode::Percent, ..test_config("legacy") });
+        registry.windows.get_mut("legacy").unwrap().logical_bounds = Some((0.0, 0.0, 1024.0, 768.0));
+
+        let restored = round_trip(&registry);
+        for mut window_state in restored.windows {
+            apply_saved_geometry(&mut window_state);
+            assert_eq!(window_state.config.size_mode, SizeMode::Pixels, "{}", window_state.label);
+            assert!(window_state.config.validate().is_ok(), "{}", window_state.label);
+        }
+    }
+
+    #[test]
+    fn live_placement_records_bounds_and_monitor() {
+        let mut registry = WindowRegistry::new();
+        registry.add_window("map".to_string(), test_config("map"));
+        let placement = LivePlacement {
+            bounds: Some((2000, 100, 800, 600)),
+            monitors: Some(vec![monitor("primary", 0, 0, 1920, 1080), monitor("right", 1920, 0, 1920, 1080)]),
+        };
+        record_live_placement(&mut registry, "map", &placement);
+
+        let window = registry.get_window("map").unwrap();
+        assert_eq!(
+            (window.current_x, window.current_y, window.current_width, window.current_height),
+            (Some(2000), Some(100), Some(800), Some(600))
+        );
+        assert_eq!(window.monitor_id.as_deref(), Some("right"));
+
+        // Nothing could be read: nothing is overwritten
+        record_live_placement(&mut registry, "map", &LivePlacement { bounds: None, monitors: None });
+        assert_eq!(registry.get_window("map").unwrap().current_x, Some(2000));
+    }
+}
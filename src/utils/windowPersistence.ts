@@ -109,7 +109,7 @@ class WindowPersistenceStorage {
    */
   async getMonitorInfo(): Promise<MonitorInfo[]> {
     try {
-      const monitors = await invoke<MonitorInfo[]>('get_monitor_info');
+      const { monitors } = await invoke<{ monitors: MonitorInfo[]; cache_age_ms: number }>('get_monitor_info');
       return monitors;
     } catch (error) {
       console.warn('Failed to get monitor info:', error);